@@ -0,0 +1,25 @@
+// 把 git 提交和构建日期写进二进制，--version 与 hello 会带上，便于确认用户跑的是哪个构建
+use std::process::Command;
+
+fn main() {
+    let run = |cmd: &str, args: &[&str]| {
+        Command::new(cmd)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    println!(
+        "cargo:rustc-env=MEM_CLEANER_GIT_HASH={}",
+        run("git", &["rev-parse", "--short=10", "HEAD"])
+    );
+    println!(
+        "cargo:rustc-env=MEM_CLEANER_BUILD_DATE={}",
+        run("date", &["-u", "+%Y-%m-%d"])
+    );
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
@@ -0,0 +1,41 @@
+/* mem_cleaner 嵌入接口：链接 libmem_cleaner.so */
+#ifndef MEM_CLEANER_H
+#define MEM_CLEANER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct MemCleaner MemCleaner;
+
+typedef struct {
+    uint64_t kills;         /* 该句柄累计清理进程数 */
+    uint64_t freed_kb;      /* 该句柄累计释放内存 */
+    uint64_t last_freed_kb; /* 上一轮释放的内存 */
+    uint64_t uptime_secs;
+} MemCleanerStats;
+
+/* 按配置文件初始化；log_path 可为 NULL（沿用配置中的 log_path）。失败或 panic 返回 NULL */
+MemCleaner *mem_cleaner_init(const char *config_path, const char *log_path);
+
+/* 立即执行一轮清理，返回清理的进程数；handle 为 NULL 或本轮 panic 时返回 -1 */
+int mem_cleaner_run_cycle(MemCleaner *handle);
+
+/* 成功返回 0，参数为 NULL 时返回 -1 */
+int mem_cleaner_stats(const MemCleaner *handle, MemCleanerStats *out);
+
+/* 执行 "<what> [schema=N]" 查询（what 为 stats/config/table），JSON 写入 buf。
+ * 与 snprintf 相同：返回完整结果长度，>= len 表示被截断；参数为 NULL、查询无效或查询中 panic 时返回 -1 */
+int mem_cleaner_query(MemCleaner *handle, const char *query, char *buf, size_t len);
+
+/* 写入该句柄的运行统计并释放引擎；handle 可为 NULL */
+void mem_cleaner_shutdown(MemCleaner *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,102 @@
+//! 进程位数：App 由 app_process32 或 app_process64 孵化，读 /proc/<pid>/exe 的 ELF 头即可区分。
+//! 部分机型上 32 位的老游戏是内存大户，可以整类优先清理或整类豁免
+
+use nix::fcntl::{openat, OFlag};
+use nix::sys::stat::Mode;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::path::Path;
+
+use crate::memory;
+
+const ELFCLASS32: u8 = 1;
+
+/// 32 位进程的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Abi32Policy {
+    Normal, // 与 64 位一视同仁
+    Prefer, // 同一轮中优先清理 32 位进程
+    Exempt, // 从不清理 32 位进程
+}
+
+impl Abi32Policy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "normal" => Some(Self::Normal),
+            "prefer" => Some(Self::Prefer),
+            "exempt" => Some(Self::Exempt),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Prefer => "prefer",
+            Self::Exempt => "exempt",
+        }
+    }
+}
+
+/// exe_path 为相对 proc_fd 的 "<pid>/exe"；读不到（内核线程、权限不足）时返回 None
+pub fn is_32bit(proc_fd: RawFd, exe_path: &str) -> Option<bool> {
+    let fd = openat(
+        Some(proc_fd),
+        Path::new(exe_path),
+        OFlag::O_RDONLY | OFlag::O_CLOEXEC,
+        Mode::empty(),
+    )
+    .ok()?;
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let mut ident = [0u8; 5];
+    file.read_exact(&mut ident).ok()?;
+    (&ident[..4] == b"\x7fELF").then_some(ident[4] == ELFCLASS32)
+}
+
+/// 按位数统计的 App 进程数和内存
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AbiCensus {
+    pub procs_32: u32,
+    pub procs_64: u32,
+    pub rss_32_kb: u64,
+    pub rss_64_kb: u64,
+}
+
+/// 扫描所有 App UID（>= 10000）进程，供 status 展示
+pub fn census(proc_fd: RawFd) -> AbiCensus {
+    let mut census = AbiCensus::default();
+    let Ok(dir) = fs::read_dir("/proc") else {
+        return census;
+    };
+    for entry in dir.flatten() {
+        let name = entry.file_name();
+        let Some(pid) = name
+            .to_str()
+            .filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+        else {
+            continue;
+        };
+        if entry.metadata().map_or(true, |m| m.uid() < 10000) {
+            continue;
+        }
+        let Ok(status) = fs::read(entry.path().join("status")) else {
+            continue;
+        };
+        let rss_kb = memory::status_field_kb(&status, "VmRSS").unwrap_or(0);
+        match is_32bit(proc_fd, &format!("{}/exe", pid)) {
+            Some(true) => {
+                census.procs_32 += 1;
+                census.rss_32_kb += rss_kb;
+            }
+            Some(false) => {
+                census.procs_64 += 1;
+                census.rss_64_kb += rss_kb;
+            }
+            None => {}
+        }
+    }
+    census
+}
@@ -0,0 +1,88 @@
+//! 自适应清理周期：平稳时逐步拉长唤醒间隔省电，内存下滑或进程成批出现时立即缩到最短
+//!
+//! 每次定时器到期喂一次采样，返回下一次应设的周期；一切在 [min_interval, max_interval] 内。
+
+use crate::config::AppConfig;
+
+const AVAIL_DROP_KB: u64 = 64 * 1024; // 两次采样间 MemAvailable 下降这么多视为内存在下滑
+const PROCESS_BURST: usize = 8; // 两次采样间新增这么多进程视为正在大量启动
+
+/// 跨周期的采样与当前周期
+#[derive(Debug, Default)]
+pub struct AdaptiveInterval {
+    current: u64, // 0 表示尚未开始，沿用基础周期
+    last_avail_kb: Option<u64>,
+    last_processes: usize,
+}
+
+impl AdaptiveInterval {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 基础周期变了（熄屏切换、配置热加载）：从基础周期重新开始
+    pub fn reset(&mut self) {
+        self.current = 0;
+    }
+
+    /// 喂入本次采样，返回下一次的周期（秒）；base 为熄屏、schedule 等套用后的基础周期
+    pub fn next(
+        &mut self,
+        config: &AppConfig,
+        base: u64,
+        screen_off: bool,
+        avail_kb: Option<u64>,
+        processes: usize,
+    ) -> u64 {
+        let (min, max) = (config.min_interval.min(base), config.max_interval.max(base));
+        let current = if self.current == 0 {
+            base
+        } else {
+            self.current
+        };
+
+        let dropping = matches!(
+            (self.last_avail_kb, avail_kb),
+            (Some(last), Some(now)) if last.saturating_sub(now) >= AVAIL_DROP_KB
+        );
+        let burst = self.last_processes > 0 && processes >= self.last_processes + PROCESS_BURST;
+        let rising =
+            matches!((self.last_avail_kb, avail_kb), (Some(last), Some(now)) if now >= last);
+
+        self.current = if dropping || burst {
+            min
+        } else if screen_off || rising {
+            // 平稳：每次翻倍，直到上限
+            (current * 2).min(max)
+        } else {
+            // 亮屏且内存缓慢下降：回到基础周期
+            current.clamp(min, base)
+        };
+        self.last_avail_kb = avail_kb;
+        self.last_processes = processes;
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+
+    #[test]
+    fn lengthens_when_calm_and_shortens_on_pressure() {
+        let config = parse_config("interval: 30\nmin_interval: 5\nmax_interval: 120\n");
+        let mut adaptive = AdaptiveInterval::new();
+        let mb = |n: u64| Some(n * 1024);
+        assert_eq!(adaptive.next(&config, 30, false, mb(2000), 400), 30);
+        assert_eq!(adaptive.next(&config, 30, false, mb(2000), 400), 60);
+        assert_eq!(adaptive.next(&config, 30, false, mb(2010), 401), 120);
+        assert_eq!(adaptive.next(&config, 30, false, mb(2020), 401), 120);
+
+        // MemAvailable 掉了 100MB
+        assert_eq!(adaptive.next(&config, 30, false, mb(1920), 401), 5);
+        // 大量新进程
+        assert_eq!(adaptive.next(&config, 30, false, mb(1920), 401), 10);
+        assert_eq!(adaptive.next(&config, 30, false, mb(1930), 420), 5);
+    }
+}
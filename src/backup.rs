@@ -0,0 +1,284 @@
+//! `state export` / `state import`：把配置和运行中学到的状态打包成一个文本文件，
+//! 刷机或换模块后导入即可继续使用
+//!
+//! 时间一律存为相对值（多久之前、还剩多久），重启后 boottime 从零开始也能正确还原。
+//!
+//! ```text
+//! # mem_cleaner state 1
+//! [config]
+//! interval: 60
+//! ...
+//! [packages]        包名 击杀次数 距上次击杀秒数 oom 累计 释放累计(kB)
+//! [relaunches]      包名 被用户重新打开次数
+//! [cooldowns]       包名 剩余冷却秒数
+//! [backoff]         进程名 快速重生次数 剩余退避秒数
+//! ```
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::engine::CleanerState;
+use crate::history::PackageHistory;
+
+pub const DEFAULT_STATE_PATH: &str = "/sdcard/mem_cleaner.state";
+const STATE_VERSION: u32 = 1;
+const HEADER: &str = "# mem_cleaner state";
+/// 导出时抹掉取值的键：备份放在谁都能读的 /sdcard，token 泄漏等于交出控制接口与 HTTP API
+const SECRET_KEYS: &[&str] = &["control_token"];
+const REDACTED: &str = "<redacted>";
+
+/// 控制端给出的路径解析为 DEFAULT_STATE_PATH 所在目录下的文件
+///
+/// 守护进程以 root 运行，放开路径等于让每个控制端都能读写任意文件：
+/// 拒绝 `..`、目录之外的绝对路径以及指向别处的符号链接
+pub fn resolve_path(arg: Option<&str>) -> Result<PathBuf, String> {
+    let base = Path::new(DEFAULT_STATE_PATH)
+        .parent()
+        .unwrap_or(Path::new("/"));
+    resolve_in(base, arg.unwrap_or(DEFAULT_STATE_PATH))
+}
+
+fn resolve_in(base: &Path, arg: &str) -> Result<PathBuf, String> {
+    let path = Path::new(arg);
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err("'..' is not allowed in state paths".to_string());
+    }
+    let path = base.join(path);
+    let name = path.file_name().ok_or("missing file name")?;
+    let base = fs::canonicalize(base).map_err(|e| format!("{}: {}", base.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let dir = fs::canonicalize(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    if !dir.starts_with(&base) {
+        return Err(format!("state files must be under {}", base.display()));
+    }
+    let target = dir.join(name);
+    if fs::symlink_metadata(&target).is_ok_and(|m| m.file_type().is_symlink()) {
+        return Err(format!("refusing to follow symlink {}", target.display()));
+    }
+    Ok(target)
+}
+
+/// 导入结果
+pub struct ImportSummary {
+    pub packages: usize,
+    pub cooldowns: usize,
+    pub config: Option<String>, // 备份中的配置文件内容
+}
+
+pub fn export(state: &CleanerState, config_path: &str, now: u64) -> String {
+    let mut out = format!("{} {}\n", HEADER, STATE_VERSION);
+    out.push_str("[config]\n");
+    if let Ok(config) = fs::read_to_string(config_path) {
+        // 配置中的 '[' 开头行会被误认为段名，原样保存时加一个空格
+        for line in config.lines() {
+            if line.starts_with('[') {
+                out.push(' ');
+            }
+            match secret_key(line) {
+                Some((key, '=')) => out.push_str(&format!("{} = \"{}\"", key, REDACTED)),
+                Some((key, _)) => out.push_str(&format!("{}: {}", key, REDACTED)),
+                None => out.push_str(line),
+            }
+            out.push('\n');
+        }
+    }
+
+    out.push_str("[packages]\n");
+    for (package, h) in state.history.iter() {
+        out.push_str(&format!(
+            "{} {} {} {} {}\n",
+            package,
+            h.kills,
+            now.saturating_sub(h.last_kill),
+            h.oom_sum,
+            h.freed_kb_sum
+        ));
+    }
+    out.push_str("[relaunches]\n");
+    for (package, count) in state.relaunch.annoyances() {
+        out.push_str(&format!("{} {}\n", package, count));
+    }
+    out.push_str("[cooldowns]\n");
+    for (package, remaining) in state.relaunch.cooldowns(now) {
+        out.push_str(&format!("{} {}\n", package, remaining));
+    }
+    out.push_str("[backoff]\n");
+    for (name, strikes, remaining) in state.respawn.backoffs(now) {
+        out.push_str(&format!("{} {} {}\n", name, strikes, remaining));
+    }
+    out
+}
+
+/// 行首的键属于 SECRET_KEYS 时返回键名与分隔符（旧格式 ':'，TOML '='）
+fn secret_key(line: &str) -> Option<(&'static str, char)> {
+    let line = line.trim_start();
+    SECRET_KEYS.iter().find_map(|&key| {
+        let sep = line.strip_prefix(key)?.trim_start().chars().next()?;
+        matches!(sep, ':' | '=').then_some((key, sep))
+    })
+}
+
+/// 备份中被抹掉的键换回当前配置里的同名行，当前配置没有时去掉该行
+pub fn restore_secrets(imported: &str, current: &str) -> String {
+    let mut out = String::with_capacity(imported.len());
+    for line in imported.lines() {
+        match secret_key(line) {
+            Some((key, _)) => {
+                if let Some(kept) = current
+                    .lines()
+                    .find(|l| secret_key(l).is_some_and(|(k, _)| k == key))
+                {
+                    out.push_str(kept);
+                    out.push('\n');
+                }
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// 合并备份中的计数与冷却到当前状态；配置内容交给调用方决定是否写回
+pub fn import(text: &str, state: &mut CleanerState, now: u64) -> Result<ImportSummary, String> {
+    let mut lines = text.lines();
+    let version = lines
+        .next()
+        .and_then(|l| l.strip_prefix(HEADER))
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .ok_or("not a mem_cleaner state file")?;
+    if version > STATE_VERSION {
+        return Err(format!(
+            "state version {} is newer than supported {}",
+            version, STATE_VERSION
+        ));
+    }
+
+    let mut summary = ImportSummary {
+        packages: 0,
+        cooldowns: 0,
+        config: None,
+    };
+    let mut config = String::new();
+    let mut section = "";
+    for line in lines {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = match name {
+                "config" | "packages" | "relaunches" | "cooldowns" | "backoff" => name,
+                _ => "",
+            };
+            continue;
+        }
+        if section == "config" {
+            config.push_str(
+                line.strip_prefix(' ')
+                    .filter(|l| l.starts_with('['))
+                    .unwrap_or(line),
+            );
+            config.push('\n');
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let num = |i: usize| fields.get(i).and_then(|v| v.parse::<u64>().ok());
+        match (section, fields.first()) {
+            ("packages", Some(package)) => {
+                let (Some(kills), Some(ago), Some(freed)) = (num(1), num(2), num(4)) else {
+                    continue;
+                };
+                let oom_sum = fields
+                    .get(3)
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .unwrap_or(0);
+                state.history.restore(
+                    package,
+                    PackageHistory {
+                        kills: kills as u32,
+                        last_kill: now.saturating_sub(ago),
+                        oom_sum,
+                        freed_kb_sum: freed,
+                    },
+                );
+                summary.packages += 1;
+            }
+            ("relaunches", Some(package)) => {
+                if let Some(count) = num(1) {
+                    state.relaunch.restore_annoyance(package, count as u32);
+                }
+            }
+            ("cooldowns", Some(package)) => {
+                if let Some(remaining) = num(1) {
+                    state.relaunch.restore_cooldown(package, remaining, now);
+                    summary.cooldowns += 1;
+                }
+            }
+            ("backoff", Some(name)) => {
+                if let (Some(strikes), Some(remaining)) = (num(1), num(2)) {
+                    state
+                        .respawn
+                        .restore_backoff(name, strikes as u32, remaining, now);
+                    summary.cooldowns += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    summary.config = (!config.is_empty()).then_some(config);
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_redacted_on_export_and_kept_on_import() {
+        let root = std::env::temp_dir().join(format!("mc_secret_{}", std::process::id()));
+        fs::write(&root, "interval: 60\ncontrol_token: hunter2\n").unwrap();
+        let config = crate::config::parse_config("");
+        let state = CleanerState::new(&config);
+        let text = export(&state, root.to_str().unwrap(), 0);
+        fs::remove_file(&root).unwrap();
+        assert!(!text.contains("hunter2"));
+        assert!(text.contains("control_token: <redacted>"));
+
+        let mut state = CleanerState::new(&config);
+        let imported = import(&text, &mut state, 0).unwrap().config.unwrap();
+        assert_eq!(
+            restore_secrets(&imported, "control_token: s3cret\n"),
+            "interval: 60\ncontrol_token: s3cret\n"
+        );
+        assert_eq!(restore_secrets(&imported, ""), "interval: 60\n");
+        assert_eq!(
+            restore_secrets(
+                "control_token = \"<redacted>\"\n",
+                "control_token = \"t\"\n"
+            ),
+            "control_token = \"t\"\n"
+        );
+    }
+
+    #[test]
+    fn paths_stay_inside_state_directory() {
+        let root = std::env::temp_dir().join(format!("mc_state_{}", std::process::id()));
+        let base = root.join("sdcard");
+        fs::create_dir_all(base.join("backups")).unwrap();
+        let base_real = fs::canonicalize(&base).unwrap();
+
+        assert_eq!(resolve_in(&base, "a.state"), Ok(base_real.join("a.state")));
+        let nested = base.join("backups/b.state");
+        assert_eq!(
+            resolve_in(&base, nested.to_str().unwrap()),
+            Ok(base_real.join("backups/b.state"))
+        );
+        assert!(resolve_in(&base, "../outside.state").is_err());
+        assert!(resolve_in(&base, "/etc/passwd").is_err());
+
+        std::os::unix::fs::symlink("/etc/passwd", base.join("link.state")).unwrap();
+        assert!(resolve_in(&base, "link.state").is_err());
+        std::os::unix::fs::symlink(&root, base.join("up")).unwrap();
+        assert!(resolve_in(&base, "up/x.state").is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
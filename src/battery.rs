@@ -0,0 +1,60 @@
+use fxhash::FxHashMap;
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+// batterystats 导出很重，半小时刷新一次足够
+const BATTERY_STATS_TTL: Duration = Duration::from_secs(1800);
+
+/// 按 UID 统计的耗电（自上次充满以来，mAh），来自 `dumpsys batterystats --checkin`
+#[derive(Default)]
+pub struct BatteryDrain {
+    per_uid: FxHashMap<u32, f32>,
+    last_refresh: Option<Instant>,
+}
+
+impl BatteryDrain {
+    pub fn new() -> Self {
+        Self {
+            per_uid: FxHashMap::default(),
+            last_refresh: None,
+        }
+    }
+
+    pub fn drain_mah(&mut self, uid: u32) -> f32 {
+        if self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= BATTERY_STATS_TTL)
+        {
+            self.refresh();
+        }
+        self.per_uid.get(&uid).copied().unwrap_or(0.0)
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        let output = match Command::new("dumpsys")
+            .args(["batterystats", "--checkin"])
+            .output()
+        {
+            Ok(o) => o,
+            Err(_) => return,
+        };
+        self.per_uid = parse_checkin(&String::from_utf8_lossy(&output.stdout));
+    }
+}
+
+/// 解析 power use item 行：<ver>,<uid>,l,pwi,uid,<mAh>,...（l 表示自上次充满）
+fn parse_checkin(text: &str) -> FxHashMap<u32, f32> {
+    let mut per_uid = FxHashMap::default();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() < 6 || fields[2] != "l" || fields[3] != "pwi" || fields[4] != "uid" {
+            continue;
+        }
+        if let (Ok(uid), Ok(mah)) = (fields[1].parse::<u32>(), fields[5].parse::<f32>()) {
+            *per_uid.entry(uid).or_insert(0.0) += mah;
+        }
+    }
+    per_uid
+}
@@ -0,0 +1,97 @@
+use fxhash::FxHashSet;
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const BINDINGS_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// 被系统绑定着的 App：设备管理器、通知监听器、勿扰规则提供者、闹钟
+///
+/// 系统持有它们的连接，杀掉后要么立即被重新拉起，要么相关功能（锁屏策略、通知转发、
+/// 勿扰自动规则、闹钟）悄悄失效，表现为整个系统状态异常
+#[derive(Default)]
+pub struct SystemBindings {
+    packages: FxHashSet<String>,
+    last_refresh: Option<Instant>,
+}
+
+impl SystemBindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&mut self, package: &str) -> bool {
+        if self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= BINDINGS_CACHE_TTL)
+        {
+            self.refresh();
+        }
+        self.packages.contains(package)
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        self.packages.clear();
+
+        // 设备管理器：admin=ComponentInfo{pkg/cls}
+        if let Some(text) = run("dumpsys", &["device_policy"]) {
+            for line in text.lines() {
+                if let Some(rest) = line.trim().strip_prefix("admin=ComponentInfo{") {
+                    self.insert_component(rest);
+                }
+            }
+        }
+        // 通知监听器："pkg/cls:pkg2/cls2"
+        if let Some(text) = secure_setting("enabled_notification_listeners") {
+            for component in text.split(':') {
+                self.insert_component(component);
+            }
+        }
+        // 可修改勿扰状态的 App（自动规则提供者）："pkg:pkg2"
+        if let Some(text) = secure_setting("enabled_notification_policy_access_packages") {
+            self.packages.extend(
+                text.split(':')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        // 设置了闹钟的 App：Alarm{... pkg} 之后跟着非空的 alarmClock（新版本为 "Alarm clock:" 段）
+        if let Some(text) = run("dumpsys", &["alarm"]) {
+            let mut owner: Option<&str> = None;
+            for line in text.lines() {
+                let line = line.trim();
+                if line.starts_with("Alarm{")
+                    || line.starts_with("RTC")
+                    || line.starts_with("ELAPSED")
+                {
+                    owner = line.trim_end_matches(['}', ':']).rsplit(' ').next();
+                } else if line == "Alarm clock:"
+                    || (line.starts_with("alarmClock=") && line != "alarmClock=null")
+                {
+                    if let Some(pkg) = owner.filter(|p| p.contains('.')) {
+                        self.packages.insert(pkg.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    fn insert_component(&mut self, component: &str) {
+        if let Some((pkg, _)) = component.split_once('/') {
+            self.packages.insert(pkg.trim().to_string());
+        }
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn secure_setting(key: &str) -> Option<String> {
+    let value = run("settings", &["get", "secure", key])?;
+    let value = value.trim();
+    (!value.is_empty() && value != "null").then(|| value.to_string())
+}
@@ -0,0 +1,53 @@
+use std::io;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::events::{self, Event};
+use crate::history;
+
+pub const ACTION_KILLED: &str = "io.memcleaner.KILLED";
+pub const ACTION_CYCLE: &str = "io.memcleaner.CYCLE";
+
+/// 把清理事件转成 `am broadcast`，供 Tasker/MacroDroid 等自动化工具接收
+///
+/// 在独立线程里订阅事件总线并逐个执行 am，避免拖慢主循环
+pub fn spawn() -> io::Result<()> {
+    let events = events::subscribe();
+    thread::Builder::new()
+        .name("broadcast".to_string())
+        .spawn(move || {
+            for event in events {
+                match event {
+                    Event::Killed {
+                        name, resident_kb, ..
+                    } => am_broadcast(
+                        ACTION_KILLED,
+                        &[
+                            ("--es", "package", history::package_name(&name)),
+                            ("--es", "process", &name),
+                            ("--el", "freed_kb", &resident_kb.to_string()),
+                        ],
+                    ),
+                    Event::CycleComplete { killed, freed_kb } if killed > 0 => am_broadcast(
+                        ACTION_CYCLE,
+                        &[
+                            ("--ei", "killed", &killed.to_string()),
+                            ("--el", "freed_kb", &freed_kb.to_string()),
+                        ],
+                    ),
+                    _ => {}
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// extras: (类型参数, 键, 值)
+fn am_broadcast(action: &str, extras: &[(&str, &str, &str)]) {
+    let mut cmd = Command::new("am");
+    cmd.args(["broadcast", "-a", action]);
+    for (kind, key, value) in extras {
+        cmd.args([*kind, *key, *value]);
+    }
+    let _ = cmd.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
@@ -0,0 +1,173 @@
+//! 守护进程的命令行：`mem_cleaner [run|once|check-config] --config <path> [选项]`
+//!
+//! 仍兼容旧写法 `mem_cleaner <config_path> [log_path]`。客户端命令（status、clean 等）、
+//! doctor、simulate 在 main 中先行分派，不经过这里。
+
+use crate::config::{AppConfig, THRESHOLD_RANGE};
+use crate::logfile::LogLevel;
+
+/// 守护进程的运行方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subcommand {
+    Run,         // 常驻运行（默认）
+    Once,        // 扫描并清理一轮后退出，供脚本调用
+    CheckConfig, // 解析配置并打印生效值，有错误时以非零退出
+}
+
+impl Subcommand {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "run" => Some(Self::Run),
+            "once" => Some(Self::Once),
+            "check-config" => Some(Self::CheckConfig),
+            _ => None,
+        }
+    }
+}
+
+/// 解析后的命令行；覆盖项在配置热加载后重新套用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Subcommand,
+    pub config: String,
+    pub log: Option<String>,
+    pub interval: Option<u64>,
+    pub threshold: Option<i32>,
+    pub dry_run: bool, // 只记录本会清理的进程
+    pub verbose: bool, // 日志级别降到 debug，击杀记录附带完整命令行
+    pub daemon: bool,  // 转入后台运行，脱离控制终端
+}
+
+impl Cli {
+    /// args 不含程序名
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut command = None;
+        let mut config = None;
+        let mut log = None;
+        let mut interval = None;
+        let mut threshold = None;
+        let mut dry_run = false;
+        let mut verbose = false;
+        let mut daemon = false;
+        let mut positional = Vec::new();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            // 同时支持 --flag value 与 --flag=value
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = |name: &str| {
+                inline
+                    .clone()
+                    .or_else(|| iter.next().cloned())
+                    .ok_or_else(|| format!("{} requires a value", name))
+            };
+            match flag {
+                "--config" | "-c" => config = Some(value(flag)?),
+                "--log" | "-l" => log = Some(value(flag)?),
+                "--interval" => {
+                    let v = value(flag)?;
+                    match v.parse::<u64>() {
+                        Ok(secs) if secs > 0 => interval = Some(secs),
+                        _ => return Err(format!("invalid interval: {}", v)),
+                    }
+                }
+                "--threshold" => {
+                    let v = value(flag)?;
+                    match v.parse::<i32>() {
+                        Ok(t) if THRESHOLD_RANGE.contains(&t) => threshold = Some(t),
+                        _ => {
+                            return Err(format!(
+                                "invalid threshold: {} (must be {}..={})",
+                                v,
+                                THRESHOLD_RANGE.start(),
+                                THRESHOLD_RANGE.end()
+                            ))
+                        }
+                    }
+                }
+                "--dry-run" | "-n" => dry_run = true,
+                "--verbose" | "-v" => verbose = true,
+                "--daemon" | "-d" => daemon = true,
+                _ if flag.starts_with('-') && flag.len() > 1 => {
+                    return Err(format!("unknown option: {}", flag));
+                }
+                _ => {
+                    if command.is_none() && positional.is_empty() {
+                        if let Some(sub) = Subcommand::parse(arg) {
+                            command = Some(sub);
+                            continue;
+                        }
+                    }
+                    positional.push(arg.clone());
+                }
+            }
+        }
+
+        // 旧写法：<config_path> [log_path]
+        let mut positional = positional.into_iter();
+        let config = match (config, positional.next()) {
+            (Some(c), None) => c,
+            (None, Some(c)) => c,
+            (Some(_), Some(extra)) => return Err(format!("unexpected argument: {}", extra)),
+            (None, None) => return Err("missing config path".to_string()),
+        };
+        if log.is_none() {
+            log = positional.next();
+        }
+        if let Some(extra) = positional.next() {
+            return Err(format!("unexpected argument: {}", extra));
+        }
+
+        Ok(Self {
+            command: command.unwrap_or(Subcommand::Run),
+            config,
+            log,
+            interval,
+            threshold,
+            dry_run,
+            verbose,
+            daemon,
+        })
+    }
+
+    /// 把命令行覆盖项套到配置上
+    pub fn apply(&self, config: &mut AppConfig) {
+        if let Some(interval) = self.interval {
+            config.interval = interval;
+        }
+        if let Some(threshold) = self.threshold {
+            config.oom_threshold = threshold;
+        }
+        if self.dry_run {
+            config.dry_run = true;
+        }
+        if self.verbose {
+            config.verbose_log = true;
+            config.log_level = LogLevel::Debug;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, String> {
+        Cli::parse(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn threshold_flag_stays_in_killable_range() {
+        assert_eq!(
+            parse(&["-c", "a.conf", "--threshold", "200"])
+                .unwrap()
+                .threshold,
+            Some(200)
+        );
+        assert!(parse(&["-c", "a.conf", "--threshold", "0"]).is_err());
+        assert!(parse(&["-c", "a.conf", "--threshold=-1000"]).is_err());
+    }
+}
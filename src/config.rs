@@ -0,0 +1,2182 @@
+//! 配置文件：`key: value` 逐行解析成 [`AppConfig`]，以及 check-config、status 用的生效值列表
+//!
+//! 未知的键记一条带行号的警告（`line N: unknown setting ...`）后跳过，取值非法的键记一条警告后
+//! 沿用默认值，保证写错一行不会让守护进程起不来，拼错的键也能在 check-config 中看到。
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use fxhash::{FxHashMap, FxHashSet};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::abi::Abi32Policy;
+use crate::control::AccessPolicy;
+use crate::logfile::{LogBackend, LogFormat, LogLevel, DEFAULT_LOG_BACKUPS};
+use crate::namespace::NamespacePolicy;
+use crate::pattern::Regex;
+use crate::power::{BatteryRule, ChargerPolicy};
+use crate::quirks::{QuirkOverrides, Quirks};
+use crate::schedule::ScheduleWindow;
+use crate::score::ScoreWeights;
+use crate::script::{self, PolicyScript};
+use crate::{control, daemonize, device, errlog, events, hook, update, watchdog};
+
+const OOM_SCORE_THRESHOLD: i32 = 800; // 默认阈值：只有大于此值的进程才会被检查
+/// 阈值允许的范围：200 以下是前台、可见进程，阈值再低也不能让它们成为候选
+pub const THRESHOLD_RANGE: std::ops::RangeInclusive<i32> = 200..=1000;
+const SCREEN_OFF_THRESHOLD: i32 = 700; // 熄屏激进模式的默认阈值：连上一个使用的 App 也清理
+const DEFAULT_INTERVAL: u64 = 60;
+const DEFAULT_INPUT_IDLE_SECS: u64 = 600; // 无输入多久视为空闲
+const DEFAULT_RESPAWN_FAST_SECS: u64 = 30; // 被杀后多少秒内重生视为无效击杀
+const DEFAULT_RELAUNCH_WINDOW_SECS: u64 = 180; // 被杀后多久内被用户重新打开算"误杀"
+const DEFAULT_RELAUNCH_SUGGEST_AFTER: u32 = 3;
+const DEFAULT_SWITCH_GRACE_SECS: u64 = 60;
+const DEFAULT_WATCHDOG_INTERVALS: u64 = 5;
+const DEFAULT_SWAP_WEIGHT: f32 = 0.25; // zram 压缩比约 3~4 倍，换出页只按 1/4 计
+const DEFAULT_DEMOTE_ADJ: i32 = 999; // 仅次于 1000，lmkd 最先回收
+const DEFAULT_MIN_INTERVAL: u64 = 5;
+const DEFAULT_MAX_INTERVAL: u64 = 300;
+
+/// 内置白名单，不计入未命中报告
+pub const BUILTIN_WHITELIST: &[&str] = &["com.android.systemui", "android", "com.android.phone"];
+const DEFAULT_WHITELIST_REPORT_DAYS: u64 = 30;
+
+/// 白名单匹配规则：完全匹配、前缀匹配、通配符或正则
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WhitelistRule {
+    Exact(String),                 // 完全匹配
+    Prefix(String),                // 前缀匹配（对应 xxx:* 格式）
+    Glob(String),                  // 其他含 * 或 ? 的规则，如 com.tencent.*:push
+    Regex(Regex),                  // re:<POSIX 扩展正则>，整串匹配
+    Uid(u32),                      // 纯数字或 uid:<n>：按完整 UID 匹配（工作资料、分身应用）
+    User(u32, Box<WhitelistRule>), // user:<n>/<规则>：只在该 Android 用户下生效
+}
+
+impl WhitelistRule {
+    /// 解析单条规则原文；正则编译失败时返回 None
+    pub fn parse(rule: &str) -> Option<Self> {
+        let mut warnings = Vec::new();
+        let parsed = Self::parse_into(rule, &mut warnings);
+        for w in warnings {
+            errlog::warn("config", &w);
+        }
+        parsed
+    }
+
+    fn parse_into(rule: &str, warnings: &mut Vec<String>) -> Option<Self> {
+        let digits = rule.strip_prefix("uid:").unwrap_or(rule);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            return match digits.parse() {
+                Ok(uid) => Some(Self::Uid(uid)),
+                Err(_) => {
+                    warnings.push(format!("Bad whitelist rule {}: UID out of range", rule));
+                    None
+                }
+            };
+        }
+        if let Some((user, inner)) = rule.strip_prefix("user:").and_then(|r| r.split_once('/')) {
+            let Ok(user) = user.parse() else {
+                warnings.push(format!("Bad whitelist rule {}: invalid user", rule));
+                return None;
+            };
+            return Self::parse_into(inner, warnings)
+                .map(|inner| Self::User(user, Box::new(inner)));
+        }
+        if let Some(source) = rule.strip_prefix("re:") {
+            return match Regex::new(source.trim()) {
+                Ok(re) => Some(Self::Regex(re)),
+                Err(e) => {
+                    warnings.push(format!("Bad whitelist rule {}: {}", rule, e));
+                    None
+                }
+            };
+        }
+        match rule.strip_suffix(":*") {
+            Some(prefix) if !prefix.is_empty() && !prefix.contains(['*', '?']) => {
+                Some(Self::Prefix(prefix.to_string()))
+            }
+            _ if rule.contains(['*', '?']) => Some(Self::Glob(rule.to_string())),
+            _ => Some(Self::Exact(rule.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for WhitelistRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(name) => write!(f, "{}", name),
+            Self::Prefix(prefix) => write!(f, "{}:*", prefix),
+            Self::Glob(pattern) => write!(f, "{}", pattern),
+            Self::Regex(re) => write!(f, "re:{}", re.as_str()),
+            Self::Uid(uid) => write!(f, "uid:{}", uid),
+            Self::User(user, inner) => write!(f, "user:{}/{}", user, inner),
+        }
+    }
+}
+
+/// 空闲判定来源：空闲时与 Doze 一样跳过清理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleMode {
+    Doze,  // cmd deviceidle
+    Input, // /dev/input 无事件
+    Any,   // 任一满足即视为空闲
+}
+
+impl IdleMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "doze" => Some(Self::Doze),
+            "input" => Some(Self::Input),
+            "any" => Some(Self::Any),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Doze => "doze",
+            Self::Input => "input",
+            Self::Any => "any",
+        }
+    }
+}
+
+/// Google Play 服务进程族的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GmsPolicy {
+    Protect, // 从不处理（默认）：杀掉会立刻重生，并打断所有 App 的推送
+    Demote,  // 不杀，只调高 oom_score_adj 让内核在真正紧张时优先回收
+    Normal,  // 与普通进程一样
+}
+
+impl GmsPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "protect" => Some(Self::Protect),
+            "demote" => Some(Self::Demote),
+            "normal" => Some(Self::Normal),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Protect => "protect",
+            Self::Demote => "demote",
+            Self::Normal => "normal",
+        }
+    }
+}
+
+/// 多次击杀仍未退出的进程如何升级处理，按配置顺序逐次尝试
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurvivorAction {
+    ThawKill,  // 不论是否检测到冻结，先解冻再杀
+    ForceStop, // am force-stop 整个包
+    Ignore,    // 放弃，此后不再处理该进程
+}
+
+impl SurvivorAction {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "thaw_kill" => Some(Self::ThawKill),
+            "force_stop" => Some(Self::ForceStop),
+            "ignore" => Some(Self::Ignore),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ThawKill => "thaw_kill",
+            Self::ForceStop => "force_stop",
+            Self::Ignore => "ignore",
+        }
+    }
+}
+
+/// 阈值模式比较的指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OomMetric {
+    Adj,   // oom_score_adj（默认）
+    Score, // 内核综合了内存占用的 oom_score；部分 ROM 的 adj 管理混乱时排序更可靠
+}
+
+impl OomMetric {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "adj" => Some(Self::Adj),
+            "oom_score" => Some(Self::Score),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Adj => "adj",
+            Self::Score => "oom_score",
+        }
+    }
+}
+
+/// 选中的进程如何处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanAction {
+    Kill,   // 杀掉，释放内存
+    Freeze, // 冻结（cgroup.freeze 或 SIGSTOP），保留应用状态，回到前台时解冻
+    Demote, // 不杀，只把 oom_score_adj 调到 demote_adj，让内核 lmkd 优先回收
+}
+
+impl CleanAction {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "kill" => Some(Self::Kill),
+            "freeze" => Some(Self::Freeze),
+            "demote" => Some(Self::Demote),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Kill => "kill",
+            Self::Freeze => "freeze",
+            Self::Demote => "demote",
+        }
+    }
+}
+
+/// 选中一个进程后清理的范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillScope {
+    Process, // 只杀选中的进程
+    Package, // 连同同一 UID 下的其他进程一起杀，避免主进程立即把服务拉起
+}
+
+impl KillScope {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "process" => Some(Self::Process),
+            "package" => Some(Self::Package),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Process => "process",
+            Self::Package => "package",
+        }
+    }
+}
+
+/// 已被系统冻结（cached-apps freezer）的进程如何处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrozenAction {
+    Skip,     // 不消耗 CPU，跳过
+    Kill,     // 照常击杀（cgroup v2 下 SIGKILL 可直接生效）
+    ThawKill, // 先解冻再击杀
+}
+
+impl FrozenAction {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "skip" => Some(Self::Skip),
+            "kill" => Some(Self::Kill),
+            "thaw_kill" => Some(Self::ThawKill),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Kill => "kill",
+            Self::ThawKill => "thaw_kill",
+        }
+    }
+}
+
+pub struct AppConfig {
+    pub interval: u64,
+    pub oom_threshold: i32,
+    pub hard_threshold: Option<i32>, // 设置后 threshold 为软阈值：每轮只杀最严重的一个，达到硬阈值的全部杀
+    pub threshold_metric: OomMetric, // threshold/hard_threshold 比较的指标
+    pub whitelist: FxHashSet<WhitelistRule>, // 规则集合
+    pub blacklist: FxHashSet<WhitelistRule>, // 进入缓存状态即清理，不看阈值（规则格式同白名单）
+    pub never_touch_prefixes: Vec<String>, // 进程名以这些前缀开头的一概不碰，黑名单也不例外
+    pub schedule: Vec<ScheduleWindow>, // 按本地时段覆盖周期、阈值或停止清理，先写的优先
+    pub on_charger: ChargerPolicy,   // 接上电源时照常清理还是暂停
+    pub battery_rules: Vec<BatteryRule>, // 电量低于各档时改用的阈值/周期，按电量从低到高排列
+    pub idle_mode: IdleMode,
+    pub input_idle_secs: u64,
+    pub doze_cache_secs: u64, // Doze 状态的缓存时长，过期后在后台重新查询
+    pub frozen_action: FrozenAction,
+    pub action: CleanAction, // 黑名单与 blacklist-once 点名的包始终直接杀
+    pub demote_adj: i32,     // action: demote 时写入的 oom_score_adj
+    pub kill_scope: KillScope,
+    pub dry_run: bool, // 演练：完整扫描筛选，只记录本会清理的进程，不发信号、不改 adj
+    pub gpu_accounting: bool, // 是否把 kgsl/dmabuf 图形内存计入进程占用
+    pub swap_weight: f32, // VmSwap 计入占用时的权重
+    pub min_size_kb: u64, // 占用低于此值的进程不杀，0 表示不限制
+    pub min_rss_kb: u64, // 只看 RSS 的下限，不计 GPU 与 swap，0 表示不限制
+    pub largest_first: bool, // 同一轮内按占用从大到小清理（打分模式下仍按分数）
+    pub min_heap_kb: u64, // Java + native 堆低于此值的进程不杀，0 表示不限制
+    pub kill_delay_ms: u64, // 同一轮中相邻两次击杀的间隔，0 表示不等待
+    pub max_kills_per_cycle: usize, // 每轮最多处理几个（击杀、冻结、降级合计），其余留到下一轮，0 表示不限制
+    pub term_grace_cycles: u64, // 先发 SIGTERM，这么多轮后仍未退出才 SIGKILL，0 表示直接 SIGKILL
+    pub survivor_actions: Vec<SurvivorAction>, // 杀不掉的进程依次尝试的处理，默认直接放弃，空表示一直重试
+    pub dumpsys_meminfo: bool,                 // 击杀前用 dumpsys meminfo 取准确的 PSS 明细（限频）
+    pub min_pss_kb: u64,                       // dumpsys 给出的总 PSS 低于此值的不杀，0 表示不限制
+    pub score: Option<ScoreWeights>,           // 设置后以加权打分代替单一 oom 阈值
+    pub policy_script: Option<PolicyScript>, // 用户 Rhai 策略脚本，可放过或改变内置策略选出的候选的处理方式
+    pub hook: Option<String>,                // 每轮清理后调用的程序或写入的 FIFO
+    pub hook_timeout_ms: u64,                // 钩子程序超过此时长即被杀掉
+    pub kill_cooldown_secs: u64,             // 同一个包两次击杀的最短间隔，0 表示不限制
+    pub storm_kills_per_hour: u32,           // 一小时内被杀超过这么多次就暂时放过它，0 表示不检测
+    pub respawn_fast_secs: u64,
+    pub comfort_kb: u64, // MemAvailable 高于此值时整轮跳过，0 表示不跳过
+    pub mem_trigger: Option<MemTrigger>,
+    pub target_available_kb: u64, // 目标模式：保持至少这么多可用内存，按大小挑最少的进程
+    pub control_socket: Option<String>, // 默认关闭，on 表示 control::DEFAULT_SOCKET_PATH
+    pub log_path: Option<String>, // 命令行参数优先
+    pub verbose_log: bool,        // 击杀日志中附带完整 argv
+    pub exclude_uids: Vec<(u32, u32)>, // 不监控的 UID 闭区间
+    pub system_targets: FxHashSet<WhitelistRule>, // 系统 UID 下仍要监控的包（白名单同款规则）
+    pub gms_policy: GmsPolicy,
+    pub fsync_logs: bool,
+    pub log_backend: LogBackend, // 清理日志写文件、logcat 或两者
+    pub log_level: LogLevel,
+    pub log_format: LogFormat,
+    pub log_max_kb: u64,      // 清理日志超过此大小后轮转，0 表示沿用按天清空
+    pub log_backups: u32,     // 轮转时保留的旧文件数（<path>.1 … <path>.N）
+    pub recent_events: usize, // 内存中保留的最近事件条数
+    pub trigger_file: Option<String>, // touch 该文件即立即清理一轮
+    pub broadcast: bool,      // 清理后发送 io.memcleaner.* 广播
+    pub http_api: Option<String>, // 本机 HTTP 接口监听地址，仅限回环
+    pub access: AccessPolicy, // 控制 socket 与 HTTP 接口共用的鉴权配置
+    pub protect_widgets: bool, // 放过在当前桌面上有小部件的 App
+    pub protect_critical: bool, // 放过默认桌面、输入法、短信、拨号与无障碍服务
+    pub protect_media: bool,  // 放过正在播放音频或有前台服务的 App
+    pub protect_bound_apps: bool, // 放过被系统绑定的 App（设备管理器、通知监听器、勿扰、闹钟）
+    pub battery_stats: bool,  // 参考 batterystats 耗电，优先清理耗电大户
+    pub quirks: Quirks,
+    pub drop_privileges: bool, // 初始化后只保留必要的 capability，热加载后需要更多时要重启
+    pub seccomp: bool,         // 同时禁止 mount/reboot/insmod 等系统调用
+    pub relaunch_window_secs: u64, // 0 表示不检测用户重新打开
+    pub relaunch_suggest_after: u32, // 重新打开达到次数后在日志里建议加白名单，0 表示不建议
+    pub switch_grace_secs: u64, // App 离开前台后多久内不清理，0 表示不等待
+    pub min_background_secs: u64, // 连续处于阈值以上这么久才可清理，0 表示不要求
+    pub protect_recent: usize, // 最近在前台出现过的这么多个包不清理，0 表示关闭
+    pub watchdog_intervals: u64, // 连续这么多个周期没有完成清理即视为卡死，0 表示关闭看门狗
+    pub watchdog_reexec: bool, // 卡死时重新执行自身
+    pub max_cycle_ms: u64,     // 单轮清理超过此时长即重置进程表，0 表示不检查
+    pub catch_up_after_resume: bool, // 休眠跳过了周期时，醒来第一轮不做空闲判定直接清理
+    pub only_when_screen_off: bool, // 定时周期只在熄屏时清理，亮屏使用中不动手
+    pub aggressive_on_screen_off: bool, // 熄屏时改用更短的周期和更低的阈值
+    pub screen_off_interval: u64, // 熄屏时的清理周期（秒），0 表示 interval 的一半
+    pub adaptive_interval: bool, // 按内存走势与进程启动情况在 min/max_interval 之间调整周期
+    pub min_interval: u64,
+    pub max_interval: u64,
+    pub screen_off_threshold: i32,  // 熄屏时的阈值，与档位阈值取较低者
+    pub psi_threshold: Option<f32>, // 内存 some 停顿百分比，设置后由 PSI 触发清理，定时周期只在压力达标时清理
+    pub proc_events: bool, // 订阅内核进程事件增量维护 pid 集合，订阅失败或事件丢失时完整扫描 /proc
+    pub whitelist_report_days: u64, // 每隔这么多天报告期间从未命中的白名单条目，0 表示不报告
+    pub stats_report_secs: u64, // 清理统计汇总的间隔（hourly/daily），0 表示不写
+    pub whitelist_stats: Option<String>, // 命中统计文件，跨重启累计；不设置则只在本次运行内统计
+    pub table_state: Option<String>, // 进程表状态文件，重启后恢复后台计时与各包击杀历史等
+    pub metrics_path: Option<String>, // 每轮清理后写入 Prometheus 文本格式指标的文件
+    pub pid_file: Option<String>, // 单实例锁与 pid 文件，off 表示不检查
+    pub namespaces: NamespacePolicy, // 其他 PID/user 命名空间（容器、沙盒）中的进程如何处理
+    pub error_log: Option<String>, // 警告与错误单独写入的文件，不设置则只输出到 stderr
+    pub error_log_max_kb: u64, // 错误日志超过此大小后轮转为 <path>.1
+    pub check_update: bool, // 允许 check-update 命令联网查询新版本
+    pub update_url: String, // 发布接口，返回含 tag_name 的 JSON
+    pub whitelist_oom_adj: Option<i32>, // 把白名单进程的 oom_score_adj 压到此值，系统 LMK 也不易杀它们
+    pub abi32: Abi32Policy,             // 32 位 App 进程整类优先清理或豁免
+    pub tiers: Vec<(u64, i32)>,         // (MemAvailable 低于此值, 改用的阈值)，按内存从低到高排列
+    pub policies: FxHashMap<String, PackagePolicy>, // 包名 -> 单独的阈值与处理方式
+}
+
+impl AppConfig {
+    /// 按当前可用内存选出阈值：落入的最低一档生效，都不满足或读不到内存时用 threshold；
+    /// 启用熄屏激进模式且正熄屏时不高于 screen_off_threshold
+    pub fn threshold_for(&self, avail_kb: Option<u64>, screen_off: bool) -> i32 {
+        let threshold = avail_kb
+            .and_then(|avail| self.tiers.iter().find(|&&(below_kb, _)| avail < below_kb))
+            .map_or(self.oom_threshold, |&(_, threshold)| threshold);
+        if screen_off && self.aggressive_on_screen_off {
+            threshold.min(self.screen_off_threshold)
+        } else {
+            threshold
+        }
+    }
+
+    /// 进程名命中 never_touch_prefixes，不进进程表也不做任何处理
+    pub fn is_never_touched(&self, name: &str) -> bool {
+        self.never_touch_prefixes
+            .iter()
+            .any(|prefix| name.starts_with(prefix.as_str()))
+    }
+
+    /// 是否需要每轮读取电源状态
+    pub fn power_aware(&self) -> bool {
+        self.on_charger != ChargerPolicy::Normal || !self.battery_rules.is_empty()
+    }
+
+    /// 当前屏幕状态下的清理周期
+    pub fn interval_for(&self, screen_off: bool) -> u64 {
+        if !(screen_off && self.aggressive_on_screen_off) {
+            return self.interval;
+        }
+        match self.screen_off_interval {
+            0 => (self.interval / 2).max(1),
+            secs => secs,
+        }
+    }
+
+    /// 是否需要检测屏幕状态
+    pub fn screen_aware(&self) -> bool {
+        self.only_when_screen_off || self.aggressive_on_screen_off
+    }
+}
+
+/// 单个包的策略覆盖：`policy: com.foo.app oom=950 action=freeze`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PackagePolicy {
+    pub oom: Option<i32>, // oom_score_adj 达到此值才清理，取代全局阈值
+    pub action: Option<CleanAction>,
+}
+
+impl PackagePolicy {
+    /// "com.foo.app oom=950 action=freeze" → (包名, 策略)
+    fn parse(s: &str, warnings: &mut Vec<String>) -> Option<(String, Self)> {
+        let mut parts = s.split_whitespace();
+        let package = parts.next()?;
+        let mut policy = Self::default();
+        for part in parts {
+            match part.split_once('=') {
+                Some(("oom", v)) => {
+                    if let Some(t) = parse_threshold("policy oom", v, warnings) {
+                        policy.oom = Some(t);
+                    }
+                }
+                Some(("action", v)) => match CleanAction::parse(v) {
+                    Some(a) => policy.action = Some(a),
+                    None => warnings.push(format!("Unknown policy action: {}", v)),
+                },
+                _ => warnings.push(format!("Unknown policy option: {}", part)),
+            }
+        }
+        Some((package.to_string(), policy))
+    }
+}
+
+/// 可用内存滞回触发：低于 enter 开始清理，回升到 exit 以上才停止
+#[derive(Debug, Clone, Copy)]
+pub struct MemTrigger {
+    pub enter_kb: u64,
+    pub exit_kb: u64,
+}
+
+/// 配置文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Legacy, // 逐行 "key: value"
+    Toml,
+}
+
+impl ConfigFormat {
+    /// 扩展名为 .toml，或第一个有效行是 `format = "toml"`（引号可省）
+    pub fn detect(path: &str, content: &str) -> Self {
+        if path.ends_with(".toml") || format_header(content).is_some() {
+            Self::Toml
+        } else {
+            Self::Legacy
+        }
+    }
+}
+
+/// `format = "toml"` 头所在的行号（从 1 开始）
+fn format_header(content: &str) -> Option<usize> {
+    let (i, line) = content
+        .lines()
+        .enumerate()
+        .find(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'))?;
+    let (key, value) = line.split_once('=')?;
+    let value = value.split('#').next().unwrap_or("").trim();
+    (key.trim() == "format" && value.trim_matches('"') == "toml").then_some(i + 1)
+}
+
+/// 读不到文件时按空配置处理，全部取默认值
+pub fn load_config(path: &str) -> AppConfig {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    report(parse_config_text(path, &content))
+}
+
+/// 按 path 判定格式解析配置文本，警告原样返回、不上报（导入备份前校验用）
+pub fn parse_config_text(path: &str, content: &str) -> (AppConfig, Vec<String>) {
+    let mut warnings = Vec::new();
+    let config = match ConfigFormat::detect(path, content) {
+        ConfigFormat::Legacy => parse_legacy(content, &mut warnings),
+        ConfigFormat::Toml => parse_toml(content, &mut warnings),
+    };
+    (config, warnings)
+}
+
+pub fn parse_config(content: &str) -> AppConfig {
+    let mut warnings = Vec::new();
+    let config = parse_legacy(content, &mut warnings);
+    report((config, warnings))
+}
+
+pub fn parse_toml_config(content: &str) -> AppConfig {
+    let mut warnings = Vec::new();
+    let config = parse_toml(content, &mut warnings);
+    report((config, warnings))
+}
+
+fn report((config, warnings): (AppConfig, Vec<String>)) -> AppConfig {
+    for w in warnings {
+        errlog::warn("config", &w);
+    }
+    config
+}
+
+fn parse_legacy(content: &str, warnings: &mut Vec<String>) -> AppConfig {
+    parse_lines(
+        content.lines().enumerate().map(|(i, l)| (i + 1, l)),
+        warnings,
+    )
+}
+
+/// TOML 配置的结构：有专门写法的表与数组各占一个成员，其余顶层键都是标量（或数组），
+/// 与旧格式的同名键一一对应
+#[derive(Deserialize)]
+struct TomlConfig {
+    #[serde(default)]
+    policy: BTreeMap<String, Spanned<TomlPolicy>>, // [policy."com.foo"]
+    #[serde(default)]
+    tiers: BTreeMap<String, Spanned<i64>>, // [tiers] 下 "<MB>" = 阈值
+    #[serde(default)]
+    quirks: BTreeMap<String, Spanned<toml::Value>>,
+    #[serde(default)]
+    schedule: Vec<Spanned<TomlSchedule>>, // 旧写法的字符串，或 [[schedule]] 数组表
+    #[serde(default)]
+    whitelist: Vec<Spanned<String>>,
+    #[serde(default)]
+    blacklist: Vec<Spanned<String>>,
+    #[serde(default)]
+    never_touch_prefixes: Vec<Spanned<String>>,
+    #[serde(flatten)]
+    settings: toml::Table,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlPolicy {
+    oom: Option<i64>,
+    action: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TomlSchedule {
+    Line(String),
+    Window {
+        window: String, // "HH:MM-HH:MM"
+        threshold: Option<i64>,
+        interval: Option<i64>,
+        enabled: Option<bool>,
+    },
+}
+
+impl TomlSchedule {
+    fn to_legacy(&self) -> String {
+        match self {
+            Self::Line(line) => line.clone(),
+            Self::Window {
+                window,
+                threshold,
+                interval,
+                enabled,
+            } => {
+                let mut line = window.clone();
+                if let Some(t) = threshold {
+                    line.push_str(&format!(" threshold={}", t));
+                }
+                if let Some(secs) = interval {
+                    line.push_str(&format!(" interval={}", secs));
+                }
+                if let Some(enabled) = enabled {
+                    line.push_str(if *enabled { " on" } else { " off" });
+                }
+                line
+            }
+        }
+    }
+}
+
+/// 标量转成旧格式里的写法：布尔为 on/off，数组以逗号分隔；表与日期时间没有对应写法
+fn toml_to_legacy(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(n) => Some(n.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(if *b { "on" } else { "off" }.to_string()),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(toml_to_legacy)
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(", ")),
+        toml::Value::Table(_) | toml::Value::Datetime(_) => None,
+    }
+}
+
+/// 字节偏移所在的行号（从 1 开始）
+fn line_at(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// TOML 配置用 serde 解析成 [`TomlConfig`]，再换算成等价的旧格式行（保留原行号）交给同一个解析器，
+/// 取值校验与默认值只有一套
+///
+/// 语法或类型错误时整份配置作废、全部取默认值，错误带行号记入警告。
+fn parse_toml(content: &str, warnings: &mut Vec<String>) -> AppConfig {
+    // 格式头允许不加引号，不是合法 TOML，解析前换成空行
+    let header = format_header(content);
+    let body: String = content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| if Some(i + 1) == header { "" } else { l })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let parsed = toml::from_str::<TomlConfig>(&body).and_then(|config| {
+        // flatten 进来的顶层键没有位置信息，另取一遍各键的起始位置
+        let spans = toml::from_str::<BTreeMap<String, Spanned<IgnoredAny>>>(&body)?;
+        Ok((config, spans))
+    });
+    let (config, spans) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let line = e.span().map_or(0, |span| line_at(&body, span.start));
+            warnings.push(format!("line {}: {}", line, e.message().trim()));
+            return parse_lines(std::iter::empty(), warnings);
+        }
+    };
+    let line_of = |key: &str| spans.get(key).map_or(0, |s| line_at(&body, s.span().start));
+
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (key, value) in &config.settings {
+        if key == "format" {
+            continue;
+        }
+        match toml_to_legacy(value) {
+            Some(value) => lines.push((line_of(key), format!("{}: {}", key, value))),
+            None => warnings.push(format!("line {}: unknown setting {}", line_of(key), key)),
+        }
+    }
+    for (package, policy) in &config.policy {
+        let mut line = format!("policy: {}", package);
+        if let Some(oom) = policy.get_ref().oom {
+            line.push_str(&format!(" oom={}", oom));
+        }
+        if let Some(action) = &policy.get_ref().action {
+            line.push_str(&format!(" action={}", action));
+        }
+        lines.push((line_at(&body, policy.span().start), line));
+    }
+
+    let mut section = |name: &str, body_lines: Vec<(usize, String)>| {
+        if let Some(&(first, _)) = body_lines.first() {
+            lines.push((first, format!("{}:", name)));
+            lines.extend(body_lines);
+        }
+    };
+    section(
+        "tiers",
+        config
+            .tiers
+            .iter()
+            .map(|(mb, t)| {
+                (
+                    line_at(&body, t.span().start),
+                    format!("{}: {}", mb, t.get_ref()),
+                )
+            })
+            .collect(),
+    );
+    let mut quirks = Vec::new();
+    for (key, value) in &config.quirks {
+        let line = line_at(&body, value.span().start);
+        match toml_to_legacy(value.get_ref()) {
+            Some(v) => quirks.push((line, format!("{}: {}", key, v))),
+            None => warnings.push(format!("line {}: unknown setting quirks.{}", line, key)),
+        }
+    }
+    section("quirks", quirks);
+    section(
+        "schedule",
+        config
+            .schedule
+            .iter()
+            .map(|w| (line_at(&body, w.span().start), w.get_ref().to_legacy()))
+            .collect(),
+    );
+    for (name, items) in [
+        ("never_touch_prefixes", &config.never_touch_prefixes),
+        ("whitelist", &config.whitelist),
+        ("blacklist", &config.blacklist),
+    ] {
+        section(
+            name,
+            items
+                .iter()
+                .map(|i| (line_at(&body, i.span().start), i.get_ref().clone()))
+                .collect(),
+        );
+    }
+    parse_lines(lines.iter().map(|(n, l)| (*n, l.as_str())), warnings)
+}
+
+/// 逐行解析；行号只用于报告无法识别的行
+fn parse_lines<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+    warnings: &mut Vec<String>,
+) -> AppConfig {
+    let mut interval = DEFAULT_INTERVAL;
+    let mut oom_threshold = OOM_SCORE_THRESHOLD;
+    let mut hard_threshold = None;
+    let mut threshold_metric = OomMetric::Adj;
+    let mut idle_mode = IdleMode::Doze;
+    let mut input_idle_secs = DEFAULT_INPUT_IDLE_SECS;
+    let mut doze_cache_secs = device::DEFAULT_DOZE_CACHE_SECS;
+    let mut frozen_action = FrozenAction::Skip;
+    let mut action = CleanAction::Kill;
+    let mut demote_adj = DEFAULT_DEMOTE_ADJ;
+    let mut kill_scope = KillScope::Process;
+    let mut dry_run = false;
+    let mut gpu_accounting = true;
+    let mut swap_weight = DEFAULT_SWAP_WEIGHT;
+    let mut min_size_kb = 0;
+    let mut min_rss_kb = 0;
+    let mut largest_first = false;
+    let mut min_heap_kb = 0;
+    let mut dumpsys_meminfo = false;
+    let mut min_pss_kb = 0;
+    let mut kill_delay_ms = 0;
+    let mut max_kills_per_cycle = 0;
+    let mut term_grace_cycles = 0;
+    let mut survivor_actions = vec![SurvivorAction::Ignore]; // thaw_kill、force_stop 需显式开启
+    let mut score_weights: Option<String> = None;
+    let mut score_threshold = OOM_SCORE_THRESHOLD as f32;
+    let mut script_path: Option<String> = None;
+    let mut script_max_operations = script::DEFAULT_MAX_OPERATIONS;
+    let mut hook = None;
+    let mut hook_timeout_ms = hook::DEFAULT_HOOK_TIMEOUT_MS;
+    let mut respawn_fast_secs = DEFAULT_RESPAWN_FAST_SECS;
+    let mut kill_cooldown_secs = 0;
+    let mut storm_kills_per_hour = 0;
+    let mut comfort_kb = 0;
+    let mut mem_enter_kb = 0;
+    let mut target_available_kb = 0;
+    let mut control_socket = None;
+    let mut log_path = None;
+    let mut verbose_log = false;
+    let mut exclude_uids = Vec::new();
+    let mut system_targets: FxHashSet<WhitelistRule> = FxHashSet::default();
+    let mut gms_policy = GmsPolicy::Protect;
+    let mut battery_stats = false;
+    let mut protect_widgets = false;
+    let mut protect_critical = true;
+    let mut protect_media = false;
+    let mut protect_bound_apps = true;
+    let mut drop_privileges = false;
+    let mut quirk_overrides = QuirkOverrides::default();
+    let mut tiers = Vec::new();
+    let mut policies = FxHashMap::default();
+    let mut seccomp = false;
+    let mut http_api = None;
+    let mut broadcast = false;
+    let mut trigger_file = None;
+    let mut fsync_logs = false;
+    let mut log_backend = LogBackend::File;
+    let mut log_level = LogLevel::Info;
+    let mut log_format = LogFormat::Text;
+    let mut log_max_kb = 0;
+    let mut log_backups = DEFAULT_LOG_BACKUPS;
+    let mut recent_events = events::DEFAULT_RECENT_CAPACITY;
+    let mut control_token = None;
+    let mut control_uids = Vec::new();
+    let mut relaunch_window_secs = DEFAULT_RELAUNCH_WINDOW_SECS;
+    let mut relaunch_suggest_after = DEFAULT_RELAUNCH_SUGGEST_AFTER;
+    let mut switch_grace_secs = DEFAULT_SWITCH_GRACE_SECS;
+    let mut min_background_secs = 0;
+    let mut protect_recent = 0;
+    let mut mem_exit_kb = 0;
+    let mut watchdog_intervals = DEFAULT_WATCHDOG_INTERVALS;
+    let mut watchdog_reexec = false;
+    let mut max_cycle_ms = watchdog::DEFAULT_MAX_CYCLE_MS;
+    let mut catch_up_after_resume = false;
+    let mut only_when_screen_off = false;
+    let mut aggressive_on_screen_off = false;
+    let mut screen_off_interval = 0;
+    let mut adaptive_interval = false;
+    let mut min_interval = DEFAULT_MIN_INTERVAL;
+    let mut max_interval = DEFAULT_MAX_INTERVAL;
+    let mut screen_off_threshold = SCREEN_OFF_THRESHOLD;
+    let mut psi_threshold = None;
+    let mut proc_events = true;
+    let mut whitelist_report_days = DEFAULT_WHITELIST_REPORT_DAYS;
+    let mut stats_report_secs = 0;
+    let mut whitelist_stats = None;
+    let mut table_state = None;
+    let mut metrics_path = None;
+    let mut pid_file = Some(daemonize::DEFAULT_PID_FILE.to_string());
+    let mut namespaces = NamespacePolicy::Isolate;
+    let mut error_log = None;
+    let mut check_update = false;
+    let mut whitelist_oom_adj = None;
+    let mut abi32 = Abi32Policy::Normal;
+    let mut update_url = update::DEFAULT_UPDATE_URL.to_string();
+    let mut error_log_max_kb = errlog::DEFAULT_ERROR_LOG_MAX_KB;
+    let mut whitelist: FxHashSet<WhitelistRule> = FxHashSet::default();
+    let mut blacklist: FxHashSet<WhitelistRule> = FxHashSet::default();
+    let mut never_touch_prefixes: Vec<String> = Vec::new();
+    let mut schedule: Vec<ScheduleWindow> = Vec::new();
+    let mut on_charger = ChargerPolicy::Normal;
+    let mut battery_rules: Vec<BatteryRule> = Vec::new();
+
+    // 内置默认白名单（完全匹配）
+    for name in BUILTIN_WHITELIST {
+        whitelist.insert(WhitelistRule::Exact(name.to_string()));
+    }
+
+    let mut in_whitelist_mode = false;
+    let mut in_blacklist = false;
+    let mut in_quirks_mode = false;
+    let mut in_tiers_mode = false;
+    let mut in_never_touch_mode = false;
+    let mut in_schedule_mode = false;
+    for (line_no, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if in_quirks_mode {
+            if quirk_overrides.parse_line(line) {
+                continue;
+            }
+            in_quirks_mode = false;
+        }
+
+        // tiers 段每行 "<MB>: <阈值>"，遇到其他行即结束
+        if in_tiers_mode {
+            if let Some(tier) = parse_tier(line, warnings) {
+                tiers.extend(tier);
+                continue;
+            }
+            in_tiers_mode = false;
+        }
+
+        // never_touch_prefixes 段每行一个前缀（也可逗号分隔），遇到 "key: value" 即结束
+        if in_never_touch_mode {
+            if !line.contains(':') {
+                never_touch_prefixes.extend(split_prefixes(line));
+                continue;
+            }
+            in_never_touch_mode = false;
+        }
+
+        // schedule 段每行以时刻开头，遇到其他行即结束
+        if in_schedule_mode {
+            if line.starts_with(|c: char| c.is_ascii_digit()) {
+                match ScheduleWindow::parse(line) {
+                    Ok(window) => schedule.push(window),
+                    Err(e) => warnings.push(format!("line {}: {}", line_no, e)),
+                }
+                continue;
+            }
+            in_schedule_mode = false;
+        }
+
+        if line.starts_with("interval:") {
+            if let Some(val_part) = line.split(':').nth(1) {
+                match val_part.trim().parse::<u64>() {
+                    Ok(val) => interval = val,
+                    Err(_) => warnings.push(format!("Invalid interval: {}", val_part.trim())),
+                }
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "threshold") {
+            if let Some(t) = parse_threshold("threshold", val, warnings) {
+                oom_threshold = t;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "hard_threshold") {
+            if let Some(t) = parse_threshold("hard_threshold", val, warnings) {
+                hard_threshold = Some(t);
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "threshold_metric") {
+            match OomMetric::parse(val) {
+                Some(metric) => threshold_metric = metric,
+                None => warnings.push(format!("Unknown threshold_metric: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "idle_detect") {
+            match IdleMode::parse(val) {
+                Some(mode) => idle_mode = mode,
+                None => warnings.push(format!("Unknown idle_detect: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "input_idle_minutes") {
+            if let Ok(mins) = val.parse::<u64>() {
+                input_idle_secs = mins * 60;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "doze_cache_secs") {
+            match val.parse::<u64>() {
+                Ok(secs) if secs > 0 => doze_cache_secs = secs,
+                _ => warnings.push(format!("Invalid doze_cache_secs: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "frozen_action") {
+            match FrozenAction::parse(val) {
+                Some(action) => frozen_action = action,
+                None => warnings.push(format!("Unknown frozen_action: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "policy") {
+            match PackagePolicy::parse(val, warnings) {
+                Some((package, policy)) => {
+                    policies.insert(package, policy);
+                }
+                None => errlog::warn("config", "Empty policy line"),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "action") {
+            match CleanAction::parse(val) {
+                Some(a) => action = a,
+                None => warnings.push(format!("Unknown action: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "demote_adj") {
+            match val.parse::<i32>() {
+                Ok(adj) if (-1000..=1000).contains(&adj) => demote_adj = adj,
+                _ => warnings.push(format!("Invalid demote_adj: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "kill_scope") {
+            match KillScope::parse(val) {
+                Some(scope) => kill_scope = scope,
+                None => warnings.push(format!("Unknown kill_scope: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "dry_run") {
+            if let Some(b) = parse_bool(val) {
+                dry_run = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "gpu_accounting") {
+            if let Some(b) = parse_bool(val) {
+                gpu_accounting = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "swap_weight") {
+            if let Ok(w) = val.parse::<f32>() {
+                swap_weight = w.clamp(0.0, 1.0);
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "min_size_mb") {
+            if let Ok(mb) = val.parse::<u64>() {
+                min_size_kb = mb * 1024;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "min_rss_mb") {
+            match val.parse::<u64>() {
+                Ok(mb) => min_rss_kb = mb * 1024,
+                Err(_) => warnings.push(format!("Invalid min_rss_mb: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "largest_first") {
+            if let Some(b) = parse_bool(val) {
+                largest_first = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "min_heap_mb") {
+            if let Ok(mb) = val.parse::<u64>() {
+                min_heap_kb = mb * 1024;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "dumpsys_meminfo") {
+            if let Some(b) = parse_bool(val) {
+                dumpsys_meminfo = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "min_pss_mb") {
+            if let Ok(mb) = val.parse::<u64>() {
+                min_pss_kb = mb * 1024;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "kill_delay_ms") {
+            if let Ok(ms) = val.parse::<u64>() {
+                kill_delay_ms = ms;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "max_kills_per_cycle") {
+            match val.parse::<usize>() {
+                Ok(n) => max_kills_per_cycle = n,
+                Err(_) => warnings.push(format!("Invalid max_kills_per_cycle: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "term_grace_cycles") {
+            match val.parse::<u64>() {
+                Ok(cycles) => term_grace_cycles = cycles,
+                Err(_) => warnings.push(format!("Invalid term_grace_cycles: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "score") {
+            score_weights = Some(val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "score_threshold") {
+            if let Ok(t) = val.parse::<f32>() {
+                score_threshold = t;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "policy_script") {
+            script_path = Some(val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "policy_max_operations") {
+            match val.parse::<u64>() {
+                Ok(n) if n > 0 => script_max_operations = n,
+                _ => warnings.push(format!("Invalid policy_max_operations: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "hook") {
+            hook = (val != "off").then(|| val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "hook_timeout_ms") {
+            match val.parse::<u64>() {
+                Ok(ms) if ms > 0 => hook_timeout_ms = ms,
+                _ => warnings.push(format!("Invalid hook_timeout_ms: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "kill_cooldown_mins") {
+            match val.parse::<u64>() {
+                Ok(mins) => kill_cooldown_secs = mins * 60,
+                Err(_) => warnings.push(format!("Invalid kill_cooldown_mins: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "storm_kills_per_hour") {
+            match val.parse::<u32>() {
+                Ok(n) => storm_kills_per_hour = n,
+                Err(_) => warnings.push(format!("Invalid storm_kills_per_hour: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "respawn_fast_secs") {
+            if let Ok(secs) = val.parse::<u64>() {
+                respawn_fast_secs = secs;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "comfort_mb") {
+            if let Ok(mb) = val.parse::<u64>() {
+                comfort_kb = mb * 1024;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "mem_enter_mb") {
+            if let Ok(mb) = val.parse::<u64>() {
+                mem_enter_kb = mb * 1024;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "mem_exit_mb") {
+            if let Ok(mb) = val.parse::<u64>() {
+                mem_exit_kb = mb * 1024;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "target_available_mb") {
+            if let Ok(mb) = val.parse::<u64>() {
+                target_available_kb = mb * 1024;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "control_socket") {
+            control_socket = match val {
+                "off" => None,
+                "on" => Some(control::DEFAULT_SOCKET_PATH.to_string()),
+                path => Some(path.to_string()),
+            };
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "log_path") {
+            log_path = Some(val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "recent_events") {
+            if let Ok(n) = val.parse::<usize>() {
+                recent_events = n;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "log_backend") {
+            match LogBackend::parse(val) {
+                Some(backend) => log_backend = backend,
+                None => warnings.push(format!("Unknown log_backend: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "log_level") {
+            match LogLevel::parse(val) {
+                Some(level) => log_level = level,
+                None => warnings.push(format!("Unknown log_level: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "log_format") {
+            match LogFormat::parse(val) {
+                Some(format) => log_format = format,
+                None => warnings.push(format!("Unknown log_format: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "log_max_kb") {
+            match val.parse::<u64>() {
+                Ok(kb) => log_max_kb = kb,
+                Err(_) => warnings.push(format!("Invalid log_max_kb: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "log_backups") {
+            match val.parse::<u32>() {
+                Ok(n) => log_backups = n,
+                Err(_) => warnings.push(format!("Invalid log_backups: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "fsync_logs") {
+            if let Some(b) = parse_bool(val) {
+                fsync_logs = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "verbose_log") {
+            if let Some(b) = parse_bool(val) {
+                verbose_log = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "exclude_uids") {
+            parse_uid_ranges(val, &mut exclude_uids, warnings);
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "system_targets") {
+            parse_whitelist_rules(val, &mut system_targets, warnings);
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "survivor_actions") {
+            survivor_actions = if val == "none" {
+                Vec::new()
+            } else {
+                val.split(',')
+                    .map(str::trim)
+                    .filter_map(|s| {
+                        let action = SurvivorAction::parse(s);
+                        if action.is_none() {
+                            warnings.push(format!("Unknown survivor action: {}", s));
+                        }
+                        action
+                    })
+                    .collect()
+            };
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "gms_policy") {
+            match GmsPolicy::parse(val) {
+                Some(policy) => gms_policy = policy,
+                None => warnings.push(format!("Unknown gms_policy: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "trigger_file") {
+            trigger_file = (val != "off").then(|| val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "broadcast") {
+            if let Some(b) = parse_bool(val) {
+                broadcast = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "http_api") {
+            http_api = (val != "off").then(|| val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "control_token") {
+            control_token = (!val.is_empty()).then(|| val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "control_uids") {
+            parse_uid_ranges(val, &mut control_uids, warnings);
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "drop_privileges") {
+            if let Some(b) = parse_bool(val) {
+                drop_privileges = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "seccomp") {
+            if let Some(b) = parse_bool(val) {
+                seccomp = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "watchdog_intervals") {
+            if let Ok(n) = val.parse::<u64>() {
+                watchdog_intervals = n;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "catch_up_after_resume") {
+            if let Some(b) = parse_bool(val) {
+                catch_up_after_resume = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "only_when_screen_off") {
+            if let Some(b) = parse_bool(val) {
+                only_when_screen_off = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "aggressive_on_screen_off") {
+            if let Some(b) = parse_bool(val) {
+                aggressive_on_screen_off = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "screen_off_interval") {
+            match val.parse::<u64>() {
+                Ok(secs) => screen_off_interval = secs,
+                Err(_) => warnings.push(format!("Invalid screen_off_interval: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "adaptive_interval") {
+            if let Some(b) = parse_bool(val) {
+                adaptive_interval = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "min_interval") {
+            match val.parse::<u64>() {
+                Ok(secs) if secs > 0 => min_interval = secs,
+                _ => warnings.push(format!("Invalid min_interval: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "max_interval") {
+            match val.parse::<u64>() {
+                Ok(secs) if secs > 0 => max_interval = secs,
+                _ => warnings.push(format!("Invalid max_interval: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "screen_off_threshold") {
+            if let Some(t) = parse_threshold("screen_off_threshold", val, warnings) {
+                screen_off_threshold = t;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "psi_threshold") {
+            match val.parse::<f32>() {
+                Ok(t) if t > 0.0 && t <= 100.0 => psi_threshold = Some(t),
+                _ if val == "off" => psi_threshold = None,
+                _ => warnings.push(format!("Invalid psi_threshold: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "proc_events") {
+            if let Some(b) = parse_bool(val) {
+                proc_events = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "stats_report") {
+            match val {
+                "off" => stats_report_secs = 0,
+                "hourly" => stats_report_secs = 3600,
+                "daily" => stats_report_secs = 86400,
+                _ => warnings.push(format!("Unknown stats_report: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "whitelist_report_days") {
+            if let Ok(n) = val.parse::<u64>() {
+                whitelist_report_days = n;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "namespaces") {
+            match NamespacePolicy::parse(val) {
+                Some(p) => namespaces = p,
+                None => warnings.push(format!("Invalid namespaces: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "abi32") {
+            match Abi32Policy::parse(val) {
+                Some(p) => abi32 = p,
+                None => warnings.push(format!("Unknown abi32: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "whitelist_oom_adj") {
+            match val.parse::<i32>() {
+                Ok(adj) if (-1000..=1000).contains(&adj) => whitelist_oom_adj = Some(adj),
+                _ if val == "off" => whitelist_oom_adj = None,
+                _ => warnings.push(format!("Invalid whitelist_oom_adj: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "check_update") {
+            if let Some(b) = parse_bool(val) {
+                check_update = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "update_url") {
+            update_url = val.to_string();
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "error_log") {
+            error_log = Some(val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "error_log_max_kb") {
+            if let Ok(n) = val.parse::<u64>() {
+                error_log_max_kb = n;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "whitelist_stats") {
+            whitelist_stats = Some(val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "table_state") {
+            table_state = Some(val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "metrics_path") {
+            metrics_path = (val != "off").then(|| val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "pid_file") {
+            pid_file = (val != "off").then(|| val.to_string());
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "watchdog_reexec") {
+            if let Some(b) = parse_bool(val) {
+                watchdog_reexec = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "max_cycle_ms") {
+            match val.parse::<u64>() {
+                Ok(ms) => max_cycle_ms = ms,
+                Err(_) => warnings.push(format!("Invalid max_cycle_ms: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "protect_bound_apps") {
+            if let Some(b) = parse_bool(val) {
+                protect_bound_apps = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "protect_widgets") {
+            if let Some(b) = parse_bool(val) {
+                protect_widgets = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "protect_critical") {
+            if let Some(b) = parse_bool(val) {
+                protect_critical = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "protect_media") {
+            if let Some(b) = parse_bool(val) {
+                protect_media = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "battery_stats") {
+            if let Some(b) = parse_bool(val) {
+                battery_stats = b;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "relaunch_window_minutes") {
+            if let Ok(minutes) = val.parse::<u64>() {
+                relaunch_window_secs = minutes * 60;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "protect_recent") {
+            match val.parse::<usize>() {
+                Ok(n) => protect_recent = n,
+                Err(_) => warnings.push(format!("Invalid protect_recent: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "switch_grace_secs") {
+            if let Ok(secs) = val.parse::<u64>() {
+                switch_grace_secs = secs;
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "min_background_secs") {
+            match val.parse::<u64>() {
+                Ok(secs) => min_background_secs = secs,
+                Err(_) => warnings.push(format!("Invalid min_background_secs: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "relaunch_suggest_after") {
+            if let Ok(n) = val.parse::<u32>() {
+                relaunch_suggest_after = n;
+            }
+            in_whitelist_mode = false;
+        } else if line.starts_with("quirks:") {
+            in_quirks_mode = true;
+            in_whitelist_mode = false;
+        } else if line == "tiers:" {
+            in_tiers_mode = true;
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "on_charger") {
+            match ChargerPolicy::parse(val) {
+                Some(policy) => on_charger = policy,
+                None => warnings.push(format!("Invalid on_charger: {}", val)),
+            }
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "battery_below") {
+            // 可写多行，也可在一行内用逗号分隔多档
+            for rule in val.split(',') {
+                match BatteryRule::parse(rule) {
+                    Ok(rule) => battery_rules.push(rule),
+                    Err(e) => warnings.push(format!("line {}: {}", line_no, e)),
+                }
+            }
+            in_whitelist_mode = false;
+        } else if line == "schedule:" {
+            in_schedule_mode = true;
+            in_whitelist_mode = false;
+        } else if let Some(val) = config_value(line, "never_touch_prefixes") {
+            never_touch_prefixes.extend(split_prefixes(val));
+            in_never_touch_mode = true;
+            in_whitelist_mode = false;
+        } else if line.starts_with("whitelist:") || line.starts_with("blacklist:") {
+            // in_whitelist_mode 对两种名单段通用，in_blacklist 区分写入哪一个
+            in_whitelist_mode = true;
+            in_blacklist = line.starts_with("blacklist:");
+            if let Some(val_part) = line.split(':').nth(1) {
+                let list = if in_blacklist {
+                    &mut blacklist
+                } else {
+                    &mut whitelist
+                };
+                parse_whitelist_rules(val_part, list, warnings);
+            }
+        } else if in_whitelist_mode {
+            let list = if in_blacklist {
+                &mut blacklist
+            } else {
+                &mut whitelist
+            };
+            parse_whitelist_rules(line, list, warnings);
+        } else {
+            // 多半是拼错的键：照旧忽略，但让 check-config 看得到
+            warnings.push(format!("line {}: unknown setting {}", line_no, line));
+        }
+    }
+
+    tiers.sort_by_key(|&(below_kb, _)| below_kb);
+    battery_rules.sort_by_key(|r| r.below);
+
+    AppConfig {
+        interval,
+        oom_threshold,
+        hard_threshold,
+        threshold_metric,
+        whitelist,
+        blacklist,
+        never_touch_prefixes,
+        schedule,
+        on_charger,
+        battery_rules,
+        idle_mode,
+        input_idle_secs,
+        doze_cache_secs,
+        frozen_action,
+        action,
+        demote_adj,
+        kill_scope,
+        dry_run,
+        gpu_accounting,
+        swap_weight,
+        min_size_kb,
+        min_rss_kb,
+        largest_first,
+        min_heap_kb,
+        dumpsys_meminfo,
+        min_pss_kb,
+        kill_delay_ms,
+        max_kills_per_cycle,
+        term_grace_cycles,
+        survivor_actions,
+        score: score_weights.map(|w| ScoreWeights::parse(&w, score_threshold)),
+        policy_script: script_path.and_then(|path| {
+            PolicyScript::load(&path, script_max_operations)
+                .map_err(|e| warnings.push(format!("policy_script: {}", e)))
+                .ok()
+        }),
+        hook,
+        hook_timeout_ms,
+        kill_cooldown_secs,
+        storm_kills_per_hour,
+        respawn_fast_secs,
+        comfort_kb,
+        mem_trigger: (mem_enter_kb > 0).then(|| MemTrigger {
+            enter_kb: mem_enter_kb,
+            exit_kb: mem_exit_kb.max(mem_enter_kb),
+        }),
+        target_available_kb,
+        control_socket,
+        log_path,
+        verbose_log,
+        exclude_uids,
+        system_targets,
+        gms_policy,
+        fsync_logs,
+        log_backend,
+        log_level,
+        log_format,
+        log_max_kb,
+        log_backups,
+        recent_events,
+        trigger_file,
+        broadcast,
+        http_api,
+        access: AccessPolicy {
+            token: control_token,
+            allowed_uids: control_uids,
+        },
+        protect_widgets,
+        protect_critical,
+        protect_media,
+        protect_bound_apps,
+        battery_stats,
+        quirks: quirk_overrides.resolve(),
+        drop_privileges,
+        seccomp,
+        relaunch_window_secs,
+        relaunch_suggest_after,
+        switch_grace_secs,
+        min_background_secs,
+        protect_recent,
+        watchdog_intervals,
+        watchdog_reexec,
+        max_cycle_ms,
+        catch_up_after_resume,
+        only_when_screen_off,
+        aggressive_on_screen_off,
+        screen_off_interval,
+        adaptive_interval,
+        min_interval,
+        max_interval,
+        screen_off_threshold,
+        psi_threshold,
+        proc_events,
+        whitelist_report_days,
+        stats_report_secs,
+        whitelist_stats,
+        table_state,
+        metrics_path,
+        pid_file,
+        namespaces,
+        error_log,
+        error_log_max_kb,
+        check_update,
+        update_url,
+        whitelist_oom_adj,
+        abi32,
+        tiers,
+        policies,
+    }
+}
+
+fn split_prefixes(line: &str) -> impl Iterator<Item = String> + '_ {
+    line.split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+}
+
+/// "500: 600"：可用内存低于 500MB 时阈值降到 600；不是档位行时返回 None，阈值无效时返回 Some(None)
+fn parse_tier(line: &str, warnings: &mut Vec<String>) -> Option<Option<(u64, i32)>> {
+    let (mb, threshold) = line.split_once(':')?;
+    let mb = mb.trim().parse::<u64>().ok()?;
+    Some(parse_threshold("tier threshold", threshold.trim(), warnings).map(|t| (mb * 1024, t)))
+}
+
+/// 阈值类的值都限制在 THRESHOLD_RANGE 内，超出时记警告并返回 None
+fn parse_threshold(key: &str, val: &str, warnings: &mut Vec<String>) -> Option<i32> {
+    match val.parse::<i32>() {
+        Ok(t) if THRESHOLD_RANGE.contains(&t) => Some(t),
+        _ => {
+            warnings.push(format!(
+                "Invalid {}: {} (must be {}..={})",
+                key,
+                val,
+                THRESHOLD_RANGE.start(),
+                THRESHOLD_RANGE.end()
+            ));
+            None
+        }
+    }
+}
+
+/// 解析后的生效配置，按配置文件的 "key: value" 写法逐行列出（token 只标明是否设置）
+pub fn describe_config(config: &AppConfig) -> Vec<String> {
+    let on_off = |b: bool| if b { "on" } else { "off" };
+    let opt = |v: &Option<String>| v.clone().unwrap_or_else(|| "off".to_string());
+    let uids = |ranges: &[(u32, u32)]| {
+        if ranges.is_empty() {
+            return "none".to_string();
+        }
+        ranges
+            .iter()
+            .map(|&(a, b)| {
+                if a == b {
+                    a.to_string()
+                } else {
+                    format!("{}-{}", a, b)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mut lines = vec![
+        format!("interval: {}", config.interval),
+        format!("threshold: {}", config.oom_threshold),
+        format!(
+            "tiers: {}",
+            if config.tiers.is_empty() {
+                "none".to_string()
+            } else {
+                config
+                    .tiers
+                    .iter()
+                    .map(|&(below_kb, t)| format!("<{}MB:{}", below_kb / 1024, t))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }
+        ),
+        format!(
+            "hard_threshold: {}",
+            config
+                .hard_threshold
+                .map_or_else(|| "off".to_string(), |t| t.to_string())
+        ),
+        format!("threshold_metric: {}", config.threshold_metric.as_str()),
+        format!("idle_detect: {}", config.idle_mode.as_str()),
+        format!("input_idle_minutes: {}", config.input_idle_secs / 60),
+        format!("doze_cache_secs: {}", config.doze_cache_secs),
+        format!("frozen_action: {}", config.frozen_action.as_str()),
+        format!("action: {}", config.action.as_str()),
+        format!("demote_adj: {}", config.demote_adj),
+        format!("kill_scope: {}", config.kill_scope.as_str()),
+        format!("dry_run: {}", on_off(config.dry_run)),
+        format!("policies: {}", {
+            let mut policies: Vec<String> = config
+                .policies
+                .iter()
+                .map(|(package, p)| {
+                    let mut s = package.clone();
+                    if let Some(oom) = p.oom {
+                        s.push_str(&format!(" oom={}", oom));
+                    }
+                    if let Some(action) = p.action {
+                        s.push_str(&format!(" action={}", action.as_str()));
+                    }
+                    s
+                })
+                .collect();
+            policies.sort();
+            if policies.is_empty() {
+                "none".to_string()
+            } else {
+                policies.join(", ")
+            }
+        }),
+        format!("gpu_accounting: {}", on_off(config.gpu_accounting)),
+        format!("swap_weight: {}", config.swap_weight),
+        format!("min_size_mb: {}", config.min_size_kb / 1024),
+        format!("min_rss_mb: {}", config.min_rss_kb / 1024),
+        format!("largest_first: {}", on_off(config.largest_first)),
+        format!("min_heap_mb: {}", config.min_heap_kb / 1024),
+        format!("dumpsys_meminfo: {}", on_off(config.dumpsys_meminfo)),
+        format!("min_pss_mb: {}", config.min_pss_kb / 1024),
+        format!("kill_delay_ms: {}", config.kill_delay_ms),
+        format!("max_kills_per_cycle: {}", config.max_kills_per_cycle),
+        format!(
+            "term_grace_cycles: {}",
+            match config.term_grace_cycles {
+                0 => "off".to_string(),
+                n => n.to_string(),
+            }
+        ),
+        format!(
+            "survivor_actions: {}",
+            if config.survivor_actions.is_empty() {
+                "none".to_string()
+            } else {
+                config
+                    .survivor_actions
+                    .iter()
+                    .map(|a| a.as_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }
+        ),
+        match &config.score {
+            Some(w) => format!(
+                "score: oom={} oom_score={} pss={} idle={} bucket={} history={} battery={} procstats={} (threshold {})",
+                w.oom, w.oom_score, w.pss, w.idle, w.bucket, w.history, w.battery, w.procstats, w.threshold
+            ),
+            None => "score: off".to_string(),
+        },
+        match &config.policy_script {
+            Some(s) => format!(
+                "policy_script: {} (max {} operations)",
+                s.path(),
+                s.max_operations()
+            ),
+            None => "policy_script: off".to_string(),
+        },
+        format!(
+            "hook: {} (timeout {}ms)",
+            opt(&config.hook),
+            config.hook_timeout_ms
+        ),
+        format!("kill_cooldown_mins: {}", config.kill_cooldown_secs / 60),
+        format!("storm_kills_per_hour: {}", config.storm_kills_per_hour),
+        format!("respawn_fast_secs: {}", config.respawn_fast_secs),
+        format!("comfort_mb: {}", config.comfort_kb / 1024),
+        match config.mem_trigger {
+            Some(t) => format!(
+                "mem_enter_mb: {} / mem_exit_mb: {}",
+                t.enter_kb / 1024,
+                t.exit_kb / 1024
+            ),
+            None => "mem_enter_mb: off".to_string(),
+        },
+        format!("target_available_mb: {}", config.target_available_kb / 1024),
+        format!("control_socket: {}", opt(&config.control_socket)),
+        format!("control_token: {}", if config.access.token.is_some() { "set" } else { "off" }),
+        format!("control_uids: {}", uids(&config.access.allowed_uids)),
+        format!("http_api: {}", opt(&config.http_api)),
+        format!("verbose_log: {}", on_off(config.verbose_log)),
+        format!("fsync_logs: {}", on_off(config.fsync_logs)),
+        format!("log_backend: {}", config.log_backend.as_str()),
+        format!("log_level: {}", config.log_level.as_str()),
+        format!("log_format: {}", config.log_format.as_str()),
+        format!("log_max_kb: {}", config.log_max_kb),
+        format!("log_backups: {}", config.log_backups),
+        format!("recent_events: {}", config.recent_events),
+        format!("trigger_file: {}", opt(&config.trigger_file)),
+        format!("broadcast: {}", on_off(config.broadcast)),
+        format!("exclude_uids: {}", uids(&config.exclude_uids)),
+        format!("gms_policy: {}", config.gms_policy.as_str()),
+        format!("protect_widgets: {}", on_off(config.protect_widgets)),
+        format!("protect_critical: {}", on_off(config.protect_critical)),
+        format!("protect_media: {}", on_off(config.protect_media)),
+        format!("protect_bound_apps: {}", on_off(config.protect_bound_apps)),
+        format!("battery_stats: {}", on_off(config.battery_stats)),
+        format!("drop_privileges: {}", on_off(config.drop_privileges)),
+        format!("seccomp: {}", on_off(config.seccomp)),
+        format!("relaunch_window_minutes: {}", config.relaunch_window_secs / 60),
+        format!("relaunch_suggest_after: {}", config.relaunch_suggest_after),
+        format!("switch_grace_secs: {}", config.switch_grace_secs),
+        format!("min_background_secs: {}", config.min_background_secs),
+        format!("protect_recent: {}", config.protect_recent),
+        format!("watchdog_intervals: {}", config.watchdog_intervals),
+        format!("watchdog_reexec: {}", on_off(config.watchdog_reexec)),
+        format!("max_cycle_ms: {}", config.max_cycle_ms),
+        format!(
+            "catch_up_after_resume: {}",
+            on_off(config.catch_up_after_resume)
+        ),
+        format!("only_when_screen_off: {}", on_off(config.only_when_screen_off)),
+        format!(
+            "aggressive_on_screen_off: {}",
+            on_off(config.aggressive_on_screen_off)
+        ),
+        format!("screen_off_interval: {}", config.screen_off_interval),
+        format!(
+            "adaptive_interval: {} ({}-{}s)",
+            on_off(config.adaptive_interval),
+            config.min_interval,
+            config.max_interval
+        ),
+        format!("screen_off_threshold: {}", config.screen_off_threshold),
+        format!(
+            "psi_threshold: {}",
+            config
+                .psi_threshold
+                .map_or_else(|| "off".to_string(), |t| format!("{}%", t))
+        ),
+        format!("proc_events: {}", on_off(config.proc_events)),
+        format!("whitelist_report_days: {}", config.whitelist_report_days),
+        format!(
+            "stats_report: {}",
+            match config.stats_report_secs {
+                0 => "off",
+                3600 => "hourly",
+                _ => "daily",
+            }
+        ),
+        format!("whitelist_stats: {}", opt(&config.whitelist_stats)),
+        format!("table_state: {}", opt(&config.table_state)),
+        format!("metrics_path: {}", opt(&config.metrics_path)),
+        format!("pid_file: {}", opt(&config.pid_file)),
+        format!("namespaces: {}", config.namespaces.describe()),
+        format!("error_log: {}", opt(&config.error_log)),
+        format!("error_log_max_kb: {}", config.error_log_max_kb),
+        format!("check_update: {}", on_off(config.check_update)),
+        format!("abi32: {}", config.abi32.as_str()),
+        format!(
+            "whitelist_oom_adj: {}",
+            config
+                .whitelist_oom_adj
+                .map_or_else(|| "off".to_string(), |adj| adj.to_string())
+        ),
+        format!("update_url: {}", config.update_url),
+        format!(
+            "quirks: profile={} zram_device={} top_app_cpuset={} oem_killers={}",
+            config.quirks.profile.unwrap_or("none"),
+            config.quirks.zram_device,
+            config.quirks.top_app_cpuset,
+            if config.quirks.oem_killers.is_empty() {
+                "none".to_string()
+            } else {
+                config.quirks.oem_killers.join(",")
+            }
+        ),
+    ];
+    let mut system_targets: Vec<String> = config
+        .system_targets
+        .iter()
+        .map(|r| r.to_string())
+        .collect();
+    system_targets.sort();
+    lines.push(format!(
+        "system_targets: {}",
+        if system_targets.is_empty() {
+            "none".to_string()
+        } else {
+            system_targets.join(",")
+        }
+    ));
+    lines.push(format!(
+        "schedule: {}",
+        if config.schedule.is_empty() {
+            "none".to_string()
+        } else {
+            config
+                .schedule
+                .iter()
+                .map(ScheduleWindow::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    ));
+    lines.push(format!("on_charger: {}", config.on_charger.as_str()));
+    lines.push(format!(
+        "battery_below: {}",
+        if config.battery_rules.is_empty() {
+            "none".to_string()
+        } else {
+            config
+                .battery_rules
+                .iter()
+                .map(BatteryRule::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    ));
+    lines.push(format!(
+        "never_touch_prefixes: {}",
+        if config.never_touch_prefixes.is_empty() {
+            "none".to_string()
+        } else {
+            config.never_touch_prefixes.join(",")
+        }
+    ));
+    lines.push(format!("whitelist: {} 条规则", config.whitelist.len()));
+    let mut blacklist: Vec<String> = config.blacklist.iter().map(|r| r.to_string()).collect();
+    blacklist.sort();
+    lines.push(format!(
+        "blacklist: {}",
+        if blacklist.is_empty() {
+            "none".to_string()
+        } else {
+            blacklist.join(",")
+        }
+    ));
+    lines
+}
+
+/// 取 "key: value" 行的值部分
+pub fn config_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    line.strip_prefix(key)?.strip_prefix(':').map(str::trim)
+}
+
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s {
+        "true" | "on" | "1" => Some(true),
+        "false" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// 解析 UID 列表："1000-1999, 5013"
+fn parse_uid_ranges(value: &str, ranges: &mut Vec<(u32, u32)>, warnings: &mut Vec<String>) {
+    for part in value.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let range = match part.split_once('-') {
+            Some((lo, hi)) => lo.trim().parse().ok().zip(hi.trim().parse().ok()),
+            None => part.parse().ok().map(|uid| (uid, uid)),
+        };
+        match range {
+            Some((lo, hi)) if lo <= hi => ranges.push((lo, hi)),
+            _ => warnings.push(format!("Invalid UID range: {}", part)),
+        }
+    }
+}
+
+/// 解析白名单规则（支持 xxx:* 前缀匹配 和 xxx 完全匹配）
+fn parse_whitelist_rules(
+    line: &str,
+    whitelist: &mut FxHashSet<WhitelistRule>,
+    warnings: &mut Vec<String>,
+) {
+    // 正则里可能有逗号，re: 开头的行整行就是一条规则
+    if line.trim_start().starts_with("re:") {
+        whitelist.extend(WhitelistRule::parse_into(line.trim(), warnings));
+        return;
+    }
+    for part in line.split(',') {
+        let pkg = part.trim();
+        if pkg.is_empty() {
+            continue;
+        }
+        whitelist.extend(WhitelistRule::parse_into(pkg, warnings));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_config_uses_defaults() {
+        let config = parse_config("");
+        assert_eq!(config.interval, DEFAULT_INTERVAL);
+        assert_eq!(config.oom_threshold, OOM_SCORE_THRESHOLD);
+        assert!(!config.dry_run);
+        for name in BUILTIN_WHITELIST {
+            assert!(config
+                .whitelist
+                .contains(&WhitelistRule::Exact(name.to_string())));
+        }
+    }
+
+    #[test]
+    fn parses_keys_and_whitelist_section() {
+        let config = parse_config(
+            "# 注释\ninterval: 30\nthreshold: 900\ndry_run: on\n\
+             whitelist:\ncom.a, com.b:*\nre:com\\.c\\..*\n",
+        );
+        assert_eq!(config.interval, 30);
+        assert_eq!(config.oom_threshold, 900);
+        assert!(config.dry_run);
+        assert!(config
+            .whitelist
+            .contains(&WhitelistRule::Exact("com.a".to_string())));
+        assert!(config
+            .whitelist
+            .contains(&WhitelistRule::Prefix("com.b".to_string())));
+        assert!(config
+            .whitelist
+            .iter()
+            .any(|r| matches!(r, WhitelistRule::Regex(re) if re.as_str() == "com\\.c\\..*")));
+    }
+
+    #[test]
+    fn invalid_values_keep_defaults() {
+        let config = parse_config("interval: soon\nmax_kills_per_cycle: many\n");
+        assert_eq!(config.interval, DEFAULT_INTERVAL);
+        assert_eq!(config.max_kills_per_cycle, 0);
+    }
+
+    #[test]
+    fn whitelist_rule_kinds() {
+        assert_eq!(
+            WhitelistRule::parse("com.foo:*"),
+            Some(WhitelistRule::Prefix("com.foo".to_string()))
+        );
+        assert_eq!(
+            WhitelistRule::parse("com.*.push"),
+            Some(WhitelistRule::Glob("com.*.push".to_string()))
+        );
+        assert_eq!(
+            WhitelistRule::parse("com.foo"),
+            Some(WhitelistRule::Exact("com.foo".to_string()))
+        );
+        assert_eq!(WhitelistRule::parse("re:("), None);
+        assert_eq!(
+            WhitelistRule::parse("uid:1010123"),
+            Some(WhitelistRule::Uid(1010123))
+        );
+        let scoped = WhitelistRule::parse("user:10/com.foo:*").unwrap();
+        assert_eq!(
+            scoped,
+            WhitelistRule::User(10, Box::new(WhitelistRule::Prefix("com.foo".to_string())))
+        );
+        assert_eq!(scoped.to_string(), "user:10/com.foo:*");
+    }
+
+    #[test]
+    fn toml_maps_onto_legacy_keys() {
+        let config = parse_toml_config(
+            "format = toml\ninterval = 30\ndry_run = true\nexclude_uids = [1000, 2000]\n\
+             whitelist = [\"com.a\", \"com.b:*\"]\n\
+             [tiers]\n512 = 800\n\
+             [policy.\"com.foo\"]\noom = 950\naction = \"freeze\"\n",
+        );
+        assert_eq!(config.interval, 30);
+        assert!(config.dry_run);
+        assert_eq!(config.exclude_uids, vec![(1000, 1000), (2000, 2000)]);
+        assert_eq!(config.tiers, vec![(512 * 1024, 800)]);
+        let policy = &config.policies["com.foo"];
+        assert_eq!(policy.oom, Some(950));
+        assert_eq!(policy.action, Some(CleanAction::Freeze));
+        assert!(config
+            .whitelist
+            .contains(&WhitelistRule::Prefix("com.b".to_string())));
+    }
+
+    #[test]
+    fn toml_supports_escapes_multiline_strings_and_array_tables() {
+        let config = parse_toml_config(
+            "log_path = \"/data/local/tmp/mc\\u002elog\"\n\
+             whitelist = [\n  \"com.a\",\n  \"\"\"com.b\"\"\"\n]\n\
+             [[schedule]]\nwindow = \"01:00-06:00\"\nthreshold = 500\n\
+             [[schedule]]\nwindow = \"09:00-18:00\"\nenabled = false\n",
+        );
+        assert_eq!(config.log_path.as_deref(), Some("/data/local/tmp/mc.log"));
+        assert!(config
+            .whitelist
+            .contains(&WhitelistRule::Exact("com.b".to_string())));
+        assert_eq!(config.schedule.len(), 2);
+        assert_eq!(config.schedule[0].threshold, Some(500));
+        assert!(!config.schedule[1].enabled);
+    }
+
+    #[test]
+    fn toml_errors_carry_line_numbers() {
+        let (config, warnings) = parse_config_text("a.toml", "interval = 30\nthreshold = \n");
+        assert_eq!(config.interval, DEFAULT_INTERVAL);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("line 2:"), "{:?}", warnings);
+
+        let (_, warnings) =
+            parse_config_text("a.toml", "interval = 30\n\n[policy.\"com.a\"]\nom = 950\n");
+        assert!(warnings[0].starts_with("line 4:"), "{:?}", warnings);
+
+        let (_, warnings) = parse_config_text("a.toml", "interval = 30\nintervl = 5\n");
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].starts_with("line 2: unknown setting"),
+            "{:?}",
+            warnings
+        );
+    }
+
+    #[test]
+    fn schedule_section_ends_at_next_key() {
+        let config =
+            parse_config("schedule:\n01:00-06:00 threshold=500\n09:00-18:00 off\ninterval: 45\n");
+        assert_eq!(config.schedule.len(), 2);
+        assert_eq!(config.schedule[0].threshold, Some(500));
+        assert!(!config.schedule[1].enabled);
+        assert_eq!(config.interval, 45);
+    }
+
+    #[test]
+    fn battery_rules_are_sorted_by_percent() {
+        let config = parse_config(
+            "on_charger: pause\nbattery_below: 20 -> threshold 600\nbattery_below: 10 -> threshold 400, 5 -> interval 10\n",
+        );
+        assert_eq!(config.on_charger, ChargerPolicy::Pause);
+        let below: Vec<u8> = config.battery_rules.iter().map(|r| r.below).collect();
+        assert_eq!(below, vec![5, 10, 20]);
+    }
+
+    #[test]
+    fn detects_toml_by_extension_or_header() {
+        assert_eq!(ConfigFormat::detect("a.toml", ""), ConfigFormat::Toml);
+        assert_eq!(
+            ConfigFormat::detect("a.conf", "# x\n\nformat = \"toml\"\n"),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::detect("a.conf", "interval: 30\nformat = toml\n"),
+            ConfigFormat::Legacy
+        );
+    }
+
+    #[test]
+    fn unknown_settings_are_reported() {
+        let before = errlog::reported();
+        let config = parse_config("interval: 30\nintervl: 5\n");
+        assert_eq!(config.interval, 30);
+        assert!(errlog::reported() > before);
+    }
+
+    #[test]
+    fn threshold_stays_in_killable_range() {
+        let (config, warnings) = parse_config_text("a.conf", "threshold: 0\n");
+        assert_eq!(config.oom_threshold, OOM_SCORE_THRESHOLD);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(parse_config("threshold: 200\n").oom_threshold, 200);
+    }
+
+    #[test]
+    fn hard_threshold_stays_in_killable_range() {
+        let (config, warnings) = parse_config_text("a.conf", "hard_threshold: -1000\n");
+        assert_eq!(config.hard_threshold, None);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            parse_config("hard_threshold: 950\n").hard_threshold,
+            Some(950)
+        );
+    }
+
+    #[test]
+    fn screen_off_threshold_stays_in_killable_range() {
+        let (config, warnings) = parse_config_text("a.conf", "screen_off_threshold: 100\n");
+        assert_eq!(config.screen_off_threshold, SCREEN_OFF_THRESHOLD);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            parse_config("screen_off_threshold: 500\n").screen_off_threshold,
+            500
+        );
+    }
+
+    #[test]
+    fn policy_oom_stays_in_killable_range() {
+        let (config, warnings) =
+            parse_config_text("a.conf", "policy: com.a oom=0\npolicy: com.b oom=950\n");
+        assert_eq!(config.policies["com.a"].oom, None);
+        assert_eq!(config.policies["com.b"].oom, Some(950));
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn tier_threshold_stays_in_killable_range() {
+        let (config, warnings) = parse_config_text("a.conf", "tiers:\n300: 100\n500: 600\n");
+        assert_eq!(config.tiers, vec![(500 * 1024, 600)]);
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    }
+
+    #[test]
+    fn control_socket_is_opt_in() {
+        assert_eq!(parse_config("interval: 30\n").control_socket, None);
+        assert_eq!(
+            parse_config("control_socket: on\n")
+                .control_socket
+                .as_deref(),
+            Some(control::DEFAULT_SOCKET_PATH)
+        );
+        assert_eq!(
+            parse_config("control_socket: /dev/mc.sock\n")
+                .control_socket
+                .as_deref(),
+            Some("/dev/mc.sock")
+        );
+    }
+
+    #[test]
+    fn parse_config_text_returns_warnings() {
+        let (config, warnings) =
+            parse_config_text("a.conf", "interval: 30\nintervl: 5\nwhitelist:\nre:(\n");
+        assert_eq!(config.interval, 30);
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[0].starts_with("line 2: unknown setting"));
+        let (_, warnings) = parse_config_text("a.toml", "interval = 30\n");
+        assert!(warnings.is_empty());
+    }
+}
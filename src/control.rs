@@ -0,0 +1,155 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::time::Duration;
+
+use crate::protocol::{CommandError, ErrorCode, Request};
+
+/// `control_socket: on` 使用的路径；控制 socket 默认关闭，需在配置中显式开启
+pub const DEFAULT_SOCKET_PATH: &str = "/data/local/tmp/mem_cleaner.sock";
+// 谁能连上由 SO_PEERCRED 与 token 决定，文件权限放开才能让 shell 与 control_uids 中的 App 连接
+const SOCKET_MODE: u32 = 0o666;
+// 单个客户端最多占用主循环这么久
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 控制接口的访问控制：对端 UID 必须在允许列表中，配置了 token 时还要求携带 token
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    pub token: Option<String>,
+    pub allowed_uids: Vec<(u32, u32)>, // 除 root/shell 外允许的 UID 闭区间（如伴生 App）
+}
+
+const ROOT_UID: u32 = 0;
+const SHELL_UID: u32 = 2000;
+
+impl AccessPolicy {
+    pub fn uid_allowed(&self, uid: Option<u32>) -> bool {
+        uid.is_some_and(|uid| {
+            uid == ROOT_UID
+                || uid == SHELL_UID
+                || self
+                    .allowed_uids
+                    .iter()
+                    .any(|&(lo, hi)| (lo..=hi).contains(&uid))
+        })
+    }
+
+    /// 未配置 token 时总是通过；比较耗时与内容无关，避免按字节猜出 token
+    pub fn token_matches(&self, token: Option<&str>) -> bool {
+        match (self.token.as_deref(), token) {
+            (None, _) => true,
+            (Some(expected), Some(given)) => {
+                constant_time_eq(expected.as_bytes(), given.as_bytes())
+            }
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// 定长比较：只在长度不同时提前返回
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 控制 socket 服务端：每个连接发送一行命令，收到一段回复后关闭
+///
+/// 配置了 token 时命令行格式为 "token=<secret> <command>"；请求与回复格式见 [`crate::protocol`]
+pub struct ControlServer {
+    listener: UnixListener,
+    access: AccessPolicy,
+}
+
+impl ControlServer {
+    pub fn bind(path: &str, access: AccessPolicy) -> io::Result<Self> {
+        // 上次非正常退出残留的 socket 文件会导致 bind 失败
+        let _ = fs::remove_file(path);
+        let listener = UnixListener::bind(path)?;
+        fs::set_permissions(path, fs::Permissions::from_mode(SOCKET_MODE))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener, access })
+    }
+
+    /// 接受一个连接并解析请求；没有待处理连接时返回 None，鉴权失败时请求中带上错误
+    ///
+    /// 对端 UID 不在允许列表时不读任何数据，直接以旧格式回复 Forbidden
+    pub fn accept(&self) -> Option<(UnixStream, Request)> {
+        let (stream, _) = self.listener.accept().ok()?;
+        stream.set_nonblocking(false).ok()?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+        let uid = peer_uid(&stream);
+        if !self.access.uid_allowed(uid) {
+            let uid = uid.map_or_else(|| "unknown".to_string(), |u| u.to_string());
+            let error = CommandError::new(ErrorCode::Forbidden, format!("uid {} not allowed", uid));
+            return Some((stream, Request::rejected(None, error)));
+        }
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).ok()?;
+        let line = line.trim();
+
+        let (token, line) = match line.strip_prefix("token=").and_then(|l| l.split_once(' ')) {
+            Some((t, rest)) => (Some(t), rest.trim()),
+            None => (None, line),
+        };
+        let request = Request::parse(line);
+        if !self.access.token_matches(token) {
+            let error = CommandError::new(ErrorCode::Unauthorized, "invalid token");
+            return Some((stream, Request::rejected(request.version, error)));
+        }
+        Some((stream, request))
+    }
+}
+
+impl AsFd for ControlServer {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.listener.as_fd()
+    }
+}
+
+/// 通过 SO_PEERCRED 取对端进程的 UID
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (ret == 0).then_some(cred.uid)
+}
+
+pub fn respond(mut stream: UnixStream, reply: &str) {
+    let _ = stream.write_all(reply.as_bytes());
+    if !reply.ends_with('\n') {
+        let _ = stream.write_all(b"\n");
+    }
+}
+
+/// 客户端：向运行中的守护进程发送一条命令并返回回复
+pub fn send_command(path: &str, token: Option<&str>, command: &str) -> io::Result<String> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    if let Some(token) = token {
+        write!(stream, "token={} ", token)?;
+    }
+    stream.write_all(command.as_bytes())?;
+    stream.write_all(b"\n")?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
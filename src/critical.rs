@@ -0,0 +1,90 @@
+use fxhash::FxHashSet;
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::widgets::current_launcher;
+
+const CRITICAL_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// 杀掉后用户马上会察觉的默认应用：桌面、输入法、短信、拨号与已启用的无障碍服务
+///
+/// 与用户白名单分开维护：用户换了默认输入法或桌面，下次刷新即跟着变
+#[derive(Default)]
+pub struct CriticalApps {
+    packages: FxHashSet<String>,
+    last_refresh: Option<Instant>,
+}
+
+impl CriticalApps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&mut self, package: &str) -> bool {
+        if self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= CRITICAL_CACHE_TTL)
+        {
+            self.refresh();
+        }
+        self.packages.contains(package)
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        let mut packages = FxHashSet::default();
+        packages.extend(current_launcher());
+        for key in [
+            "default_input_method",
+            "sms_default_application",
+            "dialer_default_application",
+            "enabled_accessibility_services",
+        ] {
+            if let Some(value) = secure_setting(key) {
+                packages.extend(component_packages(&value));
+            }
+        }
+        // 部分 ROM 不写 dialer_default_application，以 telecom 为准
+        if let Ok(output) = Command::new("telecom").arg("get-default-dialer").output() {
+            packages.extend(component_packages(&String::from_utf8_lossy(&output.stdout)));
+        }
+        self.packages = packages;
+    }
+}
+
+fn secure_setting(key: &str) -> Option<String> {
+    let output = Command::new("settings")
+        .args(["get", "secure", key])
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// "com.foo/.Ime" 或以 ':' 分隔的多个组件（无障碍服务）→ 包名；未设置时为 "null"
+fn component_packages(value: &str) -> Vec<String> {
+    value
+        .split(':')
+        .map(|c| c.split('/').next().unwrap_or("").trim())
+        .filter(|p| !p.is_empty() && *p != "null" && p.contains('.'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_packages_from_components() {
+        assert_eq!(
+            component_packages("com.foo.ime/.LatinIME\n"),
+            vec!["com.foo.ime"]
+        );
+        assert_eq!(
+            component_packages("com.a11y/com.a11y.Svc:com.talk/.Back"),
+            vec!["com.a11y", "com.talk"]
+        );
+        assert!(component_packages("null\n").is_empty());
+    }
+}
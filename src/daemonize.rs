@@ -0,0 +1,117 @@
+//! 单实例与后台运行：pid 文件上加 flock 排他锁，持锁期间其他实例拒绝启动
+//!
+//! 锁随进程退出由内核释放，异常退出也不会留下失效的锁；文件里的 pid 只是给人和脚本看的。
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+
+pub const DEFAULT_PID_FILE: &str = "/data/local/tmp/mem_cleaner.pid";
+
+/// 加锁失败的原因
+#[derive(Debug)]
+pub enum LockError {
+    Running(Option<i32>), // 另一个实例持有锁，附带文件中记录的 pid
+    Io(io::Error),
+}
+
+/// 持有锁的 pid 文件；drop 时清空内容
+///
+/// 不删除文件：删除后，正在等锁的实例可能锁住已脱离目录的旧 inode，与新建文件的实例同时运行
+pub struct PidFile {
+    file: File,
+}
+
+impl PidFile {
+    pub fn acquire(path: &str) -> Result<Self, LockError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .mode(0o644)
+            .custom_flags(libc::O_CLOEXEC)
+            .open(path)
+            .map_err(LockError::Io)?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EWOULDBLOCK) {
+                return Err(LockError::Io(err));
+            }
+            let mut content = String::new();
+            let _ = file.read_to_string(&mut content);
+            return Err(LockError::Running(content.trim().parse().ok()));
+        }
+        // umask 可能让文件对 shell 不可读
+        let _ = file.set_permissions(PermissionsExt::from_mode(0o644));
+        Ok(Self { file })
+    }
+
+    /// 写入当前 pid；转入后台之后再调用，记下的才是守护进程自己
+    pub fn write_pid(&mut self) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        writeln!(self.file, "{}", std::process::id())
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+    }
+}
+
+/// 转入后台：两次 fork 并 setsid，脱离控制终端，标准输入输出改到 /dev/null
+///
+/// 必须在创建任何线程之前调用。不切换到 /，配置里的相对路径仍按启动目录解析；
+/// 已持有的 pid 文件锁由子进程继承。
+pub fn daemonize() -> io::Result<()> {
+    fork_and_exit_parent()?;
+    if unsafe { libc::setsid() } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // 会话首进程退出，之后打开终端设备也不会再成为控制终端
+    fork_and_exit_parent()?;
+
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+    for fd in 0..3 {
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn fork_and_exit_parent() -> io::Result<()> {
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(()),
+        _ => unsafe { libc::_exit(0) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_instance_is_refused() {
+        let path = std::env::temp_dir().join(format!("mc_pid_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut first = PidFile::acquire(path).unwrap();
+        first.write_pid().unwrap();
+
+        // flock 按打开的文件描述区分，同一进程里再打开一次也会冲突
+        match PidFile::acquire(path) {
+            Err(LockError::Running(pid)) => assert_eq!(pid, Some(std::process::id() as i32)),
+            other => panic!("expected Running, got {:?}", other.map(|_| ())),
+        }
+
+        drop(first);
+        assert!(PidFile::acquire(path).is_ok());
+        let _ = std::fs::remove_file(path);
+    }
+}
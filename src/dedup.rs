@@ -0,0 +1,99 @@
+use fxhash::FxHashMap;
+use time::Date;
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::errlog::{self, Level};
+
+/// 同一进程当天被清理超过这么多次后，不再逐条记录，改为汇总
+pub const KILL_LOG_DEDUP_AFTER: u32 = 3;
+const WARN_INTERVAL: Duration = Duration::from_secs(600);
+
+/// 按天统计每个进程名的清理次数，用于合并重复的"已清理"日志
+#[derive(Debug, Clone, Default)]
+pub struct KillDedup {
+    date: Option<Date>,
+    counts: FxHashMap<String, u32>,
+}
+
+impl KillDedup {
+    /// 换日时清零，返回前一天被合并的条目
+    pub fn roll(&mut self, today: Date) -> Option<(Date, Vec<(String, u32)>)> {
+        let previous = self.date.replace(today).filter(|d| *d != today)?;
+        let collapsed = self.collapsed();
+        self.counts.clear();
+        (!collapsed.is_empty()).then_some((previous, collapsed))
+    }
+
+    /// 记一次清理，返回当天累计次数
+    pub fn record(&mut self, name: &str) -> u32 {
+        let count = self.counts.entry(name.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// 当天次数超过阈值（有记录被省略）的进程，按次数从多到少
+    pub fn collapsed(&self) -> Vec<(String, u32)> {
+        let mut collapsed: Vec<(String, u32)> = self
+            .counts
+            .iter()
+            .filter(|(_, &n)| n > KILL_LOG_DEDUP_AFTER)
+            .map(|(name, &n)| (name.clone(), n))
+            .collect();
+        collapsed.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        collapsed
+    }
+}
+
+struct WarnEntry {
+    key: String,
+    last: Instant,
+    suppressed: u32,
+}
+
+static WARNINGS: Mutex<Vec<WarnEntry>> = Mutex::new(Vec::new());
+
+/// 限频输出警告：同一 key 每 10 分钟最多打印一次，并带上期间省略的次数
+pub fn warn(key: &str, message: &str) {
+    limited(Level::Warn, key, message);
+}
+
+/// 同 warn，但在错误日志中记为 ERROR
+pub fn error(key: &str, message: &str) {
+    limited(Level::Error, key, message);
+}
+
+fn limited(level: Level, key: &str, message: &str) {
+    let Ok(mut entries) = WARNINGS.lock() else {
+        return;
+    };
+    let now = Instant::now();
+    match entries.iter_mut().find(|e| e.key == key) {
+        Some(entry) if now.duration_since(entry.last) < WARN_INTERVAL => entry.suppressed += 1,
+        Some(entry) => {
+            if entry.suppressed > 0 {
+                errlog::report(
+                    level,
+                    key,
+                    &format!(
+                        "{} (suppressed {} similar warnings)",
+                        message, entry.suppressed
+                    ),
+                );
+            } else {
+                errlog::report(level, key, message);
+            }
+            entry.last = now;
+            entry.suppressed = 0;
+        }
+        None => {
+            errlog::report(level, key, message);
+            entries.push(WarnEntry {
+                key: key.to_string(),
+                last: now,
+                suppressed: 0,
+            });
+        }
+    }
+}
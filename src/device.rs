@@ -0,0 +1,125 @@
+//! 设备空闲（Doze）状态：后台线程定期查询并缓存，主循环只读缓存，不在清理路径上 fork
+//!
+//! 一次 `dumpsys deviceidle` 即可拿到深度与轻度 Doze 状态；解析不了时退回
+//! `cmd deviceidle get deep`。缓存按 boottime 计时，休眠醒来后旧结果会被视为过期。
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::{boottime_secs, dedup};
+
+pub const DEFAULT_DOZE_CACHE_SECS: u64 = 30;
+
+#[derive(Default)]
+struct Shared {
+    deep: AtomicBool,
+    checked_at: AtomicU64, // boottime 秒，0 表示还没有结果
+    pending: AtomicBool,   // 已请求刷新、后台线程尚未完成
+}
+
+/// 设备状态缓存；查询线程在第一次读取时启动
+#[derive(Default)]
+pub struct DeviceState {
+    shared: Arc<Shared>,
+    refresh: Option<Sender<()>>,
+}
+
+impl DeviceState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否处于深度 Doze；缓存超过 ttl 时请求后台刷新并先返回旧值，
+    /// 超过两倍 ttl（多半刚从休眠醒来）或尚无结果时按未空闲处理
+    pub fn is_deep_idle(&mut self, ttl_secs: u64) -> bool {
+        let now = boottime_secs();
+        let checked_at = self.shared.checked_at.load(Ordering::Acquire);
+        let age = now.saturating_sub(checked_at);
+        if checked_at == 0 || age >= ttl_secs {
+            self.request_refresh();
+        }
+        checked_at != 0
+            && age < ttl_secs.saturating_mul(2)
+            && self.shared.deep.load(Ordering::Acquire)
+    }
+
+    fn request_refresh(&mut self) {
+        if self.shared.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        if self.refresh.is_none() {
+            self.refresh = spawn_worker(Arc::clone(&self.shared));
+        }
+        if self.refresh.as_ref().is_none_or(|tx| tx.send(()).is_err()) {
+            self.shared.pending.store(false, Ordering::Release);
+        }
+    }
+}
+
+fn spawn_worker(shared: Arc<Shared>) -> Option<Sender<()>> {
+    let (tx, rx) = mpsc::channel::<()>();
+    let spawned = thread::Builder::new()
+        .name("deviceidle".to_string())
+        .spawn(move || {
+            for () in rx {
+                let deep = query_deep_idle();
+                shared.deep.store(deep, Ordering::Release);
+                shared
+                    .checked_at
+                    .store(boottime_secs().max(1), Ordering::Release);
+                shared.pending.store(false, Ordering::Release);
+            }
+        });
+    match spawned {
+        Ok(_) => Some(tx),
+        Err(e) => {
+            dedup::error("doze", &format!("Failed to start deviceidle thread: {}", e));
+            None
+        }
+    }
+}
+
+fn query_deep_idle() -> bool {
+    if let Ok(output) = Command::new("dumpsys").arg("deviceidle").output() {
+        if let Some(deep) = parse_deviceidle(&String::from_utf8_lossy(&output.stdout)) {
+            return deep;
+        }
+    }
+    Command::new("cmd")
+        .args(["deviceidle", "get", "deep"])
+        .output()
+        .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).trim() == "IDLE")
+}
+
+/// 从 dumpsys deviceidle 中取 mState（深度 Doze 状态机），IDLE 即深度空闲
+fn parse_deviceidle(dump: &str) -> Option<bool> {
+    dump.split_whitespace()
+        .find_map(|token| token.strip_prefix("mState="))
+        .map(|state| state == "IDLE")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_deep_state_from_dumpsys() {
+        let dump = "  mLightEnabled=true  mDeepEnabled=true\n  mState=IDLE mLightState=OVERRIDE\n";
+        assert_eq!(parse_deviceidle(dump), Some(true));
+        assert_eq!(
+            parse_deviceidle("  mState=ACTIVE mLightState=IDLE\n"),
+            Some(false)
+        );
+        assert_eq!(parse_deviceidle("Can't find service: deviceidle\n"), None);
+    }
+
+    #[test]
+    fn no_result_counts_as_awake() {
+        // 查询在后台线程完成，第一次读取不会阻塞，也不会当作空闲
+        let mut device = DeviceState::new();
+        assert!(!device.is_deep_idle(DEFAULT_DOZE_CACHE_SECS));
+    }
+}
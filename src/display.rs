@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+// dumpsys 路径比较重，结果缓存一段时间
+const DUMPSYS_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// 亮屏检测的数据来源
+enum Backend {
+    Backlight(PathBuf), // .../brightness，非 0 即亮屏
+    Dpms(PathBuf),      // /sys/class/drm/<connector>/dpms，"On" 即亮屏
+    Dumpsys,            // dumpsys power 的 mWakefulness
+}
+
+/// 屏幕状态：优先读 sysfs（不 fork、不走 Binder），找不到可用节点时退回 dumpsys power
+pub struct ScreenState {
+    backend: Backend,
+    cached: Option<(Instant, bool)>,
+}
+
+impl ScreenState {
+    pub fn detect() -> Self {
+        let backend = find_backlight()
+            .map(Backend::Backlight)
+            .or_else(|| find_dpms().map(Backend::Dpms))
+            .unwrap_or(Backend::Dumpsys);
+        Self {
+            backend,
+            cached: None,
+        }
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self.backend {
+            Backend::Backlight(_) => "sysfs backlight",
+            Backend::Dpms(_) => "sysfs drm",
+            Backend::Dumpsys => "dumpsys power",
+        }
+    }
+
+    /// 屏幕是否点亮；读取失败时返回 None
+    pub fn is_on(&mut self) -> Option<bool> {
+        match &self.backend {
+            Backend::Backlight(path) => read_trimmed(path).map(|v| v != "0"),
+            Backend::Dpms(path) => read_trimmed(path).map(|v| v == "On"),
+            Backend::Dumpsys => {
+                if let Some((at, on)) = self.cached {
+                    if at.elapsed() < DUMPSYS_CACHE_TTL {
+                        return Some(on);
+                    }
+                }
+                let on = dumpsys_wakefulness()?;
+                self.cached = Some((Instant::now(), on));
+                Some(on)
+            }
+        }
+    }
+}
+
+fn read_trimmed(path: &PathBuf) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn find_backlight() -> Option<PathBuf> {
+    // 高通机型常见的 leds 节点放在前面
+    let mut candidates = vec![PathBuf::from("/sys/class/leds/lcd-backlight/brightness")];
+    if let Ok(entries) = fs::read_dir("/sys/class/backlight") {
+        for entry in entries.flatten() {
+            candidates.push(entry.path().join("brightness"));
+        }
+    }
+    candidates
+        .into_iter()
+        .find(|p| read_trimmed(p).is_some_and(|v| v.parse::<u32>().is_ok()))
+}
+
+fn find_dpms() -> Option<PathBuf> {
+    fs::read_dir("/sys/class/drm")
+        .ok()?
+        .flatten()
+        .map(|e| e.path().join("dpms"))
+        .find(|p| read_trimmed(p).is_some_and(|v| v == "On" || v == "Off"))
+}
+
+fn dumpsys_wakefulness() -> Option<bool> {
+    let output = Command::new("dumpsys").arg("power").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("mWakefulness="))
+        .map(|state| state == "Awake")
+}
@@ -0,0 +1,294 @@
+//! `mem_cleaner doctor [config_path]`：逐项检查运行环境，输出通过/失败及修复建议，
+//! 用户提问时贴出这一份报告即可
+
+use nix::sys::timerfd::{ClockId, TimerFd, TimerFlags};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use crate::config::{load_config, AppConfig, THRESHOLD_RANGE};
+use crate::freezer::Freezer;
+use crate::platform::Platform;
+
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn check(&mut self, status: Status, name: &str, detail: &str, fix: &str) {
+        let tag = match status {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => {
+                self.failures += 1;
+                "FAIL"
+            }
+        };
+        println!("[{}] {}: {}", tag, name, detail);
+        if !matches!(status, Status::Pass) && !fix.is_empty() {
+            println!("       fix: {}", fix);
+        }
+    }
+}
+
+/// 运行全部检查，有失败项时返回 false
+pub fn run(config_path: Option<&str>) -> bool {
+    let mut report = Report { failures: 0 };
+    println!("mem_cleaner {} doctor", env!("CARGO_PKG_VERSION"));
+
+    let uid = unsafe { libc::geteuid() };
+    report.check(
+        if uid == 0 { Status::Pass } else { Status::Fail },
+        "root",
+        &format!("euid {}", uid),
+        "run as root, e.g. su -c mem_cleaner ...",
+    );
+
+    match fs::read_to_string("/sys/fs/selinux/enforce").map(|s| s.trim() == "1") {
+        Ok(true) => report.check(
+            Status::Warn,
+            "selinux",
+            "enforcing",
+            "if /proc reads below fail, start from a Magisk/KernelSU service script so the daemon runs in the su domain",
+        ),
+        Ok(false) => report.check(Status::Pass, "selinux", "permissive", ""),
+        Err(_) => report.check(Status::Pass, "selinux", "not present", ""),
+    }
+
+    check_proc(&mut report);
+
+    // 写回当前值：只检验权限，不改变自身的 oom_score_adj
+    let writable = fs::read_to_string("/proc/self/oom_score_adj").and_then(|current| {
+        OpenOptions::new()
+            .write(true)
+            .open("/proc/self/oom_score_adj")?
+            .write_all(current.trim().as_bytes())
+    });
+    match writable {
+        Ok(()) => report.check(Status::Pass, "oom_score_adj", "writable", ""),
+        Err(e) => report.check(
+            Status::Fail,
+            "oom_score_adj",
+            &format!("not writable: {}", e),
+            "needs root with CAP_SYS_RESOURCE; gms_policy: demote and action: demote will not work",
+        ),
+    }
+
+    match TimerFd::new(ClockId::CLOCK_BOOTTIME, TimerFlags::empty()) {
+        Ok(_) => report.check(Status::Pass, "timerfd", "CLOCK_BOOTTIME available", ""),
+        Err(e) => report.check(
+            Status::Fail,
+            "timerfd",
+            &format!("CLOCK_BOOTTIME timerfd failed: {}", e),
+            "kernel too old or seccomp blocks timerfd_create",
+        ),
+    }
+
+    let mut freezer = Freezer::detect();
+    let platform = Platform::detect(&mut freezer);
+    report.check(
+        if platform.psi {
+            Status::Pass
+        } else {
+            Status::Warn
+        },
+        "psi",
+        if platform.psi {
+            "/proc/pressure/memory available"
+        } else {
+            "not available"
+        },
+        "kernel built without CONFIG_PSI; pressure-based triggers fall back to MemAvailable",
+    );
+    report.check(
+        if freezer.available() {
+            Status::Pass
+        } else {
+            Status::Warn
+        },
+        "cgroup freezer",
+        freezer.backend_name(),
+        "frozen-app detection disabled; frozen_action has no effect",
+    );
+
+    match config_path {
+        Some(path) => check_config(&mut report, path),
+        None => report.check(
+            Status::Warn,
+            "config",
+            "not checked",
+            "pass the config path: mem_cleaner doctor <config_path>",
+        ),
+    }
+
+    println!("{} check(s) failed", report.failures);
+    report.failures == 0
+}
+
+/// /proc 可见性：统计 App 进程（UID >= 10000）里有多少能读到 oom_score_adj
+fn check_proc(report: &mut Report) {
+    let Ok(dir) = fs::read_dir("/proc") else {
+        report.check(Status::Fail, "/proc", "cannot list", "mount procfs");
+        return;
+    };
+    let (mut apps, mut readable) = (0, 0);
+    for entry in dir.flatten() {
+        let path = entry.path();
+        let is_pid = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|s| s.bytes().all(|b| b.is_ascii_digit()));
+        if !is_pid || fs::metadata(&path).map_or(true, |m| m.uid() < 10000) {
+            continue;
+        }
+        apps += 1;
+        if fs::read(path.join("oom_score_adj")).is_ok() {
+            readable += 1;
+        }
+    }
+    let detail = format!("{}/{} app processes readable", readable, apps);
+    let status = if readable < apps {
+        Status::Fail
+    } else {
+        Status::Pass
+    };
+    report.check(
+        status,
+        "/proc",
+        &detail,
+        "/proc is mounted with hidepid or SELinux denies access; run as root in the su domain",
+    );
+}
+
+fn check_config(report: &mut Report, path: &str) {
+    if let Err(e) = fs::read_to_string(path) {
+        report.check(
+            Status::Fail,
+            "config",
+            &format!("cannot read {}: {}", path, e),
+            "check the path and permissions",
+        );
+        return;
+    }
+    // 格式错误的项在加载时已单独打印
+    let config = load_config(path);
+    let problems = config_problems(&config);
+    if problems.is_empty() {
+        report.check(
+            Status::Pass,
+            "config",
+            &format!(
+                "{} loaded, {} whitelist rules",
+                path,
+                config.whitelist.len()
+            ),
+            "",
+        );
+    } else {
+        report.check(
+            Status::Fail,
+            "config",
+            &problems.join("; "),
+            "edit the config file",
+        );
+    }
+
+    match &config.log_path {
+        Some(log) => check_writable(report, "log path", log),
+        None => report.check(Status::Pass, "log path", "logging disabled", ""),
+    }
+    if let Some(path) = &config.pid_file {
+        check_writable(report, "pid file", path);
+    }
+    if let Some(socket) = &config.control_socket {
+        match Path::new(socket).parent() {
+            Some(dir) => check_dir_writable(report, "control socket", dir),
+            None => report.check(
+                Status::Fail,
+                "control socket",
+                socket,
+                "use an absolute path",
+            ),
+        }
+    }
+}
+
+/// 各项单独合法、组合起来却不对的配置
+pub fn config_problems(config: &AppConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if config.interval == 0 {
+        problems.push("interval must be > 0".to_string());
+    }
+    if !THRESHOLD_RANGE.contains(&config.oom_threshold) {
+        problems.push(format!(
+            "threshold {} out of {}..={}",
+            config.oom_threshold,
+            THRESHOLD_RANGE.start(),
+            THRESHOLD_RANGE.end()
+        ));
+    }
+    if let Some(t) = config.mem_trigger {
+        if t.exit_kb <= t.enter_kb {
+            problems.push("mem_exit_mb must be above mem_enter_mb".to_string());
+        }
+    }
+    if config.adaptive_interval && config.min_interval > config.max_interval {
+        problems.push("min_interval must not exceed max_interval".to_string());
+    }
+    problems
+}
+
+/// 以追加方式打开；原本不存在的文件检查完即删除
+fn check_writable(report: &mut Report, name: &str, path: &str) {
+    // 按日期分文件的模板只检查所在目录
+    if path.contains('%') {
+        if let Some(dir) = Path::new(path).parent() {
+            check_dir_writable(report, name, dir);
+        }
+        return;
+    }
+    let existed = Path::new(path).exists();
+    match OpenOptions::new().append(true).create(true).open(path) {
+        Ok(_) => {
+            if !existed {
+                let _ = fs::remove_file(path);
+            }
+            report.check(Status::Pass, name, &format!("{} writable", path), "");
+        }
+        Err(e) => report.check(
+            Status::Fail,
+            name,
+            &format!("{}: {}", path, e),
+            "create the directory or choose a path under /data/local/tmp",
+        ),
+    }
+}
+
+fn check_dir_writable(report: &mut Report, name: &str, dir: &Path) {
+    let probe = dir.join(".mem_cleaner_doctor");
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            report.check(
+                Status::Pass,
+                name,
+                &format!("{} writable", dir.display()),
+                "",
+            );
+        }
+        Err(e) => report.check(
+            Status::Fail,
+            name,
+            &format!("{}: {}", dir.display(), e),
+            "create the directory or choose a path under /data/local/tmp",
+        ),
+    }
+}
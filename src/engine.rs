@@ -0,0 +1,2261 @@
+//! 清理引擎：扫描漏斗、排序截取与击杀；配置见 [`crate::config`]，日志见 [`crate::logfile`]，
+//! /proc 读取经由 [`crate::proc::ProcSource`]，测试时换成假进程表
+
+use fxhash::{FxHashMap, FxHashSet};
+use itoa::Buffer as ItoaBuffer;
+use nix::errno::Errno;
+use nix::sys::signal::{kill, Signal};
+
+use nix::unistd::Pid;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::abi::{self, Abi32Policy};
+use crate::battery::BatteryDrain;
+use crate::bindings::SystemBindings;
+use crate::boottime_secs;
+use crate::config::{
+    parse_bool, AppConfig, CleanAction, FrozenAction, GmsPolicy, KillScope, OomMetric,
+    SurvivorAction, WhitelistRule, BUILTIN_WHITELIST,
+};
+use crate::critical::CriticalApps;
+use crate::events::Event;
+use crate::freezer::{Freezer, FrozenApps, FrozenCgroup, FrozenProc};
+use crate::history::{
+    KillHistory, KillThrottle, RecentApps, RelaunchTracker, RespawnTracker, SurvivorTracker,
+    SwitchGrace,
+};
+use crate::identity::{self, PackageList};
+use crate::logfile::Logger;
+use crate::media::MediaActivity;
+use crate::meminfo::{AppMeminfo, MeminfoSampler};
+use crate::memory::ProcMemory;
+use crate::namespace::{HostNamespaces, NamespacePolicy};
+use crate::policy::{
+    is_gms_process, is_in_whitelist, may_be_whitelisted, survivor_action, whitelist_match,
+};
+use crate::power::{BatteryRule, ChargerPolicy, PowerSupply};
+use crate::proc::ProcSource;
+use crate::procevents::ProcEvent;
+use crate::procstats::CachedSquatters;
+use crate::schedule::{self, ScheduleWindow};
+use crate::score::{ScoreInputs, StandbyBuckets};
+use crate::script::{ScriptAction, ScriptCandidate};
+use crate::session::SESSION;
+use crate::stats::StatsTracker;
+use crate::table::{ProcStat, ProcessTable};
+use crate::whitelist::WhitelistHits;
+use crate::widgets::WidgetProviders;
+use crate::{dedup, events, history, memory, power, quirks, whitelist};
+
+// --- 常量配置 ---
+const MIN_KILLABLE_OOM: i32 = 200; // 打分/目标模式下 adj 低于此值（前台/可见/可感知）的进程永不参与
+const GMS_DEMOTE_ADJ: i32 = 999;
+const FOREGROUND_APP_ADJ: i32 = 0;
+const PERCEPTIBLE_APP_ADJ: i32 = 200; // 以下为前台与可见进程
+const CACHED_APP_MIN_ADJ: i32 = 900; // 缓存（后台不可见）进程的 adj 下限
+const PERSISTENT_PROC_ADJ: i32 = -800; // 系统标记为 persistent 的进程，-700 为其绑定的服务
+pub const PAUSE_PROPERTY: &str = "persist.mem_cleaner.pause"; // 设为 1 时暂停清理，便于脚本/Tasker 控制
+const PAUSE_PROPERTY_TTL_SECS: u64 = 30; // getprop 要 fork，缓存这么久再查
+
+// --- 结构体定义 ---
+/// 通过漏斗筛选的待清理进程
+pub struct Candidate {
+    pub pid: i32,
+    pub uid: u32,
+    pub name: String,
+    pub argv: Option<String>, // 完整命令行，仅 verbose_log 时采集
+    pub oom: i32,
+    pub mem: ProcMemory,
+    pub background_secs: u64,
+    pub drain_mah: f32,
+    pub frozen: Option<FrozenCgroup>, // 需要先解冻再杀
+    pub start_time: u64,              // 与 pid 一起识别同一个进程
+    pub score: f32,
+    pub is_32bit: bool,
+    pub forced: bool, // blacklist / blacklist-once 点名，不受阈值、打分和停手条件限制，处理方式仍看 action
+    pub action: CleanAction,
+}
+
+/// 跨清理周期保留的运行状态
+pub struct CleanerState {
+    pub table: ProcessTable,
+    pub history: KillHistory,
+    pub buckets: StandbyBuckets,
+    pub battery: Option<BatteryDrain>,
+    pub squatters: CachedSquatters,
+    pub meminfo: Option<MeminfoSampler>,
+    pub widgets: Option<WidgetProviders>,
+    pub critical: Option<CriticalApps>,
+    pub media: Option<MediaActivity>,
+    pub packages: PackageList,
+    pub bindings: Option<SystemBindings>,
+    pub respawn: RespawnTracker,
+    pub relaunch: RelaunchTracker,
+    pub throttle: KillThrottle,
+    pub switch_grace: SwitchGrace,
+    pub recent_apps: RecentApps,
+    pub survivors: SurvivorTracker,
+    pub whitelist_hits: WhitelistHits,
+    pub last_freed_kb: u64,              // 上一轮清理释放的内存
+    pub skipping: bool,                  // 正处于"内存充足跳过"状态，只在进入时记一次日志
+    pub pressure_active: bool,           // 滞回触发当前是否处于清理区间
+    pub paused_until: Option<u64>, // pause 命令设置的恢复时刻（boottime 秒），u64::MAX 表示直到 resume
+    pub oem_killers: Vec<(i32, String)>, // 启动时发现的厂商清理服务
+    pub yielding: bool,            // 正在给厂商清理服务让路，只在进入时记一次日志
+    pub hidden: FxHashMap<i32, u32>, // hidepid 下无权读取的进程（pid → uid），不再反复重试
+    pub host_ns: Option<HostNamespaces>, // 不区分命名空间时为 None
+    pub foreign_ns: FxHashMap<u64, u32>, // 上一轮看到的其他命名空间（inode → 进程数）
+    pub kill_once: FxHashSet<String>, // blacklist-once 点名的包，下一轮清理（含演练）后即忘记；暂停中的扫描与 candidates 预览不算
+    pub protected: usize,             // 上一轮被压低 oom_score_adj 的白名单进程数
+    pub frozen: FrozenApps,           // 冻结模式下冻结的进程
+    pub stats: StatsTracker,
+    pub screen_off: bool, // 最近一次检测到熄屏；未启用熄屏策略时始终为 false
+    pub schedule: Option<ScheduleWindow>, // 本轮所在的 schedule 时段
+    pub charger_pause: bool, // 接着电源且 on_charger: pause
+    pub battery_rule: Option<BatteryRule>, // 本轮电量落入的 battery_below 档
+    pub pause_property: PauseProperty,
+    pub kill_queue: Option<KillQueue>, // kill_delay_ms 下本轮还没轮到的候选
+}
+
+/// 间隔击杀时一轮清理剩下的部分：主循环在 kill_delay_ms 后用 continue_cleanup 接着处理，
+/// 期间不阻塞其他事件；新一轮完整清理会重新扫描并丢弃它
+pub struct KillQueue {
+    candidates: Vec<Candidate>,
+    avail_kb: Option<u64>,
+    acted: usize, // 本轮已处理数，继续计入每轮上限
+    freed_kb: u64,
+    log: CycleLog,
+}
+
+/// 一批待处理的候选及其所属清理轮次的上下文
+struct Batch {
+    candidates: Vec<Candidate>,
+    avail_kb: Option<u64>,
+    acted: usize,
+    freed_kb: u64,
+    scan: ScanReport,
+    started: Instant, // 本批开始的时刻，计入周期耗时统计
+    log: CycleLog,
+}
+
+/// 一轮清理的击杀记录：间隔击杀时随 kill_queue 延续，整轮处理完才写成一条日志
+struct CycleLog {
+    trigger: CycleTrigger,
+    avail_before_kb: Option<u64>,
+    considered: usize, // 扫描时的候选数，不随排队减少
+    started: Instant,
+    killed: Vec<KillRecord>,
+}
+
+impl CycleLog {
+    fn write(self, logger: &mut Option<Logger>, pressure_active: bool, deferred: usize) {
+        let Some(l) = logger else {
+            return;
+        };
+        if self.killed.is_empty() {
+            return;
+        }
+        let summary = CycleSummary {
+            trigger: match self.trigger {
+                CycleTrigger::Timer if pressure_active => "内存压力",
+                CycleTrigger::Timer => "定时",
+                CycleTrigger::Manual => "手动",
+                CycleTrigger::Resume => "休眠补做",
+                CycleTrigger::Psi => "PSI 压力",
+            },
+            avail_before_kb: self.avail_before_kb,
+            avail_after_kb: memory::read_mem_available_kb(),
+            considered: self.considered,
+            deferred,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        };
+        l.write_cleanup(&summary, &self.killed);
+    }
+}
+
+impl CleanerState {
+    pub fn new(config: &AppConfig) -> Self {
+        Self {
+            table: ProcessTable::default(),
+            history: KillHistory::default(),
+            buckets: StandbyBuckets::new(),
+            battery: (config.battery_stats
+                || config.score.as_ref().is_some_and(|w| w.battery != 0.0))
+            .then(BatteryDrain::new),
+            squatters: CachedSquatters::new(),
+            meminfo: config.dumpsys_meminfo.then(MeminfoSampler::new),
+            widgets: config.protect_widgets.then(WidgetProviders::new),
+            critical: config.protect_critical.then(CriticalApps::new),
+            media: config.protect_media.then(MediaActivity::new),
+            packages: PackageList::new(),
+            bindings: config.protect_bound_apps.then(SystemBindings::new),
+            respawn: RespawnTracker::new(config.respawn_fast_secs),
+            relaunch: RelaunchTracker::new(config.relaunch_window_secs),
+            throttle: KillThrottle::new(config.kill_cooldown_secs, config.storm_kills_per_hour),
+            switch_grace: SwitchGrace::new(config.switch_grace_secs),
+            recent_apps: RecentApps::new(config.protect_recent),
+            survivors: SurvivorTracker::new(),
+            whitelist_hits: WhitelistHits::load(
+                config.whitelist_stats.as_deref(),
+                whitelist::unix_secs(),
+            ),
+            last_freed_kb: 0,
+            skipping: false,
+            pressure_active: false,
+            paused_until: None,
+            oem_killers: config.quirks.find_oem_killers(),
+            yielding: false,
+            hidden: FxHashMap::default(),
+            host_ns: (config.namespaces != NamespacePolicy::All)
+                .then(HostNamespaces::detect)
+                .flatten(),
+            foreign_ns: FxHashMap::default(),
+            kill_once: FxHashSet::default(),
+            protected: 0,
+            frozen: FrozenApps::default(),
+            stats: StatsTracker::new(config.stats_report_secs, boottime_secs()),
+            screen_off: false,
+            schedule: None,
+            charger_pause: false,
+            battery_rule: None,
+            pause_property: PauseProperty::default(),
+            kill_queue: None,
+        }
+    }
+
+    /// 清理周期：低电量档优先，其次 schedule 时段，都没指定时按常规配置
+    pub fn interval(&self, config: &AppConfig) -> u64 {
+        self.battery_rule
+            .and_then(|r| r.interval)
+            .or_else(|| self.schedule.and_then(|w| w.interval))
+            .unwrap_or_else(|| config.interval_for(self.screen_off))
+    }
+
+    /// 本轮阈值：低电量档与 schedule 时段指定的 threshold 依次取代分档与熄屏阈值
+    pub fn threshold(&self, config: &AppConfig, avail_kb: Option<u64>) -> i32 {
+        self.battery_rule
+            .and_then(|r| r.threshold)
+            .or_else(|| self.schedule.and_then(|w| w.threshold))
+            .unwrap_or_else(|| config.threshold_for(avail_kb, self.screen_off))
+    }
+
+    /// 读取电源状态并更新充电暂停与低电量档；有变化时返回 true
+    fn update_power(&mut self, config: &AppConfig) -> bool {
+        let (charger_pause, rule) = if config.power_aware() {
+            let supply = PowerSupply::read();
+            (
+                config.on_charger == ChargerPolicy::Pause && supply.charging,
+                supply
+                    .capacity
+                    .and_then(|c| power::rule_for(&config.battery_rules, c)),
+            )
+        } else {
+            (false, None)
+        };
+        let changed = charger_pause != self.charger_pause || rule != self.battery_rule;
+        self.charger_pause = charger_pause;
+        self.battery_rule = rule;
+        changed
+    }
+
+    pub fn is_paused(&mut self, now: u64) -> bool {
+        match self.paused_until {
+            Some(until) if until > now => true,
+            Some(_) => {
+                self.paused_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// 配置热加载：按新配置开关各项采样器、更新时间窗口，进程表与击杀历史保留
+    pub fn apply_config(&mut self, config: &AppConfig) {
+        let want_battery =
+            config.battery_stats || config.score.as_ref().is_some_and(|w| w.battery != 0.0);
+        if want_battery != self.battery.is_some() {
+            self.battery = want_battery.then(BatteryDrain::new);
+        }
+        if config.dumpsys_meminfo != self.meminfo.is_some() {
+            self.meminfo = config.dumpsys_meminfo.then(MeminfoSampler::new);
+        }
+        if config.protect_widgets != self.widgets.is_some() {
+            self.widgets = config.protect_widgets.then(WidgetProviders::new);
+        }
+        if config.protect_critical != self.critical.is_some() {
+            self.critical = config.protect_critical.then(CriticalApps::new);
+        }
+        if config.protect_media != self.media.is_some() {
+            self.media = config.protect_media.then(MediaActivity::new);
+        }
+        if config.protect_bound_apps != self.bindings.is_some() {
+            self.bindings = config.protect_bound_apps.then(SystemBindings::new);
+        }
+        self.respawn.set_fast_secs(config.respawn_fast_secs);
+        self.relaunch.set_window_secs(config.relaunch_window_secs);
+        self.throttle
+            .set_limits(config.kill_cooldown_secs, config.storm_kills_per_hour);
+        self.switch_grace.set_grace_secs(config.switch_grace_secs);
+        self.recent_apps.set_capacity(config.protect_recent);
+        self.stats.set_report_secs(config.stats_report_secs);
+        let want_ns = config.namespaces != NamespacePolicy::All;
+        if want_ns != self.host_ns.is_some() {
+            self.host_ns = want_ns.then(HostNamespaces::detect).flatten();
+        }
+        self.oem_killers = config.quirks.find_oem_killers();
+    }
+
+    /// 应用一条进程事件：更新进程表的 pid 集合；退出或 exec 了新程序的进程丢掉按 pid 记录的跨周期状态
+    pub fn apply_proc_event(&mut self, event: ProcEvent) {
+        if let ProcEvent::Exec(pid) | ProcEvent::Exit(pid) = event {
+            self.hidden.remove(&pid);
+            self.frozen.remove(pid);
+        }
+        self.table.apply(event);
+    }
+
+    /// 清空击杀历史与重生退避（history clear）
+    pub fn reset_history(&mut self) {
+        self.history = KillHistory::default();
+        self.respawn = RespawnTracker::new(self.respawn.fast_secs());
+        self.relaunch = RelaunchTracker::new(self.relaunch.window_secs());
+        self.throttle.clear();
+        self.last_freed_kb = 0;
+    }
+
+    /// 进程表连同击杀历史写到 table_state（历史在旁边的 .history 文件）；节流同进程表
+    pub fn save_state(&mut self, path: &str, now: u64, force: bool) {
+        if self.table.save(path, now, force) {
+            self.history.save(&history::state_path(path), now);
+        }
+    }
+
+    /// 从 table_state 恢复进程表与击杀历史，返回两者各恢复的条目数
+    pub fn restore_state(&mut self, path: &str, procfs: &dyn ProcSource) -> (usize, usize) {
+        let table = self.table.restore(path, procfs);
+        let history = self
+            .history
+            .load(&history::state_path(path), boottime_secs());
+        (table, history)
+    }
+}
+
+/// 单个被清理进程的记录
+pub struct KillRecord {
+    pub pid: i32,
+    pub uid: u32,
+    pub oom: i32,
+    pub name: String,
+    pub argv: Option<String>,
+    pub mem: ProcMemory,
+    pub background_secs: u64,
+    pub drain_mah: f32,
+    pub meminfo: Option<AppMeminfo>,
+    pub is_32bit: bool,
+    pub graceful: bool,            // 发的是 SIGTERM
+    pub group: Vec<(i32, String)>, // kill_scope: package 时一并清理的同 UID 进程
+}
+
+/// 本轮清理的起因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleTrigger {
+    Timer,  // 定时周期
+    Manual, // clean 命令、触发文件、嵌入方调用
+    Resume, // 休眠醒来后的补做
+    Psi,    // PSI 触发器报告内存压力
+}
+
+/// 写在每轮清理记录开头的概况
+pub struct CycleSummary {
+    pub trigger: &'static str,
+    pub avail_before_kb: Option<u64>,
+    pub avail_after_kb: Option<u64>,
+    pub considered: usize, // 本轮候选数
+    pub deferred: usize,   // 超出每轮上限、留到下一轮的候选数
+    pub elapsed_ms: u64,
+}
+
+/// 进程名来源：cmdline 为空时依次退回 UID 对应的包名、comm、exe 链接
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameSource {
+    Cmdline,
+    Package, // packages.list 中 UID 唯一对应的包，进程后缀取自 comm，可能缺失
+    Comm,    // 内核截断为 15 字节，Android 只保留最后一个 '.' 之后的部分
+    Exe,
+}
+
+/// 扫描资源复用池
+pub struct ScannerResources {
+    pub path_buf: String,    // 复用路径字符串 "/proc/12345/..."
+    pub file_buf: Vec<u8>,   // 复用文件读取 buffer
+    pub cmdline_buf: String, // 复用 cmdline 解析 buffer
+    pub freezer: Freezer,
+}
+
+impl Default for ScannerResources {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScannerResources {
+    pub fn new() -> Self {
+        Self {
+            path_buf: String::with_capacity(64),
+            file_buf: Vec::with_capacity(128),
+            cmdline_buf: String::with_capacity(128),
+            freezer: Freezer::detect(),
+        }
+    }
+
+    /// 读取 argv0 到 cmdline_buf，为空或读取失败返回 false
+    /// 非 UTF-8 字节按 lossy 替换，保证这类进程仍能被分类和显示
+    pub fn read_cmdline(&mut self, procfs: &dyn ProcSource, pid_s: &str) -> bool {
+        if !self.read_proc_file(procfs, pid_s, "cmdline") {
+            return false;
+        }
+        let slice = self.file_buf.split(|&c| c == 0).next().unwrap_or(&[]);
+        self.cmdline_buf.clear();
+        self.cmdline_buf.push_str(&String::from_utf8_lossy(slice));
+        !self.cmdline_buf.is_empty()
+    }
+
+    /// 读取进程名到 cmdline_buf：正在 exec 的 App 和部分 native 守护进程 cmdline 为空，
+    /// 此时退回 packages.list 中该 UID 唯一的包名，再退回 comm、exe 链接的文件名
+    ///
+    /// 仍处于 zygote 域的进程正在特化，名字还不可信，返回 None 留到下一轮
+    pub fn read_name(
+        &mut self,
+        procfs: &dyn ProcSource,
+        pid_s: &str,
+        uid: u32,
+        packages: &mut PackageList,
+    ) -> Option<NameSource> {
+        if self.read_cmdline(procfs, pid_s) {
+            return Some(NameSource::Cmdline);
+        }
+
+        if self.read_proc_file(procfs, pid_s, "attr/current")
+            && identity::selinux_domain(&self.file_buf).is_some_and(identity::is_zygote_domain)
+        {
+            return None;
+        }
+
+        let comm = if self.read_proc_file(procfs, pid_s, "comm") {
+            String::from_utf8_lossy(&self.file_buf)
+                .trim_end_matches('\n')
+                .to_string()
+        } else {
+            String::new()
+        };
+        if uid >= 10000 {
+            if let Some(package) = packages.unique_package(uid) {
+                self.cmdline_buf = identity::process_name(package, &comm);
+                return Some(NameSource::Package);
+            }
+        }
+        if !comm.is_empty() {
+            self.cmdline_buf = comm;
+            return Some(NameSource::Comm);
+        }
+
+        self.path_buf.clear();
+        self.path_buf.push_str(pid_s);
+        self.path_buf.push_str("/exe");
+        let target = procfs.readlink(&self.path_buf)?;
+        let name = Path::new(&target).file_name()?.to_string_lossy();
+        if name.is_empty() {
+            return None;
+        }
+        self.cmdline_buf.clear();
+        self.cmdline_buf.push_str(&name);
+        Some(NameSource::Exe)
+    }
+
+    /// 读取 /proc/<pid>/<name> 到 file_buf
+    pub fn read_proc_file(&mut self, procfs: &dyn ProcSource, pid_s: &str, name: &str) -> bool {
+        self.path_buf.clear();
+        self.path_buf.push_str(pid_s);
+        self.path_buf.push('/');
+        self.path_buf.push_str(name);
+
+        self.file_buf.clear();
+        procfs.read(&self.path_buf, &mut self.file_buf).is_ok()
+    }
+}
+
+/// 定期报告长期未命中的白名单条目，并保存命中统计
+fn report_whitelist_hits(
+    config: &AppConfig,
+    logger: &mut Option<Logger>,
+    state: &mut CleanerState,
+) {
+    let unix_now = whitelist::unix_secs();
+    let period = config.whitelist_report_days * 86400;
+    let rules = config.whitelist.iter().filter(
+        |r| !matches!(r, WhitelistRule::Exact(name) if BUILTIN_WHITELIST.contains(&name.as_str())),
+    );
+    if let Some(unused) = state.whitelist_hits.unused_report(rules, period, unix_now) {
+        if let Some(l) = logger {
+            l.write_whitelist_report(&unused, config.whitelist_report_days);
+        }
+    }
+    if let Some(path) = &config.whitelist_stats {
+        state.whitelist_hits.save(path, unix_now, false);
+    }
+}
+
+fn read_stat(res: &mut ScannerResources, procfs: &dyn ProcSource, pid_s: &str) -> Option<ProcStat> {
+    if res.read_proc_file(procfs, pid_s, "stat") {
+        ProcStat::parse(&res.file_buf)
+    } else {
+        None
+    }
+}
+
+fn read_proc_i32(
+    res: &mut ScannerResources,
+    procfs: &dyn ProcSource,
+    pid_s: &str,
+    name: &str,
+) -> Option<i32> {
+    if !res.read_proc_file(procfs, pid_s, name) {
+        return None;
+    }
+    std::str::from_utf8(&res.file_buf).ok()?.trim().parse().ok()
+}
+
+/// 将 /proc/<pid>/cmdline 的 NUL 分隔参数拼成可读的一行
+fn full_argv(raw: &[u8]) -> String {
+    raw.split(|&c| c == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 用户仍在用或系统正依赖的包：小部件、播放/前台服务、系统绑定、刚切走或最近用过、刚被重新打开
+fn package_in_use(state: &mut CleanerState, uid: u32, package: &str, now: u64) -> bool {
+    // 桌面小部件的提供方
+    if let Some(widgets) = &mut state.widgets {
+        if widgets.contains(package) {
+            return true;
+        }
+    }
+    // 正在播放或有前台服务（下载、导航等）的 App
+    if let Some(media) = &mut state.media {
+        if media.contains(uid, package) {
+            return true;
+        }
+    }
+    // 系统持有连接的 App
+    if let Some(bindings) = &mut state.bindings {
+        if bindings.contains(package) {
+            return true;
+        }
+    }
+    // 刚切到后台的 App 多半还会被切回来；最近用过的几个包同样留着；
+    // 用户刚重新打开过的包处于冷却期
+    state.switch_grace.in_grace(package, now)
+        || state.recent_apps.contains(package)
+        || state.relaunch.cooldown_remaining(package, now).is_some()
+}
+
+/// 不论阈值与黑名单都不能清理的进程：never_touch、白名单、默认应用、不走常规击杀的 GMS
+/// 与 package_in_use
+///
+/// 与候选漏斗中的同名判定一致，同 UID 连带清理与 blacklist-once 共用
+fn is_protected(
+    config: &AppConfig,
+    state: &mut CleanerState,
+    name: &str,
+    uid: u32,
+    now: u64,
+) -> bool {
+    let package = history::package_name(name);
+    config.is_never_touched(name)
+        || is_in_whitelist(name, uid, &config.whitelist)
+        || state
+            .critical
+            .as_mut()
+            .is_some_and(|critical| critical.contains(package))
+        || (is_gms_process(name) && config.gms_policy != GmsPolicy::Normal)
+        || package_in_use(state, uid, package, now)
+}
+
+/// 与选中进程同 UID、同样可以清理的其他进程，按 pid 排列
+///
+/// 与候选选择用同一套保护（见 [`is_protected`]）；
+/// 另外 oom_score_adj 低于 min_adj（本轮阈值）或处于前台/可见范围的一律不动，
+/// 免得选中一个缓存的 :service 连带杀掉用户正在用的主进程
+fn package_group(
+    config: &AppConfig,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+    candidate: &Candidate,
+    min_adj: i32,
+    now: u64,
+) -> Vec<(i32, String)> {
+    let uid = candidate.uid;
+    let min_adj = min_adj.max(PERCEPTIBLE_APP_ADJ);
+    let mut itoa_buf = ItoaBuffer::new();
+    let mut group = Vec::new();
+    for pid in state.table.pids(procfs) {
+        let pid_s = itoa_buf.format(pid);
+        if pid == candidate.pid || procfs.uid(pid_s) != Ok(uid) {
+            continue;
+        }
+        // 读不到 adj 的同样不动
+        if read_proc_i32(res, procfs, pid_s, "oom_score_adj").is_none_or(|adj| adj < min_adj)
+            || !res.read_cmdline(procfs, pid_s)
+        {
+            continue;
+        }
+        let name = res.cmdline_buf.clone();
+        if is_protected(config, state, &name, uid, now) {
+            continue;
+        }
+        group.push((pid, name));
+    }
+    group.sort_unstable();
+    group
+}
+
+/// 写入 /proc/<pid>/oom_score_adj
+fn write_oom_adj(pid_s: &str, adj: i32) -> bool {
+    match fs::write(format!("/proc/{}/oom_score_adj", pid_s), adj.to_string()) {
+        Ok(()) => true,
+        // 进程刚好退出不算失败
+        Err(e) if e.raw_os_error() == Some(libc::ESRCH) || e.kind() == io::ErrorKind::NotFound => {
+            false
+        }
+        Err(e) => {
+            dedup::warn(
+                "oom_adj",
+                &format!("Failed to write oom_score_adj {} for {}: {}", adj, pid_s, e),
+            );
+            false
+        }
+    }
+}
+
+/// 阈值以下的白名单进程：按完整 cmdline 命中时压低 oom_score_adj
+fn protect_whitelisted(
+    config: &AppConfig,
+    res: &mut ScannerResources,
+    procfs: &dyn ProcSource,
+    pid_s: &str,
+    uid: u32,
+) -> bool {
+    let Some(adj) = config.whitelist_oom_adj else {
+        return false;
+    };
+    res.read_cmdline(procfs, pid_s)
+        && is_in_whitelist(&res.cmdline_buf, uid, &config.whitelist)
+        && write_oom_adj(pid_s, adj)
+}
+
+/// 核心清理逻辑：严格遵循“漏斗模型”进行极致性能过滤
+pub fn perform_cleanup(
+    config: &AppConfig,
+    logger: &mut Option<Logger>,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+    trigger: CycleTrigger,
+) -> usize {
+    let started = Instant::now();
+    // 重新扫描，上一轮没处理完的间隔击杀不再继续
+    drop_kill_queue(logger, state);
+    thaw_returned(logger, res, state, procfs);
+    let window = schedule::active(&config.schedule);
+    if window != state.schedule {
+        if let Some(l) = logger {
+            l.write_schedule(window.as_ref());
+        }
+        state.schedule = window;
+    }
+    if state.update_power(config) {
+        if let Some(l) = logger {
+            l.write_power(state.charger_pause, state.battery_rule.as_ref());
+        }
+    }
+    // 暂停期间（含 schedule 停用的时段与充电暂停）照常扫描但不动手，保持进程表的空闲/后台计时连续
+    if state.is_paused(boottime_secs())
+        || state.pause_property.is_set(boottime_secs())
+        || window.is_some_and(|w| !w.enabled)
+        || state.charger_pause
+    {
+        let mut scan = ScanReport::default();
+        select_candidates(config, res, state, procfs, None, true, &mut scan);
+        return 0;
+    }
+
+    // 厂商清理服务在跑时让出，避免两边重复杀同一批进程
+    if quirks::any_alive(&state.oem_killers) {
+        if !state.yielding {
+            if let Some(l) = logger {
+                l.write_yield(&state.oem_killers);
+            }
+        }
+        state.yielding = true;
+        return 0;
+    }
+    state.yielding = false;
+
+    let avail_kb = if config.comfort_kb > 0
+        || config.mem_trigger.is_some()
+        || config.target_available_kb > 0
+        || !config.tiers.is_empty()
+    {
+        memory::read_mem_available_kb()
+    } else {
+        None
+    };
+
+    // 可用内存充足时整轮跳过，减少无谓的击杀；有黑名单或 blacklist-once 点名的包时只清理它们
+    let mut only_marked = false;
+    if config.comfort_kb > 0 {
+        if let Some(avail_kb) = avail_kb {
+            if avail_kb >= config.comfort_kb {
+                if !state.skipping {
+                    if let Some(l) = logger {
+                        l.write_skip(avail_kb, state.last_freed_kb);
+                    }
+                }
+                state.skipping = true;
+                if state.kill_once.is_empty() && config.blacklist.is_empty() {
+                    return 0;
+                }
+                only_marked = true;
+            }
+        }
+        if !only_marked {
+            state.skipping = false;
+        }
+    }
+
+    // 滞回触发：避免在单一阈值附近来回切换
+    if let (Some(trigger), Some(avail_kb)) = (config.mem_trigger, avail_kb) {
+        let was_active = state.pressure_active;
+        if was_active && avail_kb >= trigger.exit_kb {
+            state.pressure_active = false;
+        } else if !was_active && avail_kb < trigger.enter_kb {
+            state.pressure_active = true;
+        }
+        if was_active != state.pressure_active {
+            if state.pressure_active {
+                events::publish(Event::PressureHigh { avail_kb });
+            }
+            if let Some(l) = logger {
+                l.write_pressure_change(state.pressure_active, avail_kb);
+            }
+        }
+        if !state.pressure_active {
+            if state.kill_once.is_empty() && config.blacklist.is_empty() {
+                return 0;
+            }
+            only_marked = true;
+        }
+    }
+
+    // 目标模式：可用内存已达标则无事可做
+    if config.target_available_kb > 0 && avail_kb.is_some_and(|a| a >= config.target_available_kb) {
+        if state.kill_once.is_empty() && config.blacklist.is_empty() {
+            return 0;
+        }
+        only_marked = true;
+    }
+
+    let mut scan = ScanReport::default();
+    // 只为日志记录清理前的可用内存，已按策略读过时不再重复读
+    let avail_before_kb = if avail_kb.is_none() && logger.is_some() {
+        memory::read_mem_available_kb()
+    } else {
+        None
+    };
+    let mut candidates = select_candidates(
+        config,
+        res,
+        state,
+        procfs,
+        avail_kb,
+        config.dry_run,
+        &mut scan,
+    );
+    if only_marked {
+        candidates.retain(|c| c.forced);
+    }
+
+    // 演练：按实际会执行的顺序和每轮上限列出，什么都不动
+    if config.dry_run {
+        if config.max_kills_per_cycle > 0 {
+            candidates.truncate(config.max_kills_per_cycle);
+        }
+        // 没有日志时启动就会报错；热加载打开演练的情况只在 reload 时警告一次
+        if let Some(l) = logger {
+            l.write_dry_run(&candidates, config);
+        }
+        // 演练的这一轮就是点名之后的"下一轮"，与实际清理一样忘掉点名
+        state.kill_once.clear();
+        state
+            .stats
+            .record_cycle(started.elapsed().as_millis() as u64);
+        return 0;
+    }
+    report_whitelist_hits(config, logger, state);
+    if let Some(path) = &config.table_state {
+        state.save_state(path, scan.now, false);
+    }
+    for c in &candidates {
+        events::publish(Event::Classified {
+            pid: c.pid,
+            name: c.name.clone(),
+            oom: c.oom,
+        });
+    }
+
+    let log = CycleLog {
+        trigger,
+        avail_before_kb: avail_kb.or(avail_before_kb),
+        considered: candidates.len(),
+        started,
+        killed: Vec::new(),
+    };
+    let batch = Batch {
+        candidates,
+        avail_kb,
+        acted: 0,
+        freed_kb: 0,
+        scan,
+        started,
+        log,
+    };
+    act_on(config, logger, res, state, procfs, batch)
+}
+
+/// 丢弃排队的候选（新一轮清理、暂停或定时器出错），本轮已击杀的照常写成一条记录；
+/// 丢下的候选由下一轮重新扫描判定，记为留到下一轮
+pub fn drop_kill_queue(logger: &mut Option<Logger>, state: &mut CleanerState) {
+    if let Some(queue) = state.kill_queue.take() {
+        queue
+            .log
+            .write(logger, state.pressure_active, queue.candidates.len());
+    }
+}
+
+/// 间隔击杀到时后处理上一轮剩下的候选；没有排队的候选或已暂停时返回 0
+pub fn continue_cleanup(
+    config: &AppConfig,
+    logger: &mut Option<Logger>,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+) -> usize {
+    let now = boottime_secs();
+    if state.is_paused(now) || state.pause_property.is_set(now) || state.charger_pause {
+        drop_kill_queue(logger, state);
+        return 0;
+    }
+    let Some(queue) = state.kill_queue.take() else {
+        return 0;
+    };
+    // 排队期间已退出或 PID 被复用的不再处理
+    let mut itoa_buf = ItoaBuffer::new();
+    let mut candidates = queue.candidates;
+    candidates.retain(|c| {
+        read_stat(res, procfs, itoa_buf.format(c.pid))
+            .is_some_and(|st| st.start_time == c.start_time)
+    });
+    let batch = Batch {
+        candidates,
+        avail_kb: queue.avail_kb,
+        acted: queue.acted,
+        freed_kb: queue.freed_kb,
+        scan: ScanReport {
+            now,
+            ..ScanReport::default()
+        },
+        started: Instant::now(),
+        log: queue.log,
+    };
+    act_on(config, logger, res, state, procfs, batch)
+}
+
+/// 没有主循环的调用方（once、嵌入接口）用：间隔击杀时就地等待，直到本轮处理完
+pub fn perform_cleanup_blocking(
+    config: &AppConfig,
+    logger: &mut Option<Logger>,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+    trigger: CycleTrigger,
+) -> usize {
+    let mut killed = perform_cleanup(config, logger, res, state, procfs, trigger);
+    while state.kill_queue.is_some() {
+        std::thread::sleep(Duration::from_millis(config.kill_delay_ms));
+        killed += continue_cleanup(config, logger, res, state, procfs);
+    }
+    killed
+}
+
+/// 按顺序对一批候选执行动作；kill_delay_ms 下击杀一个后把剩下的排进 kill_queue，
+/// 整轮处理完时才写本轮的清理记录
+fn act_on(
+    config: &AppConfig,
+    logger: &mut Option<Logger>,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+    batch: Batch,
+) -> usize {
+    let Batch {
+        candidates,
+        avail_kb,
+        acted: acted_before,
+        freed_kb: freed_before,
+        scan,
+        started,
+        mut log,
+    } = batch;
+    let now = scan.now;
+
+    let mut itoa_buf = ItoaBuffer::new();
+    let mut killed_list: Vec<KillRecord> = Vec::new();
+    let mut escalated_list: Vec<String> = Vec::new();
+    let mut frozen_list: Vec<String> = Vec::new();
+    let mut demoted_list: Vec<String> = Vec::new();
+    let mut storm_list: Vec<String> = Vec::new();
+    let mut freed_kb = 0;
+    let mut deferred = 0;
+    let mut queued = None;
+    let mut remaining = candidates.into_iter();
+    while let Some(candidate) = remaining.next() {
+        // 限制每轮处理数（击杀、冻结、降级都算），避免一次清掉太多导致系统集中重启它们；
+        // 剩下的下一轮重新扫描判定
+        let acted = acted_before + killed_list.len() + frozen_list.len() + demoted_list.len();
+        if config.max_kills_per_cycle > 0 && acted >= config.max_kills_per_cycle {
+            deferred = 1 + remaining.len();
+            break;
+        }
+
+        // 滞回触发下估算可用内存已回升到 exit 以上就停手
+        if let (Some(trigger), Some(avail_kb), false) =
+            (config.mem_trigger, avail_kb, candidate.forced)
+        {
+            if avail_kb + freed_before + freed_kb >= trigger.exit_kb {
+                break;
+            }
+        }
+
+        // 逐个间隔击杀，让系统消化每次退出，避免大批量清理后短暂卡顿；
+        // 剩下的排队，由主循环到时再处理，不在这里睡眠
+        if config.kill_delay_ms > 0 && !killed_list.is_empty() {
+            let mut candidates = vec![candidate];
+            candidates.extend(remaining);
+            queued = Some((candidates, acted));
+            break;
+        }
+
+        // 冻结模式：不释放内存，但进程不再占用 CPU、也不会被系统反复拉起，状态完整保留
+        if candidate.action == CleanAction::Freeze {
+            // 已被系统冻结的不必再动
+            if candidate.frozen.is_some() {
+                continue;
+            }
+            let pid_s = itoa_buf.format(candidate.pid);
+            let cgroup = if res.read_proc_file(procfs, pid_s, "cgroup") {
+                String::from_utf8_lossy(&res.file_buf).into_owned()
+            } else {
+                String::new()
+            };
+            match res.freezer.freeze(&cgroup, candidate.pid) {
+                Some(method) => {
+                    frozen_list.push(format!("{} ({})", candidate.name, candidate.mem));
+                    state.frozen.insert(
+                        candidate.pid,
+                        FrozenProc {
+                            name: candidate.name,
+                            start_time: candidate.start_time,
+                            method,
+                        },
+                    );
+                }
+                None => dedup::error(
+                    "freeze",
+                    &format!("Failed to freeze {} ({})", candidate.name, candidate.pid),
+                ),
+            }
+            continue;
+        }
+
+        // 降级模式：不发信号，调高 oom_score_adj 让内核回收时先挑它
+        if candidate.action == CleanAction::Demote {
+            if write_oom_adj(itoa_buf.format(candidate.pid), config.demote_adj) {
+                state.table.mark_demoted(candidate.pid, config.demote_adj);
+                demoted_list.push(candidate.name);
+            } else {
+                dedup::error(
+                    "demote",
+                    &format!("Failed to demote {} ({})", candidate.name, candidate.pid),
+                );
+            }
+            continue;
+        }
+
+        if let Some(frozen) = &candidate.frozen {
+            if !res.freezer.thaw(frozen, itoa_buf.format(candidate.pid)) {
+                dedup::warn(
+                    "thaw",
+                    &format!("Failed to thaw {} ({})", candidate.name, candidate.pid),
+                );
+            }
+        }
+
+        // 冻结的进程无法响应 dumpsys 的 binder 请求，不去问它
+        let mut meminfo = None;
+        if let (Some(sampler), None) = (&mut state.meminfo, &candidate.frozen) {
+            meminfo = sampler.sample(itoa_buf.format(candidate.pid));
+            if !candidate.forced && meminfo.is_some_and(|m| m.total_pss_kb < config.min_pss_kb) {
+                continue;
+            }
+        }
+
+        // 先发 SIGTERM 让应用保存状态，宽限期过后仍在才 SIGKILL
+        let pending =
+            (config.term_grace_cycles > 0).then(|| state.table.cycles_since_term(candidate.pid));
+        let graceful = pending == Some(None);
+        let escalated = match pending {
+            Some(Some(cycles)) if cycles < config.term_grace_cycles => continue,
+            Some(Some(_)) => true,
+            _ => false,
+        };
+
+        // 多次击杀仍未退出的进程按配置逐级升级，而不是一直盲目重试
+        let survived = if graceful {
+            0
+        } else {
+            state
+                .survivors
+                .record_attempt(candidate.pid, candidate.start_time)
+        };
+        let mut force_stopped = false;
+        if let Some(action) = survivor_action(config, survived) {
+            if let Some(l) = logger {
+                l.write_survivor(&candidate.name, survived, action);
+            }
+            let pid_s = itoa_buf.format(candidate.pid);
+            match action {
+                SurvivorAction::ThawKill => {
+                    if res.read_proc_file(procfs, pid_s, "cgroup") {
+                        let content = String::from_utf8_lossy(&res.file_buf).into_owned();
+                        if let Some(frozen) = res.freezer.frozen_cgroup(&content) {
+                            res.freezer.thaw(&frozen, pid_s);
+                        }
+                    }
+                }
+                SurvivorAction::ForceStop => {
+                    force_stopped = Command::new("am")
+                        .args(["force-stop", history::package_name(&candidate.name)])
+                        .status()
+                        .is_ok_and(|s| s.success());
+                }
+                SurvivorAction::Ignore => {
+                    state.survivors.ignore(candidate.pid, candidate.start_time);
+                    continue;
+                }
+            }
+        }
+
+        // 击杀目标进程
+        let signal = if graceful {
+            Signal::SIGTERM
+        } else {
+            Signal::SIGKILL
+        };
+        let result = if force_stopped {
+            Ok(())
+        } else {
+            kill(Pid::from_raw(candidate.pid), signal)
+        };
+        match result {
+            Ok(()) => {}
+            Err(Errno::ESRCH) => continue, // 扫描之后已自行退出
+            Err(e) => {
+                dedup::error("kill", &format!("Failed to kill {}: {}", candidate.name, e));
+                continue;
+            }
+        }
+        if graceful {
+            state.table.mark_terminated(candidate.pid);
+        }
+        let mut group = Vec::new();
+        if config.kill_scope == KillScope::Package && candidate.uid >= 10000 {
+            let min_adj = match config.threshold_metric {
+                OomMetric::Adj => state.threshold(config, avail_kb),
+                OomMetric::Score => PERCEPTIBLE_APP_ADJ,
+            };
+            for (pid, name) in package_group(config, res, state, procfs, &candidate, min_adj, now) {
+                if kill(Pid::from_raw(pid), signal).is_ok() {
+                    if graceful {
+                        state.table.mark_terminated(pid);
+                    }
+                    group.push((pid, name));
+                }
+            }
+        }
+        // SIGTERM 时已记过一次清理，升级不重复计数
+        if escalated {
+            escalated_list.push(candidate.name);
+            continue;
+        }
+
+        state.history.record_kill(
+            history::package_name(&candidate.name),
+            candidate.oom,
+            candidate.mem.resident_kb(),
+            now,
+        );
+        state.respawn.watch(&candidate.name, now);
+        let package = history::package_name(&candidate.name);
+        if state.throttle.record(package, now) {
+            storm_list.push(package.to_string());
+        }
+        state
+            .relaunch
+            .watch(history::package_name(&candidate.name), now);
+        freed_kb += candidate.mem.resident_kb();
+        SESSION.record_kill(candidate.mem.resident_kb());
+        state
+            .stats
+            .record_kill(history::package_name(&candidate.name), candidate.mem.rss_kb);
+        events::publish(Event::Killed {
+            pid: candidate.pid,
+            name: candidate.name.clone(),
+            oom: candidate.oom,
+            resident_kb: candidate.mem.resident_kb(),
+        });
+        killed_list.push(KillRecord {
+            pid: candidate.pid,
+            uid: candidate.uid,
+            oom: candidate.oom,
+            name: candidate.name,
+            argv: candidate.argv,
+            mem: candidate.mem,
+            background_secs: candidate.background_secs,
+            drain_mah: candidate.drain_mah,
+            meminfo,
+            is_32bit: candidate.is_32bit,
+            graceful,
+            group,
+        });
+    }
+
+    state.last_freed_kb = freed_before + freed_kb;
+    state
+        .stats
+        .record_cycle(started.elapsed().as_millis() as u64);
+    let killed = killed_list.len();
+    events::publish(Event::CycleComplete { killed, freed_kb });
+    log.killed.extend(killed_list);
+
+    if let Some(l) = logger {
+        if !scan.respawned.is_empty() {
+            l.write_respawns(&scan.respawned, &state.respawn, now);
+        }
+        if !scan.relaunched.is_empty() {
+            l.write_relaunches(
+                &scan.relaunched,
+                &state.relaunch,
+                now,
+                config.relaunch_suggest_after,
+            );
+        }
+        if !scan.demoted.is_empty() {
+            l.write_demoted(&scan.demoted, GMS_DEMOTE_ADJ);
+        }
+        if !escalated_list.is_empty() {
+            l.write_escalated(&escalated_list, config.term_grace_cycles);
+        }
+        if !frozen_list.is_empty() {
+            l.write_frozen(&frozen_list);
+        }
+        if !demoted_list.is_empty() {
+            l.write_demoted(&demoted_list, config.demote_adj);
+        }
+        if !storm_list.is_empty() {
+            l.write_storms(&storm_list);
+        }
+    }
+    match queued {
+        Some((candidates, acted)) => {
+            state.kill_queue = Some(KillQueue {
+                candidates,
+                avail_kb,
+                acted,
+                freed_kb: freed_before + freed_kb,
+                log,
+            });
+        }
+        None => log.write(logger, state.pressure_active, deferred),
+    }
+    killed
+}
+
+/// 冻结模式：回到前台（adj 低于可感知）的进程解冻，已退出的不再跟踪；
+/// 只在清理周期检查，周期越短用户切回时等得越少
+fn thaw_returned(
+    logger: &mut Option<Logger>,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+) {
+    if state.frozen.is_empty() {
+        return;
+    }
+    let mut itoa_buf = ItoaBuffer::new();
+    let mut gone = Vec::new();
+    let mut returned = Vec::new();
+    for (pid, proc) in state.frozen.iter() {
+        let pid_s = itoa_buf.format(pid);
+        if read_stat(res, procfs, pid_s).is_none_or(|st| st.start_time != proc.start_time) {
+            gone.push(pid);
+        } else if read_proc_i32(res, procfs, pid_s, "oom_score_adj")
+            .is_some_and(|adj| adj < MIN_KILLABLE_OOM)
+        {
+            returned.push(pid);
+        }
+    }
+    for pid in gone {
+        state.frozen.remove(pid);
+    }
+    let mut thawed = Vec::new();
+    for pid in returned {
+        if let Some(proc) = state.frozen.remove(pid) {
+            if !res.freezer.unfreeze(&proc.method, pid) {
+                dedup::warn("thaw", &format!("Failed to thaw {} ({})", proc.name, pid));
+            }
+            thawed.push(proc.name);
+        }
+    }
+    if let (Some(l), false) = (logger, thawed.is_empty()) {
+        l.write_thawed(&thawed);
+    }
+}
+
+/// 一轮扫描中顺带产生、需要写日志的事件
+#[derive(Default)]
+pub struct ScanReport {
+    pub now: u64, // 扫描时刻（boottime 秒）
+    pub respawned: Vec<(String, u64)>,
+    pub relaunched: Vec<(String, u32)>, // 包名，累计被用户重新打开次数
+    pub demoted: Vec<String>,
+}
+
+/// 扫描 /proc 并按当前策略选出本轮要清理的进程（已排序、已截取）
+///
+/// simulate 为 true 时不做任何修改（不写 oom_score_adj）
+pub fn select_candidates(
+    config: &AppConfig,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+    avail_kb: Option<u64>,
+    simulate: bool,
+    scan: &mut ScanReport,
+) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut itoa_buf = ItoaBuffer::new();
+    let now = boottime_secs();
+    let unix_now = whitelist::unix_secs();
+    scan.now = now;
+    // 按 oom_score 比较阈值时，adj 只用来排除前台/可见进程
+    let threshold_on_score = config.score.is_none()
+        && config.target_available_kb == 0
+        && config.threshold_metric == OomMetric::Score;
+    let threshold = state.threshold(config, avail_kb);
+    let policy_floor = config.policies.values().filter_map(|p| p.oom).min();
+    let min_oom = if config.score.is_some() || config.target_available_kb > 0 || threshold_on_score
+    {
+        MIN_KILLABLE_OOM
+    } else {
+        threshold
+    };
+    state.table.begin_cycle();
+    let pids = state.table.pids(procfs);
+    state.respawn.expire(now);
+    state.relaunch.expire(now);
+    state.throttle.expire(now);
+    // 只保留本轮仍然存在的隐藏进程
+    let mut hidden = FxHashMap::default();
+    let mut foreign_ns = FxHashMap::default();
+    let mut marked = Vec::new();
+    let mut protected = 0;
+
+    for pid in pids {
+        // 使用 itoa 将 pid 转为字符串（零分配）
+        let pid_s = itoa_buf.format(pid);
+
+        // ==========================================
+        // 漏斗第 1 层：查 UID (最轻量，仅 1 次 fstatat syscall)
+        // 过滤掉 30%~40% 的底层系统进程 (UID < 10000)
+        // ==========================================
+        let (uid, is_system) = match procfs.uid(pid_s) {
+            Ok(uid) => {
+                if uid < 10000 && config.system_targets.is_empty() {
+                    continue; // 核心系统进程，直接跳过
+                }
+                if config
+                    .exclude_uids
+                    .iter()
+                    .any(|&(lo, hi)| (lo..=hi).contains(&uid))
+                {
+                    continue; // 用户排除的 UID 段
+                }
+                (uid, uid < 10000)
+            }
+            Err(_) => continue,
+        };
+
+        // 上一轮已确认读不到的进程（pid 未被复用给其他 UID）直接跳过
+        if state.hidden.get(&pid) == Some(&uid) {
+            hidden.insert(pid, uid);
+            continue;
+        }
+
+        // 容器、沙盒等其他命名空间中的进程按 namespaces 策略处理
+        if let (Some(host), Some(proc_fd)) = (&state.host_ns, procfs.dir_fd()) {
+            if let Some(ns) = host.foreign(proc_fd, &mut res.path_buf, pid_s) {
+                *foreign_ns.entry(ns).or_insert(0) += 1;
+                if !config.namespaces.allows(ns) {
+                    continue;
+                }
+            }
+        }
+
+        // blacklist-once 点名的包跳过阈值与其余漏斗，直接进入本轮
+        if !state.kill_once.is_empty() && !is_system {
+            if let Some(c) = marked_candidate(config, res, state, procfs, pid, uid, now) {
+                marked.push(c);
+                continue;
+            }
+        }
+
+        // 重生检测：刚杀过进程时，检查新启动的进程是否就是它
+        if let Some(since) = state.respawn.earliest_kill() {
+            let started_at = read_stat(res, procfs, pid_s).map(|st| st.started_at());
+            if let Some(started_at) = started_at.filter(|&t| t >= since) {
+                if res.read_cmdline(procfs, pid_s) {
+                    if let Some(latency) = state.respawn.check(&res.cmdline_buf, started_at) {
+                        scan.respawned.push((res.cmdline_buf.clone(), latency));
+                    }
+                }
+            }
+        }
+
+        // ==========================================
+        // 漏斗第 2 层：查 oom_score_adj (较轻量，3 次 syscall + 简单解析)
+        // 过滤掉前台和活跃 App (adj < 800，打分/目标模式下 < 200)
+        // ==========================================
+        res.path_buf.clear();
+        res.path_buf.push_str(pid_s);
+        res.path_buf.push_str("/oom_score_adj");
+
+        res.file_buf.clear();
+        let oom_adj = {
+            // 上一轮留在进程表里的节点复用已打开的 fd，省去每轮 open/close
+            let read = if state.table.read_oom(pid, &mut res.file_buf) {
+                Ok(())
+            } else {
+                procfs.read(&res.path_buf, &mut res.file_buf)
+            };
+            match read {
+                Ok(()) => {
+                    let s = std::str::from_utf8(&res.file_buf).ok().map(|s| s.trim());
+                    s.and_then(|s| s.parse::<i32>().ok())
+                }
+                // hidepid=1：目录可见但内容不可读
+                Err(Errno::EACCES) => {
+                    dedup::warn(
+                        "denied",
+                        &format!("Permission denied reading /proc/{}/oom_score_adj", pid),
+                    );
+                    hidden.insert(pid, uid);
+                    continue;
+                }
+                Err(_) => None,
+            }
+        };
+
+        // 记录前台包，并检测用户重新打开：刚被杀的包的主进程回到了前台
+        if oom_adj == Some(FOREGROUND_APP_ADJ)
+            && (state.relaunch.is_watching()
+                || state.switch_grace.enabled()
+                || state.recent_apps.enabled())
+            && res.read_cmdline(procfs, pid_s)
+        {
+            state
+                .switch_grace
+                .saw_foreground(history::package_name(&res.cmdline_buf));
+            state
+                .recent_apps
+                .saw_foreground(history::package_name(&res.cmdline_buf));
+            if let Some(count) = state.relaunch.check(&res.cmdline_buf, now) {
+                scan.relaunched.push((res.cmdline_buf.clone(), count));
+            }
+        }
+
+        // 用户 UID 下的 persistent / 被核心进程绑定的进程（电话、拨号的伴生进程等）：
+        // 系统会立即拉起，阈值设得再低也不碰
+        if !is_system
+            && oom_adj.is_some_and(|adj| (PERSISTENT_PROC_ADJ..FOREGROUND_APP_ADJ).contains(&adj))
+        {
+            continue;
+        }
+
+        // 黑名单进程只要进入缓存状态就清理，阈值以下的缓存进程也要先看名字
+        let mut below_threshold = false;
+        let mut oom = match oom_adj {
+            Some(s) if s >= min_oom => s,
+            Some(s) if s >= CACHED_APP_MIN_ADJ && !config.blacklist.is_empty() => {
+                below_threshold = true;
+                s
+            }
+            // 有包单独设了更低的阈值，同样要看名字
+            Some(s) if policy_floor.is_some_and(|f| s >= f) => {
+                below_threshold = true;
+                s
+            }
+            // 阈值以下的白名单进程同样要压低 adj，只有开启保护时才为它们读名字
+            Some(s) if !simulate && config.whitelist_oom_adj.is_some_and(|p| s > p) => {
+                if protect_whitelisted(config, res, procfs, pid_s, uid) {
+                    protected += 1;
+                }
+                continue;
+            }
+            _ => continue, // 活跃进程，跳过
+        };
+        if threshold_on_score && !below_threshold {
+            match read_proc_i32(res, procfs, pid_s, "oom_score") {
+                Some(s) if s >= threshold => oom = s,
+                _ if (oom >= CACHED_APP_MIN_ADJ && !config.blacklist.is_empty())
+                    || policy_floor.is_some_and(|f| oom >= f) =>
+                {
+                    below_threshold = true;
+                }
+                _ => continue,
+            }
+        }
+
+        // ==========================================
+        // 漏斗第 3 层：查 cmdline 并匹配白名单 (最重，涉及字符串操作)
+        // 只有高危驻留后台 App 才会走到这一步
+        // ==========================================
+        let Some(name_source) = res.read_name(procfs, pid_s, uid, &mut state.packages) else {
+            continue;
+        };
+
+        let cmdline = &res.cmdline_buf;
+
+        if cmdline.is_empty() {
+            continue;
+        }
+
+        // 厂商助手等一概不碰：不计白名单命中，也不进进程表
+        if config.is_never_touched(cmdline) {
+            continue;
+        }
+
+        // 白名单过滤；comm/exe 得到的名字不完整，只要可能对应白名单条目就放过
+        let matched = whitelist_match(cmdline, uid, &config.whitelist).or_else(|| {
+            (name_source != NameSource::Cmdline)
+                .then(|| may_be_whitelisted(cmdline, uid, &config.whitelist))
+                .flatten()
+        });
+        if let Some(rule) = matched {
+            if !simulate {
+                state.whitelist_hits.hit(rule, unix_now);
+                // comm/exe 的模糊匹配可能对应别的进程，只保护按完整 cmdline 命中的
+                if let Some(adj) = config.whitelist_oom_adj {
+                    if name_source == NameSource::Cmdline
+                        && oom_adj.is_some_and(|s| s > adj)
+                        && write_oom_adj(pid_s, adj)
+                    {
+                        protected += 1;
+                    }
+                }
+            }
+            continue;
+        }
+
+        // 默认桌面、输入法等：黑名单也不能让它们被杀，放在黑名单判断之前
+        if let Some(critical) = &mut state.critical {
+            if critical.contains(history::package_name(cmdline)) {
+                continue;
+            }
+        }
+
+        let blacklisted =
+            name_source == NameSource::Cmdline && is_in_whitelist(cmdline, uid, &config.blacklist);
+
+        // 按包单独设的阈值取代全局阈值（比较 oom_score_adj）
+        let policy = config.policies.get(history::package_name(cmdline));
+        if let (Some(policy_oom), Some(adj)) = (policy.and_then(|p| p.oom), oom_adj) {
+            if adj < policy_oom && !blacklisted {
+                continue;
+            }
+            below_threshold = false;
+        }
+        let action = policy.and_then(|p| p.action).unwrap_or(config.action);
+        if below_threshold && !blacklisted {
+            continue;
+        }
+
+        // 系统 UID 进程只有被 system_targets 点名才处理，且不要求 ':'
+        if is_system {
+            if name_source != NameSource::Cmdline
+                || !is_in_whitelist(cmdline, uid, &config.system_targets)
+            {
+                continue;
+            }
+        } else if !cmdline.contains(':') && !blacklisted {
+            // 仅杀带有 ':' 的进程 (通常是 App 的后台服务进程，如 com.xxx.app:push)
+            continue;
+        }
+
+        // GMS/GSF 进程族按专门策略处理
+        if is_gms_process(cmdline) {
+            match config.gms_policy {
+                GmsPolicy::Protect => continue,
+                GmsPolicy::Demote => {
+                    if oom < GMS_DEMOTE_ADJ && !simulate && write_oom_adj(pid_s, GMS_DEMOTE_ADJ) {
+                        scan.demoted.push(cmdline.clone());
+                    }
+                    continue;
+                }
+                GmsPolicy::Normal => {}
+            }
+        }
+
+        // 重生过快的进程处于退避期，杀了也会马上回来
+        if state.respawn.backoff_remaining(cmdline, now).is_some() {
+            continue;
+        }
+
+        // 刚杀过、或正处于重生风暴中的包
+        if state
+            .throttle
+            .is_throttled(history::package_name(cmdline), now)
+        {
+            continue;
+        }
+
+        if package_in_use(state, uid, history::package_name(cmdline), now) {
+            continue;
+        }
+
+        let cmdline = cmdline.clone();
+        // file_buf 此时仍是 cmdline 原文，后续读取会覆盖它
+        let argv = (config.verbose_log && name_source == NameSource::Cmdline)
+            .then(|| full_argv(&res.file_buf));
+
+        // oom_score_adj 更新有延迟，仍在前台 cpuset 里的进程不动
+        if res.read_proc_file(procfs, pid_s, "cpuset")
+            && res.file_buf.trim_ascii_end() == config.quirks.top_app_cpuset.as_bytes()
+        {
+            continue;
+        }
+
+        // 每轮都要观察后台进程，否则进程表会把它当作已离开后台
+        let stat = read_stat(res, procfs, pid_s);
+        let (last_active, background_since) =
+            match stat.as_ref().map(|st| state.table.observe(pid, st, now)) {
+                Some(node) => (node.last_active, node.background_since),
+                None => (now, now),
+            };
+        if stat.is_some() && !state.table.has_oom_fd(pid) {
+            if let Some(fd) = procfs.open(&format!("{}/oom_score_adj", pid_s)) {
+                state.table.keep_oom_fd(pid, fd);
+            }
+        }
+
+        // 节点在跌回阈值以下的那一轮即被清除，background_since 就是连续处于阈值以上的起点
+        if now.saturating_sub(background_since) < config.min_background_secs && !blacklisted {
+            continue;
+        }
+
+        // 不可中断睡眠（D 状态）中 SIGKILL 要等它醒来才生效，留到下一轮
+        if stat.is_some_and(|st| st.is_uninterruptible()) {
+            continue;
+        }
+        let start_time = stat.map_or(0, |st| st.start_time);
+        if state.survivors.is_ignored(pid, start_time) || state.frozen.contains(pid, start_time) {
+            continue;
+        }
+
+        // 32 位进程按 abi32 策略整类处理
+        res.path_buf.clear();
+        res.path_buf.push_str(pid_s);
+        res.path_buf.push_str("/exe");
+        let is_32bit = procfs
+            .dir_fd()
+            .and_then(|fd| abi::is_32bit(fd, &res.path_buf))
+            .unwrap_or(false);
+        if is_32bit && config.abi32 == Abi32Policy::Exempt {
+            continue;
+        }
+
+        // 记录内存占用（进程退出后就读不到了），大小不足的进程放过
+        let mut mem = ProcMemory::default();
+        if res.read_proc_file(procfs, pid_s, "status") {
+            mem.rss_kb = memory::status_field_kb(&res.file_buf, "VmRSS").unwrap_or(0);
+            mem.swap_kb = memory::status_field_kb(&res.file_buf, "VmSwap").unwrap_or(0);
+        }
+        if config.gpu_accounting {
+            mem.gpu_kb = memory::read_gpu_kb(pid_s);
+        }
+        state.table.set_sample(pid, uid, oom, &cmdline, mem.rss_kb);
+        if action == CleanAction::Demote && !blacklisted && state.table.is_demoted(pid) {
+            continue;
+        }
+        if (mem.effective_kb(config.swap_weight) < config.min_size_kb
+            || mem.rss_kb < config.min_rss_kb)
+            && !blacklisted
+        {
+            continue;
+        }
+
+        // 只清理堆真正大的进程，放过成堆的小 stub 进程；smaps 较大，放在便宜的过滤之后读
+        if config.min_heap_kb > 0
+            && !blacklisted
+            && !(res.read_proc_file(procfs, pid_s, "smaps")
+                && memory::smaps_heap_kb(&res.file_buf) >= config.min_heap_kb)
+        {
+            continue;
+        }
+
+        // 已被系统冻结的进程：按配置跳过、直接杀或先解冻
+        let mut frozen = None;
+        if config.frozen_action != FrozenAction::Kill && res.read_proc_file(procfs, pid_s, "cgroup")
+        {
+            let content = String::from_utf8_lossy(&res.file_buf);
+            frozen = res.freezer.frozen_cgroup(&content);
+            if frozen.is_some() && config.frozen_action == FrozenAction::Skip {
+                continue;
+            }
+        }
+
+        let drain_mah = state.battery.as_mut().map_or(0.0, |b| b.drain_mah(uid));
+
+        // 打分模式：综合多项指标，未达阈值的放过
+        let mut score = 0.0;
+        if let Some(weights) = &config.score {
+            let mut inputs = ScoreInputs {
+                oom,
+                pss_kb: mem.rss_kb,
+                idle_secs: 0,
+                bucket: 0,
+                kills: 0,
+                drain_mah,
+                squat: 0.0,
+                oom_score: 0,
+            };
+            let package = history::package_name(&cmdline);
+            if weights.pss != 0.0 && res.read_proc_file(procfs, pid_s, "smaps_rollup") {
+                if let Some(pss) = memory::status_field_kb(&res.file_buf, "Pss") {
+                    inputs.pss_kb = pss + mem.gpu_kb;
+                }
+            }
+            inputs.idle_secs = now.saturating_sub(last_active);
+            if weights.bucket != 0.0 {
+                inputs.bucket = state.buckets.get(package);
+            }
+            inputs.kills = state.history.kills(package);
+            if weights.oom_score != 0.0 {
+                inputs.oom_score = read_proc_i32(res, procfs, pid_s, "oom_score").unwrap_or(0);
+            }
+            if weights.procstats != 0.0 {
+                inputs.squat = state.squatters.squat_score(&cmdline);
+            }
+
+            score = weights.score(&inputs);
+            if score < weights.threshold && !blacklisted {
+                continue;
+            }
+        }
+
+        // 黑名单进程不参与后面的排序、截取和策略脚本，与 blacklist-once 一样排在最前
+        let list = if blacklisted {
+            &mut marked
+        } else {
+            &mut candidates
+        };
+        list.push(Candidate {
+            pid,
+            uid,
+            name: cmdline,
+            argv,
+            oom,
+            mem,
+            background_secs: now.saturating_sub(background_since),
+            drain_mah,
+            frozen,
+            start_time,
+            score,
+            is_32bit,
+            forced: blacklisted,
+            action,
+        });
+    }
+    state.table.end_cycle();
+    state.switch_grace.end_cycle(now);
+    let table = &state.table;
+    state
+        .survivors
+        .retain(|pid, start| table.get(pid).is_some_and(|n| n.start_time == start));
+    state.hidden = hidden;
+    state.foreign_ns = foreign_ns;
+    if !simulate {
+        state.kill_once.clear();
+        state.protected = protected;
+    }
+
+    if config.score.is_some() {
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    } else if config.largest_first {
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.mem.effective_kb(config.swap_weight)));
+    } else if config.battery_stats {
+        candidates.sort_by(|a, b| b.drain_mah.total_cmp(&a.drain_mah));
+    } else if config.max_kills_per_cycle > 0 {
+        // 有每轮上限时先杀 adj 最高、占用最大的，名额不浪费在小进程上
+        candidates
+            .sort_by_key(|c| std::cmp::Reverse((c.oom, c.mem.effective_kb(config.swap_weight))));
+    }
+    // 稳定排序，32 位进程提到前面，同类内保持原有顺序
+    if config.abi32 == Abi32Policy::Prefer {
+        candidates.sort_by_key(|c| !c.is_32bit);
+    }
+
+    // 两级阈值：硬阈值以上全部清理，软阈值区间每轮只清理最严重的一个
+    if let Some(hard) = config.hard_threshold {
+        let (mut kept, soft): (Vec<Candidate>, Vec<Candidate>) =
+            candidates.into_iter().partition(|c| c.oom >= hard);
+        let worst = soft.into_iter().max_by(|a, b| {
+            if config.score.is_some() {
+                a.score.total_cmp(&b.score)
+            } else {
+                (a.oom, a.mem.effective_kb(config.swap_weight))
+                    .cmp(&(b.oom, b.mem.effective_kb(config.swap_weight)))
+            }
+        });
+        kept.extend(worst);
+        candidates = kept;
+    }
+
+    // 目标模式：从大到小挑选，凑够缺口即止，被杀进程数最少
+    if config.target_available_kb > 0 {
+        if let Some(avail_kb) = avail_kb {
+            let mut deficit = config.target_available_kb.saturating_sub(avail_kb);
+            candidates.sort_by_key(|c| std::cmp::Reverse(c.mem.resident_kb()));
+            let mut needed = 0;
+            for c in &candidates {
+                if deficit == 0 {
+                    break;
+                }
+                deficit = deficit.saturating_sub(c.mem.resident_kb());
+                needed += 1;
+            }
+            candidates.truncate(needed);
+        }
+    }
+
+    if let Some(script) = &config.policy_script {
+        if !candidates.is_empty() {
+            let inputs: Vec<ScriptCandidate> = candidates
+                .iter()
+                .map(|c| ScriptCandidate {
+                    pid: c.pid,
+                    name: &c.name,
+                    package: history::package_name(&c.name),
+                    oom: c.oom,
+                    rss_kb: c.mem.rss_kb,
+                    swap_kb: c.mem.swap_kb,
+                    gpu_kb: c.mem.gpu_kb,
+                    score: c.score,
+                    background_secs: c.background_secs,
+                })
+                .collect();
+            let actions = script.evaluate(&inputs);
+            let mut actions = actions.into_iter();
+            candidates.retain_mut(|c| {
+                c.action = match actions.next().flatten() {
+                    Some(ScriptAction::Keep) => return false,
+                    Some(ScriptAction::Kill) => CleanAction::Kill,
+                    Some(ScriptAction::Freeze) => CleanAction::Freeze,
+                    Some(ScriptAction::Demote) => CleanAction::Demote,
+                    None => c.action,
+                };
+                true
+            });
+        }
+    }
+
+    marked.append(&mut candidates);
+    marked
+}
+
+/// 进程属于 blacklist-once 点名的包时直接生成候选，处理方式与正常漏斗一样按包策略取
+///
+/// 只绕过阈值：[`is_protected`] 的各项保护以及前台/可见进程照样不碰，
+/// 读不到 oom_score_adj 时也不杀，交回正常漏斗处理
+fn marked_candidate(
+    config: &AppConfig,
+    res: &mut ScannerResources,
+    state: &mut CleanerState,
+    procfs: &dyn ProcSource,
+    pid: i32,
+    uid: u32,
+    now: u64,
+) -> Option<Candidate> {
+    let mut itoa_buf = ItoaBuffer::new();
+    let pid_s = itoa_buf.format(pid);
+    if !res.read_cmdline(procfs, pid_s) {
+        return None;
+    }
+    if !state
+        .kill_once
+        .contains(history::package_name(&res.cmdline_buf))
+    {
+        return None;
+    }
+    let name = res.cmdline_buf.clone();
+    if is_protected(config, state, &name, uid, now) {
+        return None;
+    }
+    let action = config
+        .policies
+        .get(history::package_name(&name))
+        .and_then(|p| p.action)
+        .unwrap_or(config.action);
+    let oom = read_proc_i32(res, procfs, pid_s, "oom_score_adj")?;
+    if oom < PERCEPTIBLE_APP_ADJ {
+        return None;
+    }
+    let stat = read_stat(res, procfs, pid_s);
+    let background_since = stat
+        .as_ref()
+        .map_or(now, |st| state.table.observe(pid, st, now).background_since);
+    let mut mem = ProcMemory::default();
+    if res.read_proc_file(procfs, pid_s, "status") {
+        mem.rss_kb = memory::status_field_kb(&res.file_buf, "VmRSS").unwrap_or(0);
+        mem.swap_kb = memory::status_field_kb(&res.file_buf, "VmSwap").unwrap_or(0);
+    }
+    let frozen = if res.read_proc_file(procfs, pid_s, "cgroup") {
+        res.freezer
+            .frozen_cgroup(&String::from_utf8_lossy(&res.file_buf))
+    } else {
+        None
+    };
+    Some(Candidate {
+        pid,
+        uid,
+        name,
+        argv: None,
+        oom,
+        mem,
+        background_secs: now.saturating_sub(background_since),
+        drain_mah: 0.0,
+        frozen,
+        start_time: stat.map_or(0, |st| st.start_time),
+        score: 0.0,
+        is_32bit: false,
+        forced: true,
+        action,
+    })
+}
+
+/// PAUSE_PROPERTY 的缓存，超过 TTL 才重新 getprop
+#[derive(Debug, Default)]
+pub struct PauseProperty {
+    set: bool,
+    checked_at: Option<u64>, // boottime 秒
+}
+
+impl PauseProperty {
+    pub fn is_set(&mut self, now: u64) -> bool {
+        if self
+            .checked_at
+            .is_none_or(|t| now.saturating_sub(t) >= PAUSE_PROPERTY_TTL_SECS)
+        {
+            self.checked_at = Some(now);
+            self.set = pause_property_set();
+        }
+        self.set
+    }
+}
+
+fn pause_property_set() -> bool {
+    Command::new("getprop")
+        .arg(PAUSE_PROPERTY)
+        .output()
+        .is_ok_and(|o| parse_bool(String::from_utf8_lossy(&o.stdout).trim()) == Some(true))
+}
+
+/// 时长的简短可读形式：3h 12m / 12m / 45s
+pub fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config as config;
+    use crate::logfile::LogBackend;
+    use crate::proc::fake::FakeProc;
+
+    fn select(config: &AppConfig, state: &mut CleanerState, procfs: &FakeProc) -> Vec<i32> {
+        let mut res = ScannerResources::new();
+        let mut scan = ScanReport::default();
+        select_candidates(config, &mut res, state, procfs, None, true, &mut scan)
+            .iter()
+            .map(|c| c.pid)
+            .collect()
+    }
+
+    #[test]
+    fn selects_only_cached_app_services() {
+        let config = config("");
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.foo:push", 900)
+            .spawn(101, 10100, "com.foo", 900) // 主进程不带 ':'
+            .spawn(102, 10200, "com.bar:svc", 0) // 前台
+            .spawn(103, 10300, "com.baz:svc", 700) // 低于阈值
+            .spawn(104, 1000, "system.helper:x", 900); // 系统 UID
+        assert_eq!(select(&config, &mut state, &procfs), vec![100]);
+    }
+
+    #[test]
+    fn whitelist_and_excluded_uids_are_skipped() {
+        let config = config("exclude_uids: 10300\nwhitelist:\ncom.keep:*\n");
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.keep:push", 950)
+            .spawn(101, 10200, "com.drop:push", 950)
+            .spawn(102, 10300, "com.other:push", 950);
+        assert_eq!(select(&config, &mut state, &procfs), vec![101]);
+    }
+
+    #[test]
+    fn unreadable_processes_are_remembered_until_reuse() {
+        let config = config("");
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs.spawn(100, 10100, "com.foo:push", 900).deny(100);
+        assert!(select(&config, &mut state, &procfs).is_empty());
+        assert_eq!(state.hidden.get(&100), Some(&10100));
+
+        // 同一 pid 换了 UID，说明已被复用，重新检查
+        procfs.exit(100).spawn(100, 10200, "com.bar:push", 900);
+        assert_eq!(select(&config, &mut state, &procfs), vec![100]);
+        assert!(state.hidden.is_empty());
+    }
+
+    #[test]
+    fn kill_budget_prefers_highest_oom() {
+        let config = config("max_kills_per_cycle: 2\n");
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.a1:push", 900)
+            .spawn(101, 10200, "com.a2:push", 999)
+            .spawn(102, 10300, "com.a3:push", 950)
+            .set_rss_kb(100, 900_000);
+        assert_eq!(select(&config, &mut state, &procfs), vec![101, 102, 100]);
+    }
+
+    #[test]
+    fn blacklist_once_bypasses_threshold() {
+        let config = config("");
+        let mut state = CleanerState::new(&config);
+        state.kill_once.insert("com.marked".to_string());
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.marked", 200)
+            .spawn(101, 10200, "com.other:push", 900);
+        assert_eq!(select(&config, &mut state, &procfs), vec![100, 101]);
+    }
+
+    #[test]
+    fn pause_property_is_cached() {
+        let mut property = PauseProperty {
+            set: true,
+            checked_at: Some(100),
+        };
+        assert!(property.is_set(100 + PAUSE_PROPERTY_TTL_SECS - 1));
+        assert_eq!(property.checked_at, Some(100));
+        property.is_set(100 + PAUSE_PROPERTY_TTL_SECS);
+        assert_eq!(property.checked_at, Some(100 + PAUSE_PROPERTY_TTL_SECS));
+    }
+
+    #[test]
+    fn blacklist_once_respects_protections() {
+        let config = config("whitelist:\ncom.kept\nnever_touch_prefixes: com.vendor\n");
+        let mut state = CleanerState::new(&config);
+        for package in ["com.kept", "com.vendor.helper", "com.front", "com.gone"] {
+            state.kill_once.insert(package.to_string());
+        }
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.kept", 900)
+            .spawn(101, 10200, "com.vendor.helper", 900)
+            .spawn(102, 10300, "com.front", 0)
+            .spawn(103, 10400, "com.gone", 900);
+        procfs.set_file(103, "oom_score_adj", "");
+        assert!(select(&config, &mut state, &procfs).is_empty());
+    }
+
+    #[test]
+    fn blacklist_once_respects_gms_policy_and_packages_in_use() {
+        let config = config("protect_recent: 3\n");
+        let mut state = CleanerState::new(&config);
+        for package in ["com.google.android.gms", "com.recent"] {
+            state.kill_once.insert(package.to_string());
+        }
+        state.recent_apps.saw_foreground("com.recent");
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.google.android.gms:persistent", 900)
+            .spawn(101, 10200, "com.recent", 900);
+        assert!(select(&config, &mut state, &procfs).is_empty());
+    }
+
+    /// 真实的子进程：冻结（SIGSTOP）与降级（写 oom_score_adj）只会落到它身上
+    struct Sleeper(std::process::Child);
+
+    impl Sleeper {
+        fn spawn() -> Self {
+            Self(Command::new("sleep").arg("30").spawn().unwrap())
+        }
+
+        fn pid(&self) -> i32 {
+            self.0.id() as i32
+        }
+
+        fn alive(&mut self) -> bool {
+            self.0.try_wait().unwrap().is_none()
+        }
+    }
+
+    impl Drop for Sleeper {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn clean_marked(config: &AppConfig, pid: i32) -> CleanerState {
+        let mut state = CleanerState::new(config);
+        state.kill_once.insert("com.marked".to_string());
+        let mut procfs = FakeProc::new();
+        procfs.spawn(pid, 10100, "com.marked", 900);
+        let killed = perform_cleanup(
+            config,
+            &mut None,
+            &mut ScannerResources::new(),
+            &mut state,
+            &procfs,
+            CycleTrigger::Manual,
+        );
+        assert_eq!(killed, 0);
+        state
+    }
+
+    #[test]
+    fn blacklist_once_freezes_with_package_action() {
+        let mut child = Sleeper::spawn();
+        let config = config("policy: com.marked action=freeze\n");
+        let state = clean_marked(&config, child.pid());
+        assert!(state.frozen.iter().any(|(pid, _)| pid == child.pid()));
+        assert!(child.alive());
+    }
+
+    #[test]
+    fn blacklist_once_demotes_with_package_action() {
+        let mut child = Sleeper::spawn();
+        let config = config("policy: com.marked action=demote\n");
+        clean_marked(&config, child.pid());
+        let adj = fs::read_to_string(format!("/proc/{}/oom_score_adj", child.pid())).unwrap();
+        assert_eq!(adj.trim().parse::<i32>().unwrap(), config.demote_adj);
+        assert!(child.alive());
+    }
+
+    #[test]
+    fn dry_run_forgets_blacklist_once() {
+        let config = config("dry_run: on\n");
+        let state = clean_marked(&config, 100);
+        assert!(state.kill_once.is_empty());
+    }
+
+    #[test]
+    fn kill_delay_writes_one_record_per_cycle() {
+        let first = Sleeper::spawn();
+        let second = Sleeper::spawn();
+        let path = std::env::temp_dir().join(format!("mc_delay_{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let config = config("kill_delay_ms: 1\n");
+        let mut logger = Logger::new(
+            Some(path.to_string_lossy().into_owned()),
+            LogBackend::File,
+            false,
+        );
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs.spawn(first.pid(), 10100, "com.foo:push", 900).spawn(
+            second.pid(),
+            10200,
+            "com.bar:sync",
+            900,
+        );
+        let killed = perform_cleanup_blocking(
+            &config,
+            &mut logger,
+            &mut ScannerResources::new(),
+            &mut state,
+            &procfs,
+            CycleTrigger::Manual,
+        );
+        drop(logger);
+        let log = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(killed, 2);
+        assert_eq!(log.matches("=== 清理时间").count(), 1);
+        assert!(log.contains("候选 2 个，清理 2 个"));
+    }
+
+    #[test]
+    fn table_follows_processes_across_scans() {
+        let config = config("");
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs.spawn(100, 10100, "com.foo:push", 900);
+        select(&config, &mut state, &procfs);
+        let node = state.table.get(100).unwrap();
+        assert_eq!(
+            (node.uid, node.oom, node.name.as_str()),
+            (10100, 900, "com.foo:push")
+        );
+
+        // 回到前台后不再跟踪
+        procfs.set_oom_adj(100, 0);
+        select(&config, &mut state, &procfs);
+        assert!(state.table.get(100).is_none());
+    }
+
+    #[test]
+    fn fresh_background_processes_wait_for_min_background_secs() {
+        let config = config("min_background_secs: 600\nblacklist:\ncom.bad:*\n");
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        select(&config, &mut state, &procfs);
+
+        // 首轮之后才出现的进程从被看到时开始计时；黑名单不等
+        procfs
+            .spawn(100, 10100, "com.foo:push", 900)
+            .spawn(101, 10200, "com.bad:push", 900);
+        assert_eq!(select(&config, &mut state, &procfs), vec![101]);
+        assert!(state.table.get(100).is_some());
+    }
+
+    #[test]
+    fn never_touch_prefixes_override_blacklist() {
+        let config = config(
+            "never_touch_prefixes:\ncom.vendor.\ncom.oem.a, com.oem.b\nblacklist:\ncom.vendor.helper:*\n",
+        );
+        assert_eq!(config.never_touch_prefixes.len(), 3);
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.vendor.helper:svc", 900)
+            .spawn(101, 10200, "com.oem.b:push", 900)
+            .spawn(102, 10300, "com.foo:push", 900);
+        assert_eq!(select(&config, &mut state, &procfs), vec![102]);
+        assert!(state.table.get(100).is_none());
+        assert_eq!(config.blacklist.len(), 1);
+    }
+
+    #[test]
+    fn zygote_children_without_cmdline_wait_for_specialization() {
+        let config = config("");
+        let mut state = CleanerState::new(&config);
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "", 900)
+            .set_file(100, "comm", "foo:push\n")
+            .set_file(100, "attr/current", "u:r:zygote:s0\0")
+            .spawn(101, 10200, "", 900)
+            .set_file(101, "comm", "bar:push\n")
+            .set_file(101, "attr/current", "u:r:untrusted_app:s0:c200,c256\0");
+        assert_eq!(select(&config, &mut state, &procfs), vec![101]);
+        assert!(state.table.get(100).is_none());
+    }
+
+    #[test]
+    fn package_group_shares_uid_and_skips_protected() {
+        // 关掉切换宽限与最近应用，单看 adj 的判断（前台主进程本身也会让整个包进入宽限）
+        let config = config("switch_grace_secs: 0\nprotect_recent: 0\nwhitelist:\ncom.foo:keep\n");
+        let mut state = CleanerState::new(&config);
+        let mut res = ScannerResources::new();
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(100, 10100, "com.foo:push", 900)
+            .spawn(101, 10100, "com.foo:sync", 950)
+            .spawn(102, 10100, "com.foo:keep", 900)
+            .spawn(103, 10200, "com.bar", 900)
+            .spawn(104, 10100, "com.foo", 0) // 前台主进程
+            .spawn(105, 10100, "com.foo:media", 700) // 低于本轮阈值
+            .spawn(106, 10100, "com.foo:gone", 900);
+        procfs.set_file(106, "oom_score_adj", "");
+        let mut scan = ScanReport::default();
+        let scanned = select_candidates(
+            &config, &mut res, &mut state, &procfs, None, false, &mut scan,
+        );
+        let candidate = scanned.iter().find(|c| c.pid == 100).unwrap();
+        assert_eq!(
+            package_group(&config, &mut res, &mut state, &procfs, candidate, 800, scan.now),
+            vec![(101, "com.foo:sync".to_string())]
+        );
+    }
+
+    #[test]
+    fn dry_run_kills_nothing() {
+        let config = config("dry_run: on\n");
+        let mut state = CleanerState::new(&config);
+        let mut res = ScannerResources::new();
+        let mut procfs = FakeProc::new();
+        procfs.spawn(100, 10100, "com.foo:push", 900);
+        let killed = perform_cleanup(
+            &config,
+            &mut None,
+            &mut res,
+            &mut state,
+            &procfs,
+            CycleTrigger::Manual,
+        );
+        assert_eq!(killed, 0);
+        assert_eq!(state.stats.total.kills, 0);
+        assert!(state.respawn.earliest_kill().is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//! 错误日志：警告和错误（击杀失败、读取被拒、配置有误等）与面向用户的清理日志分开保存，
+//! 便于排查问题时单独索取。每行带级别和来源标签：
+//!
+//! ```text
+//! 2024-05-01 12:00:00 WARN [config] Unknown gms_policy: foo
+//! 2024-05-01 12:03:10 ERROR [kill] Failed to kill com.foo:push: EPERM
+//! ```
+//!
+//! 超过 error_log_max_kb 后把当前文件改名为 `<path>.1` 再重新开始，只保留一代。
+//! 未配置 error_log 时只输出到 stderr。
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::logcat::{self, Priority};
+use crate::logfile::now_fmt;
+
+pub const DEFAULT_ERROR_LOG_MAX_KB: u64 = 512;
+const MAX_PENDING: usize = 64; // init 之前（加载配置时）最多缓存这么多条
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+        }
+    }
+}
+
+struct ErrorLog {
+    path: Option<String>,
+    max_bytes: u64,
+    pending: Vec<String>, // 仍未 init 时缓存的行
+    ready: bool,
+}
+
+static REPORTED: AtomicU64 = AtomicU64::new(0);
+
+static ERROR_LOG: Mutex<ErrorLog> = Mutex::new(ErrorLog {
+    path: None,
+    max_bytes: DEFAULT_ERROR_LOG_MAX_KB * 1024,
+    pending: Vec::new(),
+    ready: false,
+});
+
+/// 设置错误日志文件，并写入 init 之前缓存的条目（path 为 None 时丢弃）
+pub fn init(path: Option<String>, max_kb: u64) {
+    let Ok(mut log) = ERROR_LOG.lock() else {
+        return;
+    };
+    log.path = path;
+    log.max_bytes = max_kb.max(1) * 1024;
+    log.ready = true;
+    let pending = std::mem::take(&mut log.pending);
+    for line in pending {
+        log.append(&line);
+    }
+}
+
+pub fn warn(tag: &str, message: &str) {
+    report(Level::Warn, tag, message);
+}
+
+pub fn error(tag: &str, message: &str) {
+    report(Level::Error, tag, message);
+}
+
+/// 输出到 stderr，并追加到错误日志（启用 logcat 时也写一份）
+pub fn report(level: Level, tag: &str, message: &str) {
+    eprintln!("{}", message);
+    REPORTED.fetch_add(1, Ordering::Relaxed);
+    if logcat::enabled() {
+        let priority = match level {
+            Level::Warn => Priority::Warn,
+            Level::Error => Priority::Error,
+        };
+        logcat::write(priority, &format!("[{}] {}", tag, message));
+    }
+    let Ok(mut log) = ERROR_LOG.lock() else {
+        return;
+    };
+    let line = format!("{} {} [{}] {}\n", now_fmt(), level.as_str(), tag, message);
+    if log.ready {
+        log.append(&line);
+    } else if log.pending.len() < MAX_PENDING {
+        log.pending.push(line);
+    }
+}
+
+/// 启动以来报告过的警告与错误条数（check-config 据此判断配置是否有误）
+pub fn reported() -> u64 {
+    REPORTED.load(Ordering::Relaxed)
+}
+
+impl ErrorLog {
+    fn append(&self, line: &str) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if fs::metadata(path).is_ok_and(|m| m.len() >= self.max_bytes) {
+            let _ = fs::rename(path, format!("{}.1", path));
+        }
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+use crate::logfile::now_fmt;
+
+pub const DEFAULT_RECENT_CAPACITY: usize = 500;
+
+/// 引擎运行中产生的事件
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// 进程已被清理
+    Killed {
+        pid: i32,
+        name: String,
+        oom: i32,
+        resident_kb: u64,
+    },
+    /// 进程通过全部筛选，被选为本轮清理候选
+    Classified { pid: i32, name: String, oom: i32 },
+    /// 一轮清理结束
+    CycleComplete { killed: usize, freed_kb: u64 },
+    /// 可用内存跌破 mem_enter_mb，进入清理区间
+    PressureHigh { avail_kb: u64 },
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Killed {
+                pid,
+                name,
+                oom,
+                resident_kb,
+            } => write!(
+                f,
+                "已清理: {} (pid {}, oom {}, {}MB)",
+                name,
+                pid,
+                oom,
+                resident_kb / 1024
+            ),
+            Self::Classified { pid, name, oom } => {
+                write!(f, "候选: {} (pid {}, oom {})", name, pid, oom)
+            }
+            Self::CycleComplete { killed, freed_kb } => {
+                write!(f, "本轮清理 {} 个进程，释放 {}MB", killed, freed_kb / 1024)
+            }
+            Self::PressureHigh { avail_kb } => {
+                write!(f, "内存紧张: 可用 {}MB", avail_kb / 1024)
+            }
+        }
+    }
+}
+
+static SUBSCRIBERS: Mutex<Vec<Sender<Event>>> = Mutex::new(Vec::new());
+
+/// 最近事件的环形缓冲，不依赖文件日志；没配置 log_path 时也能事后查看
+struct Recent {
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+static RECENT: Mutex<Recent> = Mutex::new(Recent {
+    entries: VecDeque::new(),
+    capacity: DEFAULT_RECENT_CAPACITY,
+});
+
+/// 设置环形缓冲容量，0 表示不保留
+pub fn set_recent_capacity(capacity: usize) {
+    if let Ok(mut recent) = RECENT.lock() {
+        recent.capacity = capacity;
+        while recent.entries.len() > capacity {
+            recent.entries.pop_front();
+        }
+    }
+}
+
+/// 缓冲中的事件，从旧到新，每行带时间
+pub fn recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|recent| recent.entries.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn remember(event: &Event) {
+    // 候选和空轮次每轮都有，会很快挤掉真正有用的记录
+    if matches!(
+        event,
+        Event::Classified { .. } | Event::CycleComplete { killed: 0, .. }
+    ) {
+        return;
+    }
+    let Ok(mut recent) = RECENT.lock() else {
+        return;
+    };
+    if recent.capacity == 0 {
+        return;
+    }
+    if recent.entries.len() >= recent.capacity {
+        recent.entries.pop_front();
+    }
+    recent.entries.push_back(format!("{} {}", now_fmt(), event));
+}
+
+/// 订阅之后发生的全部事件；丢弃 Receiver 即取消订阅
+pub fn subscribe() -> Receiver<Event> {
+    let (tx, rx) = mpsc::channel();
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.push(tx);
+    }
+    rx
+}
+
+/// 记入环形缓冲并投递给所有订阅者，顺带清理已断开的订阅
+pub fn publish(event: Event) {
+    remember(&event);
+    let Ok(mut subscribers) = SUBSCRIBERS.lock() else {
+        return;
+    };
+    if subscribers.is_empty() {
+        return;
+    }
+    subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+}
@@ -0,0 +1,242 @@
+//! C 接口：供现有的 C/C++ root 工具、模块管理器直接嵌入引擎，而不必另起守护进程
+//!
+//! 头文件见 include/mem_cleaner.h
+
+use nix::fcntl::{open, OFlag};
+use nix::sys::stat::Mode;
+use nix::unistd::close;
+
+use std::ffi::{c_char, c_int, CStr};
+use std::os::unix::io::RawFd;
+use std::path::Path;
+
+use crate::boottime_secs;
+use crate::config::{load_config, AppConfig};
+use crate::engine::{perform_cleanup_blocking, CleanerState, CycleTrigger, ScannerResources};
+use crate::logfile::{LogBackend, Logger};
+use crate::platform::Platform;
+use crate::proc::ProcFs;
+use crate::session::SessionTotals;
+use crate::whitelist;
+use crate::{errlog, logcat, schema, watchdog};
+
+/// 嵌入方持有的引擎实例（对 C 侧不透明）
+pub struct MemCleaner {
+    config: AppConfig,
+    logger: Option<Logger>,
+    res: ScannerResources,
+    state: CleanerState,
+    proc_fd: RawFd,
+    started_at: u64, // boottime 秒；统计按句柄各自计算，不与同进程的其他实例混在一起
+}
+
+impl MemCleaner {
+    fn totals(&self, now: u64) -> SessionTotals {
+        SessionTotals {
+            kills: self.state.stats.total.kills,
+            freed_kb: self.state.stats.total.freed_kb,
+            uptime_secs: now.saturating_sub(self.started_at),
+        }
+    }
+}
+
+/// 引擎统计，字段含义与守护进程的 status 输出一致
+#[repr(C)]
+pub struct MemCleanerStats {
+    pub kills: u64,         // 该句柄累计清理进程数
+    pub freed_kb: u64,      // 该句柄累计释放内存
+    pub last_freed_kb: u64, // 上一轮释放的内存
+    pub uptime_secs: u64,
+}
+
+/// 按配置文件初始化引擎；log_path 可为 NULL（沿用配置中的 log_path）。失败或 panic 返回 NULL
+///
+/// # Safety
+///
+/// config_path 必须是有效的 NUL 结尾字符串；log_path 为 NULL 或有效的 NUL 结尾字符串
+#[no_mangle]
+pub unsafe extern "C" fn mem_cleaner_init(
+    config_path: *const c_char,
+    log_path: *const c_char,
+) -> *mut MemCleaner {
+    watchdog::isolate(|| {
+        if config_path.is_null() {
+            return std::ptr::null_mut();
+        }
+        let Ok(config_path) = CStr::from_ptr(config_path).to_str() else {
+            return std::ptr::null_mut();
+        };
+        let log_path = if log_path.is_null() {
+            None
+        } else {
+            CStr::from_ptr(log_path).to_str().ok().map(str::to_string)
+        };
+
+        let Ok(proc_fd) = open(
+            Path::new("/proc"),
+            OFlag::O_DIRECTORY | OFlag::O_RDONLY | OFlag::O_CLOEXEC,
+            Mode::empty(),
+        ) else {
+            return std::ptr::null_mut();
+        };
+
+        let config = load_config(config_path);
+        errlog::init(config.error_log.clone(), config.error_log_max_kb);
+        logcat::set_enabled(config.log_backend != LogBackend::File);
+        let mut logger = Logger::new(
+            log_path.or_else(|| config.log_path.clone()),
+            config.log_backend,
+            config.fsync_logs,
+        );
+        if let Some(l) = &mut logger {
+            l.apply_config(&config);
+            l.write_startup(&config);
+        }
+        let mut res = ScannerResources::new();
+        Platform::detect(&mut res.freezer);
+
+        Box::into_raw(Box::new(MemCleaner {
+            state: CleanerState::new(&config),
+            config,
+            logger,
+            res,
+            proc_fd,
+            started_at: boottime_secs(),
+        }))
+    })
+    .unwrap_or_else(|message| {
+        errlog::error("ffi", &format!("mem_cleaner_init panicked: {}", message));
+        std::ptr::null_mut()
+    })
+}
+
+/// 立即执行一轮清理（不做空闲判定），返回清理的进程数；handle 为 NULL 或本轮 panic 时返回 -1
+///
+/// 配置了 kill_delay_ms 时在调用内逐个间隔等待，直到本轮处理完
+///
+/// # Safety
+///
+/// handle 必须来自 mem_cleaner_init 且尚未 shutdown；不能在多个线程上同时调用
+#[no_mangle]
+pub unsafe extern "C" fn mem_cleaner_run_cycle(handle: *mut MemCleaner) -> c_int {
+    let Some(mc) = handle.as_mut() else {
+        return -1;
+    };
+    // panic 不能穿过 extern "C" 边界，接住后按失败返回
+    let result = watchdog::isolate(|| {
+        perform_cleanup_blocking(
+            &mc.config,
+            &mut mc.logger,
+            &mut mc.res,
+            &mut mc.state,
+            &ProcFs::new(mc.proc_fd),
+            CycleTrigger::Manual,
+        )
+    });
+    match result {
+        Ok(killed) => killed as c_int,
+        Err(message) => {
+            errlog::error(
+                "ffi",
+                &format!("mem_cleaner_run_cycle panicked: {}", message),
+            );
+            -1
+        }
+    }
+}
+
+/// 把统计写入 out，成功返回 0，参数为 NULL 时返回 -1
+///
+/// # Safety
+///
+/// handle 同 mem_cleaner_run_cycle；out 必须指向可写的 MemCleanerStats
+#[no_mangle]
+pub unsafe extern "C" fn mem_cleaner_stats(
+    handle: *const MemCleaner,
+    out: *mut MemCleanerStats,
+) -> c_int {
+    let (Some(mc), false) = (handle.as_ref(), out.is_null()) else {
+        return -1;
+    };
+    let totals = mc.totals(boottime_secs());
+    out.write(MemCleanerStats {
+        kills: totals.kills,
+        freed_kb: totals.freed_kb,
+        last_freed_kb: mc.state.last_freed_kb,
+        uptime_secs: totals.uptime_secs,
+    });
+    0
+}
+
+/// 执行 "<what> [schema=N]" 查询（what 为 stats/config/table，同控制 socket 的 query 命令），把 JSON 写入 buf
+///
+/// 与 snprintf 相同：最多写入 len - 1 字节并以 NUL 结尾，返回完整结果的长度（不含 NUL），
+/// 返回值 >= len 说明被截断；参数为 NULL、查询无效或查询中 panic 时返回 -1
+///
+/// # Safety
+///
+/// handle 同 mem_cleaner_run_cycle；query 必须是有效的 NUL 结尾字符串；buf 必须可写 len 字节
+#[no_mangle]
+pub unsafe extern "C" fn mem_cleaner_query(
+    handle: *mut MemCleaner,
+    query: *const c_char,
+    buf: *mut c_char,
+    len: usize,
+) -> c_int {
+    let (Some(mc), false) = (handle.as_mut(), query.is_null()) else {
+        return -1;
+    };
+    let Ok(query) = CStr::from_ptr(query).to_str() else {
+        return -1;
+    };
+    let now = boottime_secs();
+    let totals = mc.totals(now);
+    let json =
+        match watchdog::isolate(|| schema::query(query, &mc.config, &mut mc.state, totals, now)) {
+            Ok(Ok(json)) => json,
+            Ok(Err(_)) => return -1,
+            Err(message) => {
+                errlog::error("ffi", &format!("mem_cleaner_query panicked: {}", message));
+                return -1;
+            }
+        };
+    if !buf.is_null() && len > 0 {
+        let n = json.len().min(len - 1);
+        std::ptr::copy_nonoverlapping(json.as_ptr(), buf.cast::<u8>(), n);
+        buf.add(n).write(0);
+    }
+    json.len() as c_int
+}
+
+/// 写入该句柄的运行统计并释放引擎；handle 可为 NULL
+///
+/// # Safety
+///
+/// handle 必须来自 mem_cleaner_init，且之后不能再使用
+#[no_mangle]
+pub unsafe extern "C" fn mem_cleaner_shutdown(handle: *mut MemCleaner) {
+    if handle.is_null() {
+        return;
+    }
+    let mut mc = Box::from_raw(handle);
+    let proc_fd = mc.proc_fd;
+    let result = watchdog::isolate(move || {
+        mc.state.frozen.thaw_all(&mc.res.freezer);
+        let totals = mc.totals(boottime_secs());
+        if let Some(l) = &mut mc.logger {
+            l.write_session_summary("shutdown", totals);
+        }
+        if let Some(path) = &mc.config.whitelist_stats {
+            mc.state
+                .whitelist_hits
+                .save(path, whitelist::unix_secs(), true);
+        }
+    });
+    if let Err(message) = result {
+        errlog::error(
+            "ffi",
+            &format!("mem_cleaner_shutdown panicked: {}", message),
+        );
+    }
+    let _ = close(proc_fd);
+}
@@ -0,0 +1,195 @@
+use fxhash::FxHashMap;
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+
+use std::fs;
+
+/// cgroup 冻结器挂载信息（启动时从 /proc/mounts 探测一次）
+pub struct Freezer {
+    v2_root: Option<String>, // cgroup2 挂载点，Android 一般为 /sys/fs/cgroup
+    v1_root: Option<String>, // freezer 子系统挂载点，Android 11 为 /dev/freezer
+}
+
+/// 冻结状态查询结果
+pub enum FrozenCgroup {
+    V2(String), // 进程所在 cgroup 目录
+    V1,
+}
+
+/// 冻结模式下由本程序冻结进程的方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FreezeMethod {
+    Cgroup(String), // 写过 cgroup.freeze 的进程独占 cgroup 目录
+    Signal,         // SIGSTOP
+}
+
+impl Freezer {
+    pub fn detect() -> Self {
+        let mut v2_root = None;
+        let mut v1_root = None;
+
+        if let Ok(mounts) = fs::read_to_string("/proc/mounts") {
+            for line in mounts.lines() {
+                let mut parts = line.split_whitespace();
+                let (_, Some(mount_point), Some(fs_type), Some(options)) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                if fs_type == "cgroup2" && v2_root.is_none() {
+                    v2_root = Some(mount_point.to_string());
+                } else if fs_type == "cgroup" && options.split(',').any(|o| o == "freezer") {
+                    v1_root = Some(mount_point.to_string());
+                }
+            }
+        }
+
+        Self { v2_root, v1_root }
+    }
+
+    /// 只保留该 Android 版本系统实际使用的冻结器
+    pub fn select_for_sdk(&mut self, sdk: u32) {
+        match sdk {
+            31.. => self.v1_root = None,
+            30 => self.v2_root = None,
+            _ => {
+                self.v1_root = None;
+                self.v2_root = None;
+            }
+        }
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match (&self.v2_root, &self.v1_root) {
+            (Some(_), Some(_)) => "cgroup v1+v2",
+            (Some(_), None) => "cgroup v2",
+            (None, Some(_)) => "cgroup v1",
+            (None, None) => "none",
+        }
+    }
+
+    pub fn available(&self) -> bool {
+        self.v2_root.is_some() || self.v1_root.is_some()
+    }
+
+    /// 根据 /proc/<pid>/cgroup 内容判断进程是否已被冻结
+    pub fn frozen_cgroup(&self, cgroup_content: &str) -> Option<FrozenCgroup> {
+        for line in cgroup_content.lines() {
+            // 格式: hierarchy-ID:controller-list:cgroup-path
+            let mut parts = line.splitn(3, ':');
+            let (Some(_), Some(controllers), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+
+            if controllers.is_empty() {
+                if let Some(root) = &self.v2_root {
+                    let dir = format!("{}{}", root, path);
+                    let frozen = fs::read_to_string(format!("{}/cgroup.freeze", dir))
+                        .map(|s| s.trim() == "1")
+                        .unwrap_or(false);
+                    if frozen {
+                        return Some(FrozenCgroup::V2(dir));
+                    }
+                }
+            } else if controllers.split(',').any(|c| c == "freezer") {
+                if let Some(root) = &self.v1_root {
+                    let state = fs::read_to_string(format!("{}{}/freezer.state", root, path))
+                        .unwrap_or_default();
+                    if state.trim() == "FROZEN" {
+                        return Some(FrozenCgroup::V1);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// 冻结单个进程：Android 12+ 每个进程有独立的 cgroup v2 目录（uid_X/pid_Y），直接冻结它；
+    /// 共享 cgroup 的进程不能整组冻结，退回 SIGSTOP
+    pub fn freeze(&self, cgroup_content: &str, pid: i32) -> Option<FreezeMethod> {
+        if let Some(root) = &self.v2_root {
+            let own_group = cgroup_content
+                .lines()
+                .find_map(|l| l.strip_prefix("0::"))
+                .filter(|path| path.ends_with(&format!("/pid_{}", pid)));
+            if let Some(path) = own_group {
+                let dir = format!("{}{}", root, path);
+                if fs::write(format!("{}/cgroup.freeze", dir), "1").is_ok() {
+                    return Some(FreezeMethod::Cgroup(dir));
+                }
+            }
+        }
+        kill(Pid::from_raw(pid), Signal::SIGSTOP)
+            .ok()
+            .map(|()| FreezeMethod::Signal)
+    }
+
+    /// 解冻自己冻结的进程
+    pub fn unfreeze(&self, method: &FreezeMethod, pid: i32) -> bool {
+        match method {
+            FreezeMethod::Cgroup(dir) => fs::write(format!("{}/cgroup.freeze", dir), "0").is_ok(),
+            FreezeMethod::Signal => kill(Pid::from_raw(pid), Signal::SIGCONT).is_ok(),
+        }
+    }
+
+    /// 解冻单个进程：v2 直接解冻其 cgroup，v1 将其迁回 freezer 根组
+    pub fn thaw(&self, frozen: &FrozenCgroup, pid_s: &str) -> bool {
+        match frozen {
+            FrozenCgroup::V2(dir) => fs::write(format!("{}/cgroup.freeze", dir), "0").is_ok(),
+            FrozenCgroup::V1 => match &self.v1_root {
+                Some(root) => fs::write(format!("{}/cgroup.procs", root), pid_s).is_ok(),
+                None => false,
+            },
+        }
+    }
+}
+
+/// 冻结模式下被本程序冻结的进程
+pub struct FrozenProc {
+    pub name: String,
+    pub start_time: u64, // 与 pid 一起识别同一个进程
+    pub method: FreezeMethod,
+}
+
+/// 冻结的进程离开后台候选就不在进程表里了，单独记录，回到前台时解冻
+#[derive(Default)]
+pub struct FrozenApps {
+    procs: FxHashMap<i32, FrozenProc>,
+}
+
+impl FrozenApps {
+    pub fn insert(&mut self, pid: i32, proc: FrozenProc) {
+        self.procs.insert(pid, proc);
+    }
+
+    pub fn contains(&self, pid: i32, start_time: u64) -> bool {
+        self.procs
+            .get(&pid)
+            .is_some_and(|p| p.start_time == start_time)
+    }
+
+    pub fn len(&self) -> usize {
+        self.procs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.procs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &FrozenProc)> {
+        self.procs.iter().map(|(&pid, p)| (pid, p))
+    }
+
+    pub fn remove(&mut self, pid: i32) -> Option<FrozenProc> {
+        self.procs.remove(&pid)
+    }
+
+    /// 退出前全部解冻，不留下停住的进程
+    pub fn thaw_all(&mut self, freezer: &Freezer) {
+        for (pid, proc) in self.procs.drain() {
+            freezer.unfreeze(&proc.method, pid);
+        }
+    }
+}
@@ -0,0 +1,573 @@
+use fxhash::{FxHashMap, FxHashSet};
+
+use std::collections::VecDeque;
+use std::fs;
+
+/// 单个包的击杀历史
+#[derive(Debug, Default, Clone)]
+pub struct PackageHistory {
+    pub kills: u32,
+    pub last_kill: u64, // boottime 秒
+    pub oom_sum: i64,
+    pub freed_kb_sum: u64,
+}
+
+impl PackageHistory {
+    pub fn avg_oom(&self) -> i64 {
+        self.oom_sum / self.kills.max(1) as i64
+    }
+
+    pub fn avg_freed_kb(&self) -> u64 {
+        self.freed_kb_sum / self.kills.max(1) as u64
+    }
+}
+
+/// 按包名（cmdline 中 ':' 之前部分）汇总的击杀历史
+#[derive(Default)]
+pub struct KillHistory {
+    packages: FxHashMap<String, PackageHistory>,
+}
+
+impl KillHistory {
+    pub fn record_kill(&mut self, package: &str, oom: i32, freed_kb: u64, now: u64) {
+        let entry = self.packages.entry(package.to_string()).or_default();
+        entry.kills += 1;
+        entry.last_kill = now;
+        entry.oom_sum += oom as i64;
+        entry.freed_kb_sum += freed_kb;
+    }
+
+    pub fn get(&self, package: &str) -> Option<&PackageHistory> {
+        self.packages.get(package)
+    }
+
+    pub fn kills(&self, package: &str) -> u32 {
+        self.packages.get(package).map_or(0, |h| h.kills)
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.packages.values().map(|h| h.kills).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PackageHistory)> {
+        self.packages.iter().map(|(p, h)| (p.as_str(), h))
+    }
+
+    /// 导入备份时使用，覆盖同名包的记录
+    pub fn restore(&mut self, package: &str, history: PackageHistory) {
+        self.packages.insert(package.to_string(), history);
+    }
+
+    /// 保存到 table_state 旁的历史文件，重启后 `stats <package>` 仍能看到以前的击杀
+    ///
+    /// 每行 `<包名> <击杀次数> <距上次击杀秒数> <oom 累计> <释放累计(kB)>`，
+    /// 时间与 state export 一样存为相对值，换过开机也能还原
+    pub fn save(&self, path: &str, now: u64) {
+        let mut out = format!("{}\n", HISTORY_HEADER);
+        for (package, h) in &self.packages {
+            out.push_str(&format!(
+                "{} {} {} {} {}\n",
+                package,
+                h.kills,
+                now.saturating_sub(h.last_kill),
+                h.oom_sum,
+                h.freed_kb_sum
+            ));
+        }
+        let tmp = format!("{}.tmp", path);
+        if fs::write(&tmp, out).is_err() || fs::rename(&tmp, path).is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+
+    /// 从 save 写的文件恢复，返回恢复的包数；覆盖同名包的记录
+    pub fn load(&mut self, path: &str, now: u64) -> usize {
+        let Ok(content) = fs::read_to_string(path) else {
+            return 0;
+        };
+        let mut lines = content.lines();
+        if lines.next() != Some(HISTORY_HEADER) {
+            return 0;
+        }
+        let mut restored = 0;
+        for line in lines {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            let [package, kills, ago, oom_sum, freed_kb_sum] = f[..] else {
+                continue;
+            };
+            let (Ok(kills), Ok(ago), Ok(oom_sum), Ok(freed_kb_sum)) = (
+                kills.parse(),
+                ago.parse::<u64>(),
+                oom_sum.parse(),
+                freed_kb_sum.parse(),
+            ) else {
+                continue;
+            };
+            self.restore(
+                package,
+                PackageHistory {
+                    kills,
+                    last_kill: now.saturating_sub(ago),
+                    oom_sum,
+                    freed_kb_sum,
+                },
+            );
+            restored += 1;
+        }
+        restored
+    }
+}
+
+const HISTORY_HEADER: &str = "# mem_cleaner history 1";
+
+/// 击杀历史文件：放在 table_state 旁边，随它一起保存与恢复
+pub fn state_path(table_state: &str) -> String {
+    format!("{}.history", table_state)
+}
+
+/// 被杀后多久内没重生就不再关注
+const RESPAWN_WATCH_SECS: u64 = 600;
+const BACKOFF_BASE_SECS: u64 = 600;
+const BACKOFF_MAX_SECS: u64 = 24 * 3600;
+
+/// 快速重生进程的退避状态
+struct Backoff {
+    strikes: u32,
+    until: u64,
+}
+
+/// 重生检测：记录被杀进程名，统计其重新出现的耗时，重生过快的进程进入指数退避
+pub struct RespawnTracker {
+    watches: FxHashMap<String, u64>, // 进程名 -> 击杀时刻
+    backoff: FxHashMap<String, Backoff>,
+    fast_secs: u64, // 在此时间内重生视为"杀了也白杀"，0 表示只统计不退避
+}
+
+impl RespawnTracker {
+    pub fn new(fast_secs: u64) -> Self {
+        Self {
+            watches: FxHashMap::default(),
+            backoff: FxHashMap::default(),
+            fast_secs,
+        }
+    }
+
+    pub fn fast_secs(&self) -> u64 {
+        self.fast_secs
+    }
+
+    /// 配置热加载：已有的观察与退避保留
+    pub fn set_fast_secs(&mut self, fast_secs: u64) {
+        self.fast_secs = fast_secs;
+    }
+
+    pub fn watch(&mut self, name: &str, now: u64) {
+        self.watches.insert(name.to_string(), now);
+    }
+
+    /// 最早一次仍在观察中的击杀时刻；没有观察项时返回 None
+    pub fn earliest_kill(&self) -> Option<u64> {
+        self.watches.values().copied().min()
+    }
+
+    /// 新进程出现时调用，若是被观察的进程则返回重生耗时（秒）
+    pub fn check(&mut self, name: &str, started_at: u64) -> Option<u64> {
+        let killed_at = *self.watches.get(name)?;
+        if started_at < killed_at {
+            return None;
+        }
+        self.watches.remove(name);
+        let latency = started_at - killed_at;
+
+        if self.fast_secs > 0 && latency <= self.fast_secs {
+            let entry = self.backoff.entry(name.to_string()).or_insert(Backoff {
+                strikes: 0,
+                until: 0,
+            });
+            entry.strikes += 1;
+            let secs = BACKOFF_BASE_SECS
+                .saturating_mul(1 << (entry.strikes - 1).min(16))
+                .min(BACKOFF_MAX_SECS);
+            entry.until = started_at + secs;
+        } else {
+            self.backoff.remove(name);
+        }
+        Some(latency)
+    }
+
+    /// 剩余退避时长，不在退避中返回 None
+    pub fn backoff_remaining(&self, name: &str, now: u64) -> Option<u64> {
+        self.backoff
+            .get(name)
+            .filter(|b| b.until > now)
+            .map(|b| b.until - now)
+    }
+
+    pub fn expire(&mut self, now: u64) {
+        self.watches
+            .retain(|_, killed_at| now.saturating_sub(*killed_at) < RESPAWN_WATCH_SECS);
+    }
+
+    /// 仍在退避中的进程：进程名、累计次数、剩余秒数
+    pub fn backoffs(&self, now: u64) -> impl Iterator<Item = (&str, u32, u64)> {
+        self.backoff
+            .iter()
+            .filter(move |(_, b)| b.until > now)
+            .map(move |(name, b)| (name.as_str(), b.strikes, b.until - now))
+    }
+
+    pub fn restore_backoff(&mut self, name: &str, strikes: u32, remaining: u64, now: u64) {
+        self.backoff.insert(
+            name.to_string(),
+            Backoff {
+                strikes,
+                until: now + remaining,
+            },
+        );
+    }
+}
+
+/// 从进程名中取包名：com.foo.app:push -> com.foo.app
+pub fn package_name(cmdline: &str) -> &str {
+    cmdline.split(':').next().unwrap_or(cmdline)
+}
+
+const RELAUNCH_COOLDOWN_SECS: u64 = 3600;
+
+/// 用户重新打开检测：被杀的包在窗口期内回到前台，说明用户还在用它
+///
+/// 每发生一次，该包的冷却期按次数线性延长，冷却期内不再清理
+pub struct RelaunchTracker {
+    watches: FxHashMap<String, u64>, // 包名 -> 击杀时刻
+    cooldown: FxHashMap<String, u64>,
+    annoyances: FxHashMap<String, u32>,
+    window_secs: u64, // 0 表示关闭
+}
+
+impl RelaunchTracker {
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            watches: FxHashMap::default(),
+            cooldown: FxHashMap::default(),
+            annoyances: FxHashMap::default(),
+            window_secs,
+        }
+    }
+
+    pub fn window_secs(&self) -> u64 {
+        self.window_secs
+    }
+
+    pub fn set_window_secs(&mut self, window_secs: u64) {
+        self.window_secs = window_secs;
+    }
+
+    pub fn watch(&mut self, package: &str, now: u64) {
+        if self.window_secs > 0 {
+            self.watches.insert(package.to_string(), now);
+        }
+    }
+
+    pub fn is_watching(&self) -> bool {
+        !self.watches.is_empty()
+    }
+
+    /// 包回到前台时调用，若是窗口期内被杀的包则返回累计次数
+    pub fn check(&mut self, package: &str, now: u64) -> Option<u32> {
+        let killed_at = self.watches.remove(package)?;
+        if now.saturating_sub(killed_at) > self.window_secs {
+            return None;
+        }
+        let count = self.annoyances.entry(package.to_string()).or_insert(0);
+        *count += 1;
+        self.cooldown.insert(
+            package.to_string(),
+            now + RELAUNCH_COOLDOWN_SECS * *count as u64,
+        );
+        Some(*count)
+    }
+
+    pub fn cooldown_remaining(&self, package: &str, now: u64) -> Option<u64> {
+        self.cooldown
+            .get(package)
+            .filter(|&&until| until > now)
+            .map(|until| until - now)
+    }
+
+    /// 各包被用户重新打开的累计次数
+    pub fn annoyances(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.annoyances.iter().map(|(p, &n)| (p.as_str(), n))
+    }
+
+    /// 冷却中的包及剩余秒数
+    pub fn cooldowns(&self, now: u64) -> impl Iterator<Item = (&str, u64)> {
+        self.cooldown
+            .iter()
+            .filter(move |(_, &until)| until > now)
+            .map(move |(p, &until)| (p.as_str(), until - now))
+    }
+
+    pub fn restore_annoyance(&mut self, package: &str, count: u32) {
+        self.annoyances.insert(package.to_string(), count);
+    }
+
+    pub fn restore_cooldown(&mut self, package: &str, remaining: u64, now: u64) {
+        self.cooldown.insert(package.to_string(), now + remaining);
+    }
+
+    pub fn expire(&mut self, now: u64) {
+        let window = self.window_secs;
+        self.watches
+            .retain(|_, killed_at| now.saturating_sub(*killed_at) <= window);
+        self.cooldown.retain(|_, until| *until > now);
+    }
+}
+
+const STORM_WINDOW_SECS: u64 = 3600;
+const STORM_PAUSE_SECS: u64 = 3600;
+
+/// 按包的击杀节流：同一个包两次击杀至少相隔冷却时间；
+/// 一小时内被杀超过上限视为重生风暴（杀了马上又被拉起），暂时放过它一小时
+pub struct KillThrottle {
+    kills: FxHashMap<String, VecDeque<u64>>, // 包名 -> 窗口内的击杀时刻
+    storms: FxHashMap<String, u64>,          // 包名 -> 放过到何时
+    cooldown_secs: u64,                      // 0 表示不限制
+    storm_kills: u32,                        // 0 表示不检测
+}
+
+impl KillThrottle {
+    pub fn new(cooldown_secs: u64, storm_kills: u32) -> Self {
+        Self {
+            kills: FxHashMap::default(),
+            storms: FxHashMap::default(),
+            cooldown_secs,
+            storm_kills,
+        }
+    }
+
+    pub fn set_limits(&mut self, cooldown_secs: u64, storm_kills: u32) {
+        self.cooldown_secs = cooldown_secs;
+        self.storm_kills = storm_kills;
+    }
+
+    fn enabled(&self) -> bool {
+        self.cooldown_secs > 0 || self.storm_kills > 0
+    }
+
+    pub fn clear(&mut self) {
+        self.kills.clear();
+        self.storms.clear();
+    }
+
+    /// 记录一次击杀；该包因此进入风暴放过期时返回 true
+    pub fn record(&mut self, package: &str, now: u64) -> bool {
+        if !self.enabled() {
+            return false;
+        }
+        let times = self.kills.entry(package.to_string()).or_default();
+        times.push_back(now);
+        while times
+            .front()
+            .is_some_and(|&t| now.saturating_sub(t) >= STORM_WINDOW_SECS)
+        {
+            times.pop_front();
+        }
+        if self.storm_kills > 0 && times.len() > self.storm_kills as usize {
+            times.clear();
+            self.storms
+                .insert(package.to_string(), now + STORM_PAUSE_SECS);
+            return true;
+        }
+        false
+    }
+
+    /// 仍在冷却或风暴放过期内，本轮不杀
+    pub fn is_throttled(&self, package: &str, now: u64) -> bool {
+        if self.storms.get(package).is_some_and(|&until| until > now) {
+            return true;
+        }
+        self.cooldown_secs > 0
+            && self
+                .kills
+                .get(package)
+                .and_then(|times| times.back())
+                .is_some_and(|&last| now.saturating_sub(last) < self.cooldown_secs)
+    }
+
+    /// 风暴放过中的包及剩余秒数
+    pub fn storms(&self, now: u64) -> impl Iterator<Item = (&str, u64)> {
+        self.storms
+            .iter()
+            .filter(move |(_, &until)| until > now)
+            .map(move |(p, &until)| (p.as_str(), until - now))
+    }
+
+    pub fn expire(&mut self, now: u64) {
+        let keep = self.cooldown_secs.max(STORM_WINDOW_SECS);
+        self.kills.retain(|_, times| {
+            times
+                .back()
+                .is_some_and(|&last| now.saturating_sub(last) < keep)
+        });
+        self.storms.retain(|_, until| *until > now);
+    }
+}
+
+/// 前台切换宽限：刚离开前台的 App 很可能马上被切回来，离开后的一段时间内不清理
+pub struct SwitchGrace {
+    previous: FxHashSet<String>, // 上一轮扫描时处于前台的包
+    current: FxHashSet<String>,
+    left_at: FxHashMap<String, u64>, // 包名 -> 离开前台的时刻
+    grace_secs: u64,                 // 0 表示关闭
+}
+
+impl SwitchGrace {
+    pub fn new(grace_secs: u64) -> Self {
+        Self {
+            previous: FxHashSet::default(),
+            current: FxHashSet::default(),
+            left_at: FxHashMap::default(),
+            grace_secs,
+        }
+    }
+
+    pub fn set_grace_secs(&mut self, grace_secs: u64) {
+        self.grace_secs = grace_secs;
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.grace_secs > 0
+    }
+
+    pub fn saw_foreground(&mut self, package: &str) {
+        if self.enabled() && !self.current.contains(package) {
+            self.current.insert(package.to_string());
+        }
+    }
+
+    /// 上一轮还在前台（本轮刚切走），或离开前台未满宽限期
+    pub fn in_grace(&self, package: &str, now: u64) -> bool {
+        self.previous.contains(package)
+            || self
+                .left_at
+                .get(package)
+                .is_some_and(|&t| now.saturating_sub(t) < self.grace_secs)
+    }
+
+    /// 一轮扫描结束：上一轮在前台、本轮不在的包从此刻开始计时
+    pub fn end_cycle(&mut self, now: u64) {
+        let grace = self.grace_secs;
+        self.left_at.retain(|_, t| now.saturating_sub(*t) < grace);
+        for package in self.previous.difference(&self.current) {
+            self.left_at.insert(package.clone(), now);
+        }
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+
+/// 最近在前台出现过的 N 个包，不论离开多久都不清理（多任务切换最常用的几个）
+pub struct RecentApps {
+    packages: VecDeque<String>, // 最近一次出现在前台的在前
+    capacity: usize,            // 0 表示关闭
+}
+
+impl RecentApps {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            packages: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.packages.truncate(capacity);
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn saw_foreground(&mut self, package: &str) {
+        if !self.enabled() || self.packages.front().is_some_and(|p| p == package) {
+            return;
+        }
+        if let Some(i) = self.packages.iter().position(|p| p == package) {
+            self.packages.remove(i);
+        }
+        self.packages.push_front(package.to_string());
+        self.packages.truncate(self.capacity);
+    }
+
+    pub fn contains(&self, package: &str) -> bool {
+        self.packages.iter().any(|p| p == package)
+    }
+
+    /// 从新到旧
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.packages.iter().map(String::as_str)
+    }
+}
+
+/// 杀不掉的进程：击杀后同一进程（pid 与启动时间都不变）仍然出现在候选中
+#[derive(Default)]
+pub struct SurvivorTracker {
+    attempts: FxHashMap<i32, (u64, u32)>, // pid -> (启动时间, 已击杀次数)
+    ignored: FxHashMap<i32, u64>,         // 已放弃处理的 pid -> 启动时间
+}
+
+impl SurvivorTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记一次击杀，返回此前已经杀过却没死的次数
+    pub fn record_attempt(&mut self, pid: i32, start_time: u64) -> u32 {
+        let entry = self.attempts.entry(pid).or_insert((start_time, 0));
+        if entry.0 != start_time {
+            *entry = (start_time, 0);
+        }
+        entry.1 += 1;
+        entry.1 - 1
+    }
+
+    pub fn ignore(&mut self, pid: i32, start_time: u64) {
+        self.attempts.remove(&pid);
+        self.ignored.insert(pid, start_time);
+    }
+
+    pub fn is_ignored(&self, pid: i32, start_time: u64) -> bool {
+        self.ignored.get(&pid) == Some(&start_time)
+    }
+
+    /// 只保留仍然存在的进程
+    pub fn retain(&mut self, alive: impl Fn(i32, u64) -> bool) {
+        self.attempts.retain(|&pid, (start, _)| alive(pid, *start));
+        self.ignored.retain(|&pid, start| alive(pid, *start));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_survives_save_and_load() {
+        let path = std::env::temp_dir().join(format!("mc_history_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut history = KillHistory::default();
+        history.record_kill("com.foo", 900, 2048, 100);
+        history.record_kill("com.foo", 950, 4096, 400);
+        history.record_kill("com.bar", 800, 1024, 300);
+        history.save(path, 500);
+
+        // 重启后 boottime 从头计时，距上次击杀的时长保持不变
+        let mut restored = KillHistory::default();
+        assert_eq!(restored.load(path, 1000), 2);
+        let foo = restored.get("com.foo").unwrap();
+        assert_eq!((foo.kills, foo.last_kill), (2, 900));
+        assert_eq!((foo.avg_oom(), foo.avg_freed_kb()), (925, 3072));
+        assert_eq!(restored.kills("com.bar"), 1);
+        let _ = fs::remove_file(path);
+    }
+}
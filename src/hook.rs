@@ -0,0 +1,168 @@
+//! 清理事件钩子：每轮有进程被清理后调用用户程序，或向 FIFO 写一行 JSON
+//!
+//! 程序的参数为被清理的包名，stdin 为同一份 JSON：
+//!
+//! ```text
+//! {"killed":[{"pid":4567,"name":"com.foo:push","package":"com.foo","freed_kb":51200}],"freed_kb":51200}
+//! ```
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::events::{self, Event};
+use crate::protocol::push_json_str;
+use crate::{dedup, history};
+
+pub const DEFAULT_HOOK_TIMEOUT_MS: u64 = 5000;
+const WAIT_POLL: Duration = Duration::from_millis(50);
+
+/// 一次被清理的进程
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookKill {
+    pub pid: i32,
+    pub name: String,
+    pub freed_kb: u64,
+}
+
+/// 在独立线程订阅事件总线，攒齐一轮的击杀后执行钩子；钩子卡住也只耽误这个线程
+pub fn spawn(path: String, timeout: Duration) -> io::Result<()> {
+    let events = events::subscribe();
+    thread::Builder::new()
+        .name("hook".to_string())
+        .spawn(move || {
+            let mut batch = Vec::new();
+            for event in events {
+                match event {
+                    Event::Killed {
+                        pid,
+                        name,
+                        resident_kb,
+                        ..
+                    } => batch.push(HookKill {
+                        pid,
+                        name,
+                        freed_kb: resident_kb,
+                    }),
+                    Event::CycleComplete { freed_kb, .. } if !batch.is_empty() => {
+                        run(&path, timeout, &batch, freed_kb);
+                        batch.clear();
+                    }
+                    _ => {}
+                }
+            }
+        })?;
+    Ok(())
+}
+
+fn run(path: &str, timeout: Duration, batch: &[HookKill], freed_kb: u64) {
+    let payload = payload(batch, freed_kb);
+    let is_fifo = std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo());
+    let result = if is_fifo {
+        write_fifo(path, &payload)
+    } else {
+        run_program(path, timeout, batch, &payload)
+    };
+    if let Err(e) = result {
+        dedup::warn("hook", &format!("Hook {} failed: {}", path, e));
+    }
+}
+
+/// 非阻塞打开：没有读端时报 ENXIO，本轮丢弃而不是等下去
+fn write_fifo(path: &str, payload: &str) -> io::Result<()> {
+    let mut fifo = OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+        .open(path)?;
+    fifo.write_all(payload.as_bytes())?;
+    fifo.write_all(b"\n")
+}
+
+fn run_program(path: &str, timeout: Duration, batch: &[HookKill], payload: &str) -> io::Result<()> {
+    let mut child = Command::new(path)
+        .args(batch.iter().map(|k| history::package_name(&k.name)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // 程序不读 stdin 时写入会 EPIPE，不算失败
+        let _ = stdin.write_all(payload.as_bytes());
+    }
+    wait_with_timeout(&mut child, timeout)
+}
+
+/// 超时即 SIGKILL 并回收，不留僵尸进程
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> io::Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return if status.success() {
+                Ok(())
+            } else {
+                Err(io::Error::other(format!("exited with {}", status)))
+            };
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "timed out"));
+        }
+        thread::sleep(WAIT_POLL);
+    }
+}
+
+/// 传给钩子的 JSON（单行）
+pub fn payload(batch: &[HookKill], freed_kb: u64) -> String {
+    let mut out = String::from("{\"killed\":[");
+    for (i, kill) in batch.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!("{{\"pid\":{},\"name\":", kill.pid));
+        push_json_str(&mut out, &kill.name);
+        out.push_str(",\"package\":");
+        push_json_str(&mut out, history::package_name(&kill.name));
+        out.push_str(&format!(",\"freed_kb\":{}}}", kill.freed_kb));
+    }
+    out.push_str(&format!("],\"freed_kb\":{}}}", freed_kb));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_lists_kills() {
+        let batch = [
+            HookKill {
+                pid: 4567,
+                name: "com.foo:push".to_string(),
+                freed_kb: 51200,
+            },
+            HookKill {
+                pid: 4568,
+                name: "com.bar".to_string(),
+                freed_kb: 1024,
+            },
+        ];
+        assert_eq!(
+            payload(&batch, 52224),
+            "{\"killed\":[{\"pid\":4567,\"name\":\"com.foo:push\",\"package\":\"com.foo\",\"freed_kb\":51200},\
+             {\"pid\":4568,\"name\":\"com.bar\",\"package\":\"com.bar\",\"freed_kb\":1024}],\"freed_kb\":52224}"
+        );
+    }
+
+    #[test]
+    fn hung_hook_is_killed_after_timeout() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let started = Instant::now();
+        let err = wait_with_timeout(&mut child, Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
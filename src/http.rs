@@ -0,0 +1,228 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::time::{Duration, Instant};
+
+use crate::control::{constant_time_eq, AccessPolicy};
+
+// 整个请求头必须在这段时间内读完，慢速客户端不能拖住主循环
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// 本机 HTTP 接口：把少量 REST 路由翻译成控制命令，供伴生 App / Tasker 的 HTTP 动作调用
+///
+/// GET /status、GET /candidates、GET /metrics、POST /clean、POST /reload、POST /pause[?seconds=N]、POST /resume
+pub struct HttpServer {
+    listener: TcpListener,
+    token: String,
+    access: AccessPolicy,
+}
+
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    authorized: bool,
+}
+
+impl HttpServer {
+    /// 只允许绑定回环地址，且必须配置 token
+    pub fn bind(addr: &str, access: AccessPolicy) -> io::Result<Self> {
+        let addr: SocketAddr = addr
+            .parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid address"))?;
+        if !addr.ip().is_loopback() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only loopback addresses are allowed",
+            ));
+        }
+        let Some(token) = access.token.clone().filter(|t| !t.is_empty()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "control_token is required",
+            ));
+        };
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            token,
+            access,
+        })
+    }
+
+    /// 接受一个连接并解析请求头；没有待处理连接或请求无效时返回 None
+    ///
+    /// 对端 UID 不在允许列表时不读任何数据，直接回 403 关闭
+    pub fn accept(&self) -> Option<(TcpStream, HttpRequest)> {
+        let (stream, peer) = self.listener.accept().ok()?;
+        let local = stream.local_addr().ok()?;
+        stream.set_nonblocking(false).ok()?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+        if !self.access.uid_allowed(tcp_peer_uid(&peer, &local)) {
+            respond(stream, 403, "ERR");
+            return None;
+        }
+
+        let head = read_head(&stream, Instant::now() + CLIENT_TIMEOUT)?;
+        let mut lines = head.lines();
+        let mut parts = lines.next()?.split_whitespace();
+        let method = parts.next()?.to_string();
+        let target = parts.next()?;
+        let (path, query) = match target.split_once('?') {
+            Some((p, q)) => (p.to_string(), Some(q.to_string())),
+            None => (target.to_string(), None),
+        };
+
+        let mut authorized = query
+            .as_deref()
+            .and_then(|q| query_param(q, "token"))
+            .is_some_and(|t| self.token_matches(t));
+        for line in lines.take_while(|l| !l.trim().is_empty()) {
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("authorization")
+                    && value
+                        .trim()
+                        .strip_prefix("Bearer ")
+                        .is_some_and(|t| self.token_matches(t))
+                {
+                    authorized = true;
+                }
+            }
+        }
+
+        Some((
+            stream,
+            HttpRequest {
+                method,
+                path,
+                query,
+                authorized,
+            },
+        ))
+    }
+
+    fn token_matches(&self, token: &str) -> bool {
+        constant_time_eq(token.as_bytes(), self.token.as_bytes())
+    }
+}
+
+impl AsFd for HttpServer {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.listener.as_fd()
+    }
+}
+
+impl HttpRequest {
+    /// 翻译成控制命令；返回 Err 时为 HTTP 状态码
+    pub fn to_command(&self) -> Result<String, u16> {
+        if !self.authorized {
+            return Err(401);
+        }
+        match (self.method.as_str(), self.path.as_str()) {
+            ("GET", "/status") => Ok("status".to_string()),
+            ("GET", "/candidates") => Ok("candidates".to_string()),
+            ("GET", "/metrics") => Ok("metrics".to_string()),
+            ("POST", "/clean") => Ok("clean".to_string()),
+            ("POST", "/reload") => Ok("reload".to_string()),
+            ("POST", "/resume") => Ok("resume".to_string()),
+            ("POST", "/pause") => {
+                match self
+                    .query
+                    .as_deref()
+                    .and_then(|q| query_param(q, "seconds"))
+                {
+                    Some(secs) => Ok(format!("pause {}", secs)),
+                    None => Ok("pause".to_string()),
+                }
+            }
+            (
+                _,
+                "/status" | "/candidates" | "/metrics" | "/clean" | "/reload" | "/pause"
+                | "/resume",
+            ) => Err(405),
+            _ => Err(404),
+        }
+    }
+}
+
+/// 回环 TCP 连接的对端 UID：在 /proc/net/tcp{,6} 中找本地端口为对端端口、远端端口为我们的连接
+fn tcp_peer_uid(peer: &SocketAddr, local: &SocketAddr) -> Option<u32> {
+    let local_port = format!(":{:04X}", peer.port());
+    let rem_port = format!(":{:04X}", local.port());
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let Ok(content) = fs::read_to_string(table) else {
+            continue;
+        };
+        for line in content.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() > 7
+                && fields[1].ends_with(&local_port)
+                && fields[2].ends_with(&rem_port)
+            {
+                return fields[7].parse().ok();
+            }
+        }
+    }
+    None
+}
+
+/// 读到空行为止的请求头；超过总时限或大小上限时放弃
+fn read_head(mut stream: &TcpStream, deadline: Instant) -> Option<String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        if remaining.is_zero() || buf.len() >= MAX_REQUEST_BYTES {
+            return None;
+        }
+        stream.set_read_timeout(Some(remaining)).ok()?;
+        match stream.read(&mut chunk).ok()? {
+            0 => break,
+            n => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+    buf.truncate(MAX_REQUEST_BYTES);
+    String::from_utf8(buf).ok()
+}
+
+fn query_param<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+/// 以纯文本回复；body 为控制命令的回复（首词 OK/ERR）
+pub fn respond(stream: TcpStream, status: u16, body: &str) {
+    respond_with(stream, status, "text/plain; charset=utf-8", body);
+}
+
+pub fn respond_with(mut stream: TcpStream, status: u16, content_type: &str, body: &str) {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    };
+    let body = if body.ends_with('\n') {
+        body.to_string()
+    } else {
+        format!("{}\n", body)
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    );
+}
@@ -0,0 +1,132 @@
+//! cmdline 读不到进程名时的身份推断：UID → 包名映射与 SELinux 上下文
+//!
+//! zygote 子进程在特化过程中 cmdline 会短暂为空，comm 也还是 "main"/"zygote64"；
+//! 此时 attr/current 仍是 zygote 域，这种进程留到下一轮再看，不按残缺的名字归类。
+
+use fxhash::FxHashMap;
+
+use std::fs;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::policy::PER_USER_RANGE;
+
+const PACKAGES_LIST: &str = "/data/system/packages.list";
+// 安装/卸载才会改动，定期看一眼 mtime 即可
+const PACKAGES_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// /data/system/packages.list 中 appId → 包名；sharedUserId 的多个包共用一个 appId
+#[derive(Default)]
+pub struct PackageList {
+    by_app_id: FxHashMap<u32, Vec<String>>,
+    mtime: Option<SystemTime>,
+    last_check: Option<Instant>,
+}
+
+impl PackageList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// uid 唯一对应的包名；共享 UID 或未知时返回 None
+    pub fn unique_package(&mut self, uid: u32) -> Option<&str> {
+        if self
+            .last_check
+            .is_none_or(|t| t.elapsed() >= PACKAGES_CHECK_INTERVAL)
+        {
+            self.refresh();
+        }
+        match self.by_app_id.get(&(uid % PER_USER_RANGE))?.as_slice() {
+            [package] => Some(package),
+            _ => None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.last_check = Some(Instant::now());
+        let mtime = fs::metadata(PACKAGES_LIST).and_then(|m| m.modified()).ok();
+        if mtime.is_none() || mtime == self.mtime {
+            return;
+        }
+        if let Ok(text) = fs::read_to_string(PACKAGES_LIST) {
+            self.by_app_id = parse_packages_list(&text);
+            self.mtime = mtime;
+        }
+    }
+}
+
+/// 每行 `<包名> <appId> <debuggable> <数据目录> <seinfo> <gids>`
+fn parse_packages_list(text: &str) -> FxHashMap<u32, Vec<String>> {
+    let mut by_app_id: FxHashMap<u32, Vec<String>> = FxHashMap::default();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(package), Some(Ok(app_id))) =
+            (fields.next(), fields.next().map(str::parse::<u32>))
+        {
+            by_app_id
+                .entry(app_id)
+                .or_default()
+                .push(package.to_string());
+        }
+    }
+    by_app_id
+}
+
+/// attr/current（如 `u:r:untrusted_app:s0:c123,c256`）中的域
+pub fn selinux_domain(attr: &[u8]) -> Option<&str> {
+    let context = std::str::from_utf8(attr).ok()?;
+    context
+        .trim_end_matches(['\0', '\n'])
+        .split(':')
+        .nth(2)
+        .filter(|d| !d.is_empty())
+}
+
+/// 仍在 zygote 域：fork 出来还没特化成 App
+pub fn is_zygote_domain(domain: &str) -> bool {
+    matches!(domain, "zygote" | "webview_zygote" | "app_zygote")
+}
+
+/// 由包名和 comm 拼出进程名：comm 带 ':' 时保留其后的进程后缀
+pub fn process_name(package: &str, comm: &str) -> String {
+    match comm.split_once(':') {
+        Some((_, suffix)) if !suffix.is_empty() => format!("{}:{}", package, suffix),
+        _ => package.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_uids_are_ambiguous() {
+        let mut list = PackageList::new();
+        list.by_app_id = parse_packages_list(
+            "com.foo 10123 0 /data/user/0/com.foo default:targetSdkVersion=33 3003\n\
+             com.shared.a 10200 0 /data/user/0/com.shared.a platform 3003\n\
+             com.shared.b 10200 0 /data/user/0/com.shared.b platform 3003\n",
+        );
+        list.last_check = Some(Instant::now());
+        assert_eq!(list.unique_package(10123), Some("com.foo"));
+        // 第二个用户的同一 App
+        assert_eq!(list.unique_package(1_010_123), Some("com.foo"));
+        assert_eq!(list.unique_package(10200), None);
+        assert_eq!(list.unique_package(10999), None);
+    }
+
+    #[test]
+    fn reads_domain_and_suffix() {
+        assert_eq!(
+            selinux_domain(b"u:r:untrusted_app:s0:c123,c256,c512,c768\0"),
+            Some("untrusted_app")
+        );
+        assert_eq!(selinux_domain(b"u:r:zygote:s0\n"), Some("zygote"));
+        assert_eq!(selinux_domain(b"unconfined\n"), None);
+        assert!(is_zygote_domain("zygote"));
+        assert!(!is_zygote_domain("untrusted_app"));
+
+        assert_eq!(process_name("com.foo", "com.foo:push"), "com.foo:push");
+        assert_eq!(process_name("com.foo", "oo.bar:remote"), "com.foo:remote");
+        assert_eq!(process_name("com.foo", "com.foo"), "com.foo");
+    }
+}
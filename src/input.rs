@@ -0,0 +1,99 @@
+use nix::fcntl::OFlag;
+use nix::sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout};
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::boottime_secs;
+
+const INPUT_DIR: &str = "/dev/input";
+// 收到事件后休眠一段时间再继续监听，避免触摸时每个事件都唤醒线程
+const INPUT_POLL_GRANULARITY: Duration = Duration::from_secs(5);
+
+/// 输入空闲检测：后台线程 epoll 监听 /dev/input/event*，记录最近一次输入时间
+pub struct InputIdleMonitor {
+    last_input: Arc<AtomicU64>, // CLOCK_BOOTTIME 秒数
+    devices: usize,
+}
+
+impl InputIdleMonitor {
+    /// 打开所有输入设备并启动监听线程；一个设备都打不开时返回 None
+    pub fn spawn() -> Option<Self> {
+        let epoll = Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC).ok()?;
+        let mut devices: Vec<File> = Vec::new();
+
+        for entry in fs::read_dir(INPUT_DIR).ok()?.flatten() {
+            let name = entry.file_name();
+            if !name.as_encoded_bytes().starts_with(b"event") {
+                continue;
+            }
+            let file = match OpenOptions::new()
+                .read(true)
+                .custom_flags(OFlag::O_NONBLOCK.bits() | OFlag::O_CLOEXEC.bits())
+                .open(entry.path())
+            {
+                Ok(f) => f,
+                Err(_) => continue,
+            };
+            let event = EpollEvent::new(EpollFlags::EPOLLIN, devices.len() as u64);
+            if epoll.add(&file, event).is_ok() {
+                devices.push(file);
+            }
+        }
+
+        if devices.is_empty() {
+            return None;
+        }
+
+        let last_input = Arc::new(AtomicU64::new(boottime_secs()));
+        let shared = Arc::clone(&last_input);
+        let count = devices.len();
+
+        thread::Builder::new()
+            .name("input-idle".to_string())
+            .spawn(move || watch_devices(epoll, devices, shared))
+            .ok()?;
+
+        Some(Self {
+            last_input,
+            devices: count,
+        })
+    }
+
+    pub fn device_count(&self) -> usize {
+        self.devices
+    }
+
+    /// 距离最近一次输入事件的秒数
+    pub fn idle_secs(&self) -> u64 {
+        boottime_secs().saturating_sub(self.last_input.load(Ordering::Relaxed))
+    }
+}
+
+fn watch_devices(epoll: Epoll, mut devices: Vec<File>, last_input: Arc<AtomicU64>) {
+    let mut events = [EpollEvent::empty(); 8];
+    let mut buf = [0u8; 1024];
+
+    loop {
+        let n = match epoll.wait(&mut events, EpollTimeout::NONE) {
+            Ok(n) => n,
+            Err(nix::errno::Errno::EINTR) => continue,
+            Err(_) => return,
+        };
+
+        // 排空所有就绪设备的缓冲，内容本身不关心
+        for ev in &events[..n] {
+            if let Some(dev) = devices.get_mut(ev.data() as usize) {
+                while matches!(dev.read(&mut buf), Ok(len) if len > 0) {}
+            }
+        }
+
+        last_input.store(boottime_secs(), Ordering::Relaxed);
+        thread::sleep(INPUT_POLL_GRANULARITY);
+    }
+}
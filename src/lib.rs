@@ -0,0 +1,77 @@
+//! 进程压制引擎的各组成模块；守护进程二进制与嵌入方共用
+//!
+//! 嵌入方可通过 [`events::subscribe`] 订阅清理事件，无需解析日志；C/C++ 调用方见 [`ffi`]
+
+pub mod abi;
+pub mod adaptive;
+pub mod backup;
+pub mod battery;
+pub mod bindings;
+pub mod broadcast;
+pub mod cli;
+pub mod config;
+pub mod control;
+pub mod critical;
+pub mod daemonize;
+pub mod dedup;
+pub mod device;
+pub mod display;
+pub mod doctor;
+pub mod engine;
+pub mod errlog;
+pub mod events;
+pub mod ffi;
+pub mod freezer;
+pub mod history;
+pub mod hook;
+pub mod http;
+pub mod identity;
+pub mod input;
+pub mod logcat;
+pub mod logfile;
+pub mod media;
+pub mod meminfo;
+pub mod memory;
+pub mod metrics;
+pub mod namespace;
+pub mod pattern;
+pub mod platform;
+pub mod policy;
+pub mod power;
+pub mod privilege;
+pub mod proc;
+pub mod procevents;
+pub mod procstats;
+pub mod protocol;
+pub mod psi;
+pub mod quirks;
+pub mod reload;
+pub mod schedule;
+pub mod schema;
+pub mod score;
+pub mod script;
+pub mod selfstat;
+pub mod session;
+pub mod stats;
+pub mod table;
+pub mod trigger;
+pub mod update;
+pub mod watchdog;
+pub mod whitelist;
+pub mod widgets;
+
+use nix::time::{clock_gettime, ClockId};
+
+/// CLOCK_BOOTTIME 秒数（包含休眠时间）
+pub fn boottime_secs() -> u64 {
+    clock_gettime(ClockId::CLOCK_BOOTTIME)
+        .map(|ts| ts.tv_sec() as u64)
+        .unwrap_or(0)
+}
+
+/// CLOCK_MONOTONIC 秒数（不含休眠时间）
+pub fn monotonic_secs() -> u64 {
+    clock_gettime(ClockId::CLOCK_MONOTONIC)
+        .map(|ts| ts.tv_sec() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,88 @@
+//! logcat 输出：多数模块用户习惯用 `logcat -s mem-cleaner` 看日志，比去 /data 下找文件方便
+//!
+//! Android 上直接调用 liblog 的 `__android_log_write`；其他平台退回执行 `log -t mem-cleaner`，
+//! 找不到 `log` 命令时不再尝试。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::logfile::LogLevel;
+
+pub const TAG: &str = "mem-cleaner";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// logcat 优先级，数值与 android/log.h 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Debug = 3,
+    Info = 4,
+    Warn = 5,
+    Error = 6,
+}
+
+impl From<LogLevel> for Priority {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Debug => Self::Debug,
+            LogLevel::Info => Self::Info,
+            LogLevel::Warn => Self::Warn,
+        }
+    }
+}
+
+/// 错误日志是否同时输出到 logcat（清理日志由 Logger 自己决定）
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[cfg(target_os = "android")]
+pub fn write(priority: Priority, message: &str) {
+    use std::ffi::CString;
+
+    #[link(name = "log")]
+    extern "C" {
+        fn __android_log_write(
+            prio: libc::c_int,
+            tag: *const libc::c_char,
+            text: *const libc::c_char,
+        ) -> libc::c_int;
+    }
+
+    let (Ok(tag), Ok(text)) = (CString::new(TAG), CString::new(message.replace('\0', ""))) else {
+        return;
+    };
+    unsafe {
+        __android_log_write(priority as libc::c_int, tag.as_ptr(), text.as_ptr());
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn write(priority: Priority, message: &str) {
+    use std::process::{Command, Stdio};
+
+    static UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+    if UNAVAILABLE.load(Ordering::Relaxed) {
+        return;
+    }
+    let flag = match priority {
+        Priority::Debug => "d",
+        Priority::Info => "i",
+        Priority::Warn => "w",
+        Priority::Error => "e",
+    };
+    let status = Command::new("log")
+        .args(["-t", TAG, "-p", flag, message])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    if status.is_err() {
+        // 不走 errlog：errlog 本身也会输出到这里
+        UNAVAILABLE.store(true, Ordering::Relaxed);
+        eprintln!("log command not available, logcat output disabled");
+    }
+}
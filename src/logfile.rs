@@ -0,0 +1,1148 @@
+//! 日志文件写入：内存告急时 /data 往往也快满了，写满（ENOSPC）后暂停写文件，
+//! 期间的清理记录只留在内存环形缓冲（`recent` 命令）里，每隔一段时间重试一次
+//!
+//! `log_format: json` 时每行一个 JSON 对象：击杀记录带结构化字段，其余记录为
+//! `{"timestamp":…,"level":…,"message":…}`，便于用 jq 等工具筛选。
+//!
+//! [`Logger`] 负责清理日志的各类记录、按天或按大小轮转与 logcat 镜像。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use nix::errno::Errno;
+use time::macros::format_description;
+use time::{format_description::FormatItem, Date, OffsetDateTime};
+
+use crate::boottime_secs;
+use crate::config::{describe_config, AppConfig, CleanAction, SurvivorAction, WhitelistRule};
+use crate::dedup::KillDedup;
+use crate::engine::{format_duration, Candidate, CycleSummary, KillRecord};
+use crate::history::{RelaunchTracker, RespawnTracker};
+use crate::power::BatteryRule;
+use crate::protocol::push_json_str;
+use crate::schedule::ScheduleWindow;
+use crate::selfstat::SelfUsage;
+use crate::session::SessionTotals;
+use crate::stats::Stats;
+use crate::table::{ProcessNode, ProcessTable};
+use crate::watchdog::{CycleFault, Recovery};
+use crate::{dedup, history, logcat, update};
+
+pub const FULL_RETRY_SECS: u64 = 300;
+pub(crate) const DEFAULT_LOG_BACKUPS: u32 = 3;
+const STATS_REPORT_TOP: usize = 5; // 统计汇总里列出的包数
+
+/// 日志级别：低于配置级别的记录不写
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug, // 跳过、压力切换、自身资源占用等周期性记录
+    Info,  // 清理、冻结、启动与配置变更
+    Warn,  // 看门狗、杀不掉的进程、重生风暴等需要留意的情况
+}
+
+impl LogLevel {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "debug" => Some(Self::Debug),
+            "info" => Some(Self::Info),
+            "warn" => Some(Self::Warn),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json, // JSON Lines
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// 清理日志写到哪里
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogBackend {
+    File,
+    Logcat,
+    Both,
+}
+
+impl LogBackend {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "file" => Some(Self::File),
+            "logcat" => Some(Self::Logcat),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Logcat => "logcat",
+            Self::Both => "both",
+        }
+    }
+}
+
+/// 日志分区状态，Logger 的各个副本（看门狗线程、panic hook）共享
+#[derive(Debug, Default)]
+pub struct LogHealth {
+    full_since: AtomicU64, // 写满的时刻（boottime 秒），0 表示正常
+    dropped: AtomicU64,    // 写满期间没能写入的记录数
+}
+
+impl LogHealth {
+    pub fn is_full(&self) -> bool {
+        self.full_since.load(Ordering::Relaxed) != 0
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 写满且未到重试时间时返回 false，并把这条记录计为丢弃
+    pub fn should_try(&self) -> bool {
+        let since = self.full_since.load(Ordering::Relaxed);
+        if since != 0 && boottime_secs().saturating_sub(since) < FULL_RETRY_SECS {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    /// 检查写入结果，遇到 ENOSPC 记为写满；首次写满返回 true
+    pub fn check<T>(&self, result: &io::Result<T>) -> bool {
+        let Err(e) = result else {
+            return false;
+        };
+        if e.raw_os_error() != Some(Errno::ENOSPC as i32) {
+            return false;
+        }
+        let was_full = self
+            .full_since
+            .swap(boottime_secs().max(1), Ordering::Relaxed)
+            != 0;
+        if !was_full {
+            self.dropped.store(1, Ordering::Relaxed);
+        }
+        !was_full
+    }
+
+    /// 重试成功，返回写满期间丢弃的记录数
+    pub fn recovered(&self) -> Option<u64> {
+        (self.full_since.swap(0, Ordering::Relaxed) != 0)
+            .then(|| self.dropped.swap(0, Ordering::Relaxed))
+    }
+}
+
+/// 逐行处理：JSON 模式下每行包成一个对象，同时输出到 logcat 时每行一条
+struct LineMode {
+    level: LogLevel,
+    json: bool,
+    logcat: bool,
+    line: Vec<u8>,
+}
+
+/// 带写满检测的日志写入器，drop 时刷新缓冲；只输出到 logcat 时没有文件
+pub struct LogWriter {
+    inner: Option<BufWriter<File>>,
+    health: Arc<LogHealth>,
+    lines: Option<LineMode>,
+}
+
+impl LogWriter {
+    pub fn new(file: Option<File>, health: Arc<LogHealth>) -> Self {
+        Self {
+            inner: file.map(BufWriter::new),
+            health,
+            lines: None,
+        }
+    }
+
+    /// 之后写入的文本按行处理，标为 level 级别
+    pub fn line_mode(mut self, level: LogLevel, json: bool, logcat: bool) -> Self {
+        if json || logcat || self.inner.is_none() {
+            self.lines = Some(LineMode {
+                level,
+                json,
+                logcat,
+                line: Vec::new(),
+            });
+        }
+        self
+    }
+
+    /// 原样写入一个已编码好的 JSON 对象（不含换行）
+    pub fn write_json(&mut self, object: &str) {
+        if self.lines.as_ref().is_some_and(|m| m.logcat) {
+            logcat::write(LogLevel::Info.into(), object);
+        }
+        self.write_file_line(object);
+    }
+
+    fn write_file_line(&mut self, line: &str) {
+        let Some(inner) = &mut self.inner else {
+            return;
+        };
+        let result = inner
+            .write_all(line.as_bytes())
+            .and_then(|()| inner.write_all(b"\n"));
+        self.record(&result);
+    }
+
+    fn emit_line(&mut self, line: &[u8]) {
+        let Some(mode) = &self.lines else {
+            return;
+        };
+        let (level, json, to_logcat) = (mode.level, mode.json, mode.logcat);
+        let text = String::from_utf8_lossy(line);
+        let text = text.trim_end();
+        if to_logcat && !text.is_empty() {
+            logcat::write(level.into(), text);
+        }
+        if !json {
+            self.write_file_line(text);
+            return;
+        }
+        if text.is_empty() {
+            return;
+        }
+        let mut out = String::from("{\"timestamp\":");
+        push_json_str(&mut out, &now_fmt());
+        out.push_str(",\"level\":");
+        push_json_str(&mut out, level.as_str());
+        out.push_str(",\"message\":");
+        push_json_str(&mut out, text);
+        out.push('}');
+        self.write_file_line(&out);
+    }
+
+    /// 刷新并落盘
+    pub fn sync_data(&mut self) {
+        if self.flush().is_ok() {
+            if let Some(inner) = &self.inner {
+                let _ = inner.get_ref().sync_data();
+            }
+        }
+    }
+
+    fn record<T>(&self, result: &io::Result<T>) -> bool {
+        if self.health.check(result) {
+            crate::errlog::error(
+                "log",
+                "Log filesystem is full, pausing log writes; recent events stay in memory",
+            );
+        }
+        result.is_err()
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(mode) = &mut self.lines else {
+            let Some(inner) = &mut self.inner else {
+                return Ok(buf.len());
+            };
+            let result = inner.write(buf);
+            self.record(&result);
+            return result;
+        };
+        let mut pending = std::mem::take(&mut mode.line);
+        pending.extend_from_slice(buf);
+        let mut start = 0;
+        while let Some(end) = pending[start..].iter().position(|&b| b == b'\n') {
+            self.emit_line(&pending[start..start + end]);
+            start += end + 1;
+        }
+        pending.drain(..start);
+        if let Some(mode) = &mut self.lines {
+            mode.line = pending;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(mode) = &mut self.lines {
+            if !mode.line.is_empty() {
+                let line = std::mem::take(&mut mode.line);
+                self.emit_line(&line);
+            }
+        }
+        let Some(inner) = &mut self.inner else {
+            return Ok(());
+        };
+        let result = inner.flush();
+        self.record(&result);
+        result
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// 智能日志管理器
+#[derive(Clone)]
+pub struct Logger {
+    pub path: std::path::PathBuf,
+    pub template: Option<String>, // 含 %Y/%m/%d 的路径模板，每天一个文件
+    pub last_write_date: Option<Date>,
+    pub kills: KillDedup,       // 当天重复清理的进程只记前几次，其余合并为汇总
+    pub fsync: bool,            // 清理记录写完后立即落盘
+    pub health: Arc<LogHealth>, // 分区写满时暂停写文件
+    pub level: LogLevel,
+    pub format: LogFormat,
+    pub max_bytes: u64, // 0 表示不按大小轮转
+    pub backups: u32,
+    pub file: bool,   // 写日志文件；只输出到 logcat 时为 false
+    pub logcat: bool, // 每行同时输出到 logcat
+}
+
+impl Logger {
+    /// 没有可写的输出（只写文件却没有路径）时返回 None
+    pub fn new(path: Option<String>, backend: LogBackend, fsync: bool) -> Option<Self> {
+        let file = backend != LogBackend::Logcat && path.is_some();
+        let logcat = backend != LogBackend::File;
+        (file || logcat).then(|| {
+            let p = path.filter(|_| file).unwrap_or_default();
+            let template = p.contains('%').then(|| p.clone());
+            Self {
+                path: std::path::PathBuf::from(p),
+                template,
+                last_write_date: None,
+                kills: KillDedup::default(),
+                fsync,
+                health: Arc::default(),
+                level: LogLevel::Info,
+                format: LogFormat::Text,
+                max_bytes: 0,
+                backups: DEFAULT_LOG_BACKUPS,
+                file,
+                logcat,
+            }
+        })
+    }
+
+    /// 启动与热加载时按配置设置级别、格式与轮转
+    pub fn apply_config(&mut self, config: &AppConfig) {
+        self.fsync = config.fsync_logs;
+        self.level = config.log_level;
+        self.format = config.log_format;
+        self.max_bytes = config.log_max_kb * 1024;
+        self.backups = config.log_backups;
+    }
+
+    pub fn open_writer(&mut self) -> Option<LogWriter> {
+        self.open(LogLevel::Info)
+    }
+
+    /// 按级别打开日志；低于配置级别时返回 None
+    pub fn open(&mut self, level: LogLevel) -> Option<LogWriter> {
+        if level < self.level {
+            return None;
+        }
+        if !self.file {
+            return Some(LogWriter::new(None, self.health.clone()).line_mode(
+                level,
+                false,
+                self.logcat,
+            ));
+        }
+        if !self.health.should_try() {
+            return None;
+        }
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let today = now.date();
+
+        let mut should_truncate = false;
+
+        if let Some(template) = &self.template {
+            // 模板路径：按日期换文件，始终追加
+            if self.last_write_date != Some(today) {
+                self.path = std::path::PathBuf::from(expand_date_template(template, today));
+                self.last_write_date = Some(today);
+            }
+        } else if self.max_bytes == 0 && self.last_write_date != Some(today) {
+            if let Ok(meta) = fs::metadata(&self.path) {
+                if let Ok(mtime) = meta.modified() {
+                    let mtime_dt = OffsetDateTime::from(mtime);
+                    let file_date = mtime_dt.date();
+                    if file_date != today {
+                        should_truncate = true;
+                    }
+                } else {
+                    should_truncate = false;
+                }
+            } else {
+                should_truncate = true;
+            }
+            self.last_write_date = Some(today);
+        }
+
+        if self.max_bytes > 0 && fs::metadata(&self.path).is_ok_and(|m| m.len() >= self.max_bytes) {
+            should_truncate = !self.rotate();
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(!should_truncate)
+            .truncate(should_truncate)
+            .open(&self.path);
+        self.health.check(&file);
+        let mut writer = LogWriter::new(Some(file.ok()?), self.health.clone()).line_mode(
+            level,
+            self.format == LogFormat::Json,
+            self.logcat,
+        );
+
+        // 写满后的重试：先确认能写进去，再补一行说明中间缺了多少记录
+        if self.health.is_full() {
+            let _ = writeln!(
+                writer,
+                "=== 日志恢复: {} ===\n日志分区曾写满，期间 {} 条记录未写入，可用 recent 命令查看最近事件\n",
+                now_fmt(),
+                self.health.dropped()
+            );
+            if writer.flush().is_err() {
+                return None;
+            }
+            self.health.recovered();
+        }
+        Some(writer)
+    }
+
+    /// 按大小轮转：<path>.N-1 → <path>.N … <path> → <path>.1；不保留旧文件或改名失败时返回 false，由调用方清空
+    fn rotate(&self) -> bool {
+        if self.backups == 0 {
+            return false;
+        }
+        let numbered = |n: u32| {
+            let mut p = self.path.clone().into_os_string();
+            p.push(format!(".{}", n));
+            std::path::PathBuf::from(p)
+        };
+        for n in (1..self.backups).rev() {
+            let _ = fs::rename(numbered(n), numbered(n + 1));
+        }
+        fs::rename(&self.path, numbered(1)).is_ok()
+    }
+
+    /// 在守护进程运行期间安全清空日志（logs clear）
+    pub fn clear(&mut self) -> bool {
+        if !self.file {
+            return false;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path);
+        match file {
+            Ok(f) => {
+                let mut writer = BufWriter::new(f);
+                let _ = writeln!(writer, "=== 日志已清空: {} ===", now_fmt());
+                let _ = writeln!(writer);
+                let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+                self.last_write_date = Some(now.date());
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn write_startup(&mut self, config: &AppConfig) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 启动时间: {} ===", now_fmt());
+            let _ = writeln!(writer, "版本: {}", update::version_string());
+            let _ = writeln!(writer, "⚡ 进程压制已启动 ⚡");
+            // 解析后的完整配置（含默认值），便于复现问题
+            let _ = writeln!(writer, "生效配置:");
+            for line in describe_config(config) {
+                let _ = writeln!(writer, "  {}", line);
+            }
+            // 命令行参数可能覆盖了配置中的 log_path，以实际写入的路径为准
+            let _ = writeln!(
+                writer,
+                "  log_path: {}",
+                match &self.template {
+                    _ if !self.file => "logcat".to_string(),
+                    Some(template) => template.clone(),
+                    None => self.path.display().to_string(),
+                }
+            );
+            // 系统 UID 进程默认不碰，显式列出的目标要让用户看得见
+            for rule in &config.system_targets {
+                let _ = writeln!(writer, "⚠ 系统进程目标已启用: {}", rule);
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    /// 退出前的本次运行统计（正常退出、SIGTERM、panic 都会写）
+    pub fn write_session_summary(&mut self, reason: &str, session: SessionTotals) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 停止时间: {} ({}) ===", now_fmt(), reason);
+            let _ = writeln!(
+                writer,
+                "本次运行: 清理 {} 个进程，释放 {}MB，运行 {}",
+                session.kills,
+                session.freed_kb / 1024,
+                format_duration(session.uptime_secs)
+            );
+            for (name, count) in self.kills.collapsed() {
+                let _ = writeln!(writer, "今日重复清理: {} x{}", name, count);
+            }
+            let _ = writeln!(writer);
+            self.sync(writer);
+        }
+    }
+
+    pub fn write_cleanup(&mut self, summary: &CycleSummary, killed_list: &[KillRecord]) {
+        if killed_list.is_empty() {
+            return;
+        }
+        // JSON 供程序处理，每条击杀都记，不做当日去重
+        if self.format == LogFormat::Json {
+            if let Some(mut writer) = self.open_writer() {
+                for record in killed_list {
+                    writer.write_json(&kill_json(record, summary.trigger));
+                }
+                self.sync(writer);
+            }
+            return;
+        }
+        let today = OffsetDateTime::now_local()
+            .unwrap_or_else(|_| OffsetDateTime::now_utc())
+            .date();
+        let collapsed = self.kills.roll(today);
+        let counts: Vec<u32> = killed_list
+            .iter()
+            .map(|r| self.kills.record(&r.name))
+            .collect();
+        let shown = counts
+            .iter()
+            .filter(|&&n| n <= dedup::KILL_LOG_DEDUP_AFTER)
+            .count();
+        if collapsed.is_none() && shown == 0 {
+            return;
+        }
+
+        if let Some(mut writer) = self.open_writer() {
+            if let Some((date, collapsed)) = collapsed {
+                let _ = writeln!(writer, "=== {} 重复清理汇总 ===", date);
+                for (name, count) in collapsed {
+                    let _ = writeln!(writer, "{} x{}", name, count);
+                }
+                let _ = writeln!(writer);
+            }
+            if shown == 0 {
+                return;
+            }
+            let mb = |kb: Option<u64>| {
+                kb.map_or_else(|| "?".to_string(), |kb| format!("{}MB", kb / 1024))
+            };
+            let _ = writeln!(
+                writer,
+                "=== 清理时间: {} ({}) ===",
+                now_fmt(),
+                summary.trigger
+            );
+            let _ = write!(
+                writer,
+                "可用内存 {} → {}，候选 {} 个，清理 {} 个",
+                mb(summary.avail_before_kb),
+                mb(summary.avail_after_kb),
+                summary.considered,
+                killed_list.len()
+            );
+            if summary.deferred > 0 {
+                let _ = write!(
+                    writer,
+                    "（已达每轮上限，{} 个留到下一轮）",
+                    summary.deferred
+                );
+            }
+            let _ = writeln!(writer, "，耗时 {}ms", summary.elapsed_ms);
+            for (record, &count) in killed_list.iter().zip(&counts) {
+                if count > dedup::KILL_LOG_DEDUP_AFTER {
+                    continue;
+                }
+                let _ = write!(
+                    writer,
+                    "已清理: {} ({}, 后台 {}",
+                    record.name,
+                    record.mem,
+                    format_duration(record.background_secs)
+                );
+                if record.drain_mah > 0.0 {
+                    let _ = write!(writer, ", 耗电 {:.1}mAh", record.drain_mah);
+                }
+                if record.is_32bit {
+                    let _ = write!(writer, ", 32 位");
+                }
+                if record.graceful {
+                    let _ = write!(writer, ", SIGTERM");
+                }
+                let _ = writeln!(writer, ")");
+                if !record.group.is_empty() {
+                    let members: Vec<String> = record
+                        .group
+                        .iter()
+                        .map(|(pid, name)| format!("{} ({})", name, pid))
+                        .collect();
+                    let _ = writeln!(writer, "  同 UID 一并清理: {}", members.join(", "));
+                }
+                if let Some(argv) = record.argv.as_ref().filter(|a| **a != record.name) {
+                    let _ = writeln!(writer, "  命令行: {}", argv);
+                }
+                if let Some(meminfo) = &record.meminfo {
+                    let _ = writeln!(writer, "  meminfo: {}", meminfo);
+                }
+                if count == dedup::KILL_LOG_DEDUP_AFTER {
+                    let _ = writeln!(
+                        writer,
+                        "  今日已清理 {} 次，之后的同名记录合并为每日汇总",
+                        count
+                    );
+                }
+            }
+            let _ = writeln!(writer);
+            self.sync(writer);
+        }
+    }
+
+    /// 内存耗尽后常伴随突然重启，清理记录恰恰是最容易丢的那部分
+    fn sync(&self, mut writer: LogWriter) {
+        if self.fsync {
+            writer.sync_data();
+        }
+    }
+
+    pub fn write_demoted(&mut self, demoted: &[String], adj: i32) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 降级时间: {} ===", now_fmt());
+            for name in demoted {
+                let _ = writeln!(writer, "已降级: {} (oom_score_adj -> {})", name, adj);
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    /// SIGUSR1：转储进程表
+    pub fn write_table(&mut self, table: &ProcessTable, now: u64) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 进程表: {} ({} 个) ===", now_fmt(), table.len());
+            let mut nodes: Vec<(i32, &ProcessNode)> = table.iter().collect();
+            nodes.sort_unstable_by_key(|&(pid, _)| pid);
+            for (pid, node) in nodes {
+                let term = match table.cycles_since_term(pid) {
+                    Some(cycles) => format!("，SIGTERM 后 {} 轮", cycles),
+                    None => String::new(),
+                };
+                let _ = writeln!(
+                    writer,
+                    "{} uid {} {} [{}] oom {} RSS {}MB 后台 {}{}",
+                    pid,
+                    node.uid,
+                    node.name,
+                    node.state as char,
+                    node.oom,
+                    node.rss_kb / 1024,
+                    format_duration(now.saturating_sub(node.background_since)),
+                    term
+                );
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_dry_run(&mut self, candidates: &[Candidate], config: &AppConfig) {
+        if candidates.is_empty() {
+            return;
+        }
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 演练: {} ===", now_fmt());
+            for c in candidates {
+                let _ = writeln!(writer, "{}", dry_run_line(c, config));
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_stats_report(&mut self, stats: &Stats, period_secs: u64) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(
+                writer,
+                "=== 清理统计: {} (过去 {}) ===",
+                now_fmt(),
+                format_duration(period_secs)
+            );
+            let _ = writeln!(
+                writer,
+                "清理周期 {} 轮，平均耗时 {}ms；清理 {} 个进程，估算回收 {}MB",
+                stats.cycles,
+                stats.avg_scan_ms(),
+                stats.kills,
+                stats.freed_kb / 1024
+            );
+            for (package, kills, kb) in stats.top(STATS_REPORT_TOP) {
+                let _ = writeln!(writer, "  {} x{} ({}MB)", package, kills, kb / 1024);
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_self_usage(&mut self, usage: &SelfUsage) {
+        if let Some(mut writer) = self.open(LogLevel::Debug) {
+            let _ = writeln!(writer, "=== 自身开销: {} ===", now_fmt());
+            let _ = writeln!(
+                writer,
+                "过去 {}: CPU {}ms，唤醒 {} 次；当前内存 {}MB，fd {} 个",
+                format_duration(usage.period_secs),
+                usage.cpu_ms,
+                usage.wakeups,
+                usage.rss_kb / 1024,
+                usage.fds
+            );
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_watchdog(&mut self, stalled_secs: u64, reexec: bool) {
+        if let Some(mut writer) = self.open(LogLevel::Warn) {
+            let _ = writeln!(writer, "=== ⚠ 看门狗: {} ===", now_fmt());
+            let _ = writeln!(
+                writer,
+                "主循环已 {} 无响应（可能卡在 /proc 读取上）{}",
+                format_duration(stalled_secs),
+                if reexec {
+                    "，重新启动守护进程"
+                } else {
+                    ""
+                }
+            );
+            let _ = writeln!(writer);
+            self.sync(writer);
+        }
+    }
+
+    /// 看门狗重新执行后的第一条记录
+    pub fn write_restart(&mut self, stalled_secs: u64) {
+        if let Some(mut writer) = self.open(LogLevel::Warn) {
+            let _ = writeln!(writer, "=== ⚠ 自动恢复: {} ===", now_fmt());
+            let _ = writeln!(
+                writer,
+                "守护进程已由看门狗重新启动（此前主循环 {} 无响应）",
+                format_duration(stalled_secs)
+            );
+            let _ = writeln!(writer);
+            self.sync(writer);
+        }
+    }
+
+    /// 某轮清理 panic 或耗时异常，以及随之采取的恢复动作
+    pub fn write_recovery(&mut self, recovery: &Recovery) {
+        if let Some(mut writer) = self.open(LogLevel::Warn) {
+            let _ = writeln!(writer, "=== ⚠ 自动恢复: {} ===", now_fmt());
+            let _ = match &recovery.fault {
+                CycleFault::Panic {
+                    message,
+                    consecutive,
+                } => writeln!(
+                    writer,
+                    "本轮清理 panic（连续第 {} 次）: {}",
+                    consecutive, message
+                ),
+                CycleFault::Slow { millis } => {
+                    writeln!(writer, "本轮清理耗时 {}ms，超过上限", millis)
+                }
+            };
+            let _ = writeln!(
+                writer,
+                "{}",
+                if recovery.reset_table {
+                    "已丢弃进程表，下一轮重新采样"
+                } else {
+                    "已跳过本轮，下一轮照常进行"
+                }
+            );
+            let _ = writeln!(writer);
+            self.sync(writer);
+        }
+    }
+
+    pub fn write_sleep(&mut self, slept_secs: u64, skipped: u64, catch_up: bool) {
+        if let Some(mut writer) = self.open(LogLevel::Debug) {
+            let _ = writeln!(
+                writer,
+                "=== 唤醒: {} ===\n设备休眠了 {}，期间跳过 {} 轮清理{}",
+                now_fmt(),
+                format_duration(slept_secs),
+                skipped,
+                if catch_up {
+                    "，立即补做一轮"
+                } else {
+                    ""
+                }
+            );
+            let _ = writeln!(writer);
+        }
+    }
+
+    /// 配置热加载：列出改动的项，需重启才生效的单独注明
+    pub fn write_reload(&mut self, applied: &[String], restart: &[String]) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 配置已重新加载: {} ===", now_fmt());
+            for line in applied {
+                let _ = writeln!(writer, "已生效: {}", line);
+            }
+            for line in restart {
+                let _ = writeln!(writer, "需重启: {}", line);
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_platform(&mut self, capabilities: &str) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "系统能力: {}", capabilities);
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_hidepid(&mut self, mode: &str) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(
+                writer,
+                "⚠ /proc 以 hidepid={} 挂载：其他用户的进程可能不可见或无法读取，清理范围受限",
+                mode
+            );
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_yield(&mut self, killers: &[(i32, String)]) {
+        if let Some(mut writer) = self.open(LogLevel::Warn) {
+            let _ = writeln!(writer, "=== 暂停清理: {} ===", now_fmt());
+            for (pid, name) in killers {
+                let _ = writeln!(
+                    writer,
+                    "检测到厂商清理服务 {} (pid {})，让出清理",
+                    name, pid
+                );
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_skip(&mut self, avail_kb: u64, last_freed_kb: u64) {
+        if let Some(mut writer) = self.open(LogLevel::Debug) {
+            let _ = writeln!(writer, "=== 跳过清理: {} ===", now_fmt());
+            let _ = writeln!(
+                writer,
+                "可用内存 {}MB 充足 (上轮释放 {}MB)，跳过直到内存回落",
+                avail_kb / 1024,
+                last_freed_kb / 1024
+            );
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_schedule(&mut self, window: Option<&ScheduleWindow>) {
+        if let Some(mut writer) = self.open_writer() {
+            match window {
+                Some(w) => {
+                    let _ = writeln!(writer, "=== 进入时段 {}: {} ===", w, now_fmt());
+                }
+                None => {
+                    let _ = writeln!(
+                        writer,
+                        "=== 离开 schedule 时段，恢复常规策略: {} ===",
+                        now_fmt()
+                    );
+                }
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_power(&mut self, charger_pause: bool, rule: Option<&BatteryRule>) {
+        if let Some(mut writer) = self.open_writer() {
+            let state = match (charger_pause, rule) {
+                (true, _) => "正在充电，暂停清理".to_string(),
+                (false, Some(r)) => format!("电量低于 {}%，改用 {}", r.below, r),
+                (false, None) => "电源状态恢复常规策略".to_string(),
+            };
+            let _ = writeln!(writer, "=== {}: {} ===", state, now_fmt());
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_pressure_change(&mut self, active: bool, avail_kb: u64) {
+        if let Some(mut writer) = self.open(LogLevel::Debug) {
+            let state = if active {
+                "内存紧张，开始清理"
+            } else {
+                "内存恢复，停止清理"
+            };
+            let _ = writeln!(writer, "=== {}: {} ===", state, now_fmt());
+            let _ = writeln!(writer, "可用内存: {}MB", avail_kb / 1024);
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_respawns(
+        &mut self,
+        respawned: &[(String, u64)],
+        tracker: &RespawnTracker,
+        now: u64,
+    ) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 重生检测: {} ===", now_fmt());
+            for (name, latency) in respawned {
+                match tracker.backoff_remaining(name, now) {
+                    Some(secs) => {
+                        let _ = writeln!(
+                            writer,
+                            "重生: {} ({} 秒后重启，暂停清理 {} 分钟)",
+                            name,
+                            latency,
+                            secs / 60
+                        );
+                    }
+                    None => {
+                        let _ = writeln!(writer, "重生: {} ({} 秒后重启)", name, latency);
+                    }
+                }
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_frozen(&mut self, frozen: &[String]) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 冻结时间: {} ===", now_fmt());
+            for entry in frozen {
+                let _ = writeln!(writer, "已冻结: {}", entry);
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_storms(&mut self, packages: &[String]) {
+        if let Some(mut writer) = self.open(LogLevel::Warn) {
+            for package in packages {
+                let _ = writeln!(
+                    writer,
+                    "⚠ {} 一小时内被杀次数过多，疑似被反复拉起，暂时放过它",
+                    package
+                );
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_thawed(&mut self, names: &[String]) {
+        if let Some(mut writer) = self.open_writer() {
+            for name in names {
+                let _ = writeln!(writer, "已解冻: {}（回到前台）", name);
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_escalated(&mut self, names: &[String], cycles: u64) {
+        if let Some(mut writer) = self.open(LogLevel::Warn) {
+            for name in names {
+                let _ = writeln!(
+                    writer,
+                    "⚠ {} 收到 SIGTERM {} 轮后仍未退出，已 SIGKILL",
+                    name, cycles
+                );
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_survivor(&mut self, name: &str, survived: u32, action: SurvivorAction) {
+        if let Some(mut writer) = self.open(LogLevel::Warn) {
+            let what = match action {
+                SurvivorAction::ThawKill => "先解冻再杀",
+                SurvivorAction::ForceStop => "改用 am force-stop",
+                SurvivorAction::Ignore => "放弃处理，直到它自行退出（可能被冻结或持有特权）",
+            };
+            let _ = writeln!(
+                writer,
+                "⚠ {} 已被杀 {} 次仍未退出，{}",
+                name, survived, what
+            );
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_whitelist_report(&mut self, unused: &[&WhitelistRule], days: u64) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 白名单命中报告: {} ===", now_fmt());
+            if unused.is_empty() {
+                let _ = writeln!(writer, "过去 {} 天所有白名单条目都有命中", days);
+            } else {
+                let _ = writeln!(
+                    writer,
+                    "以下 {} 个条目过去 {} 天从未命中任何进程，可能拼写有误或应用已卸载:",
+                    unused.len(),
+                    days
+                );
+                for rule in unused {
+                    let _ = writeln!(writer, "  {}", rule);
+                }
+            }
+            let _ = writeln!(writer);
+        }
+    }
+
+    pub fn write_relaunches(
+        &mut self,
+        relaunched: &[(String, u32)],
+        tracker: &RelaunchTracker,
+        now: u64,
+        suggest_after: u32,
+    ) {
+        if let Some(mut writer) = self.open_writer() {
+            let _ = writeln!(writer, "=== 用户重新打开: {} ===", now_fmt());
+            for (package, count) in relaunched {
+                let secs = tracker.cooldown_remaining(package, now).unwrap_or(0);
+                let _ = writeln!(
+                    writer,
+                    "重新打开: {} (第 {} 次，暂停清理 {} 分钟)",
+                    package,
+                    count,
+                    secs / 60
+                );
+                if suggest_after > 0 && *count >= suggest_after {
+                    let _ = writeln!(writer, "  建议将 {}:* 加入白名单", package);
+                }
+            }
+            let _ = writeln!(writer);
+        }
+    }
+}
+
+/// 展开日志路径模板中的 %Y %m %d（%% 为字面量 %）
+fn expand_date_template(template: &str, date: Date) -> String {
+    let mut out = String::with_capacity(template.len() + 8);
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", date.year())),
+            Some('m') => out.push_str(&format!("{:02}", date.month() as u8)),
+            Some('d') => out.push_str(&format!("{:02}", date.day())),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+static TIME_FMT: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+
+/// 演练模式下的一条记录：would kill pid/package/oom/rss
+pub fn dry_run_line(c: &Candidate, config: &AppConfig) -> String {
+    let action = match c.action {
+        CleanAction::Freeze if !c.forced => "freeze",
+        CleanAction::Demote if !c.forced => "demote",
+        _ if config.term_grace_cycles > 0 => "terminate",
+        _ => "kill",
+    };
+    format!(
+        "would {} pid {} {} (oom {}, RSS {}MB)",
+        action,
+        c.pid,
+        c.name,
+        c.oom,
+        c.mem.rss_kb / 1024
+    )
+}
+
+/// JSON 日志中的一条击杀记录
+fn kill_json(record: &KillRecord, trigger: &str) -> String {
+    let mut out = String::from("{\"timestamp\":");
+    push_json_str(&mut out, &now_fmt());
+    out.push_str(",\"level\":\"info\",\"event\":\"kill\",\"trigger\":");
+    push_json_str(&mut out, trigger);
+    out.push_str(&format!(
+        ",\"pid\":{},\"uid\":{},\"package\":",
+        record.pid, record.uid
+    ));
+    push_json_str(&mut out, history::package_name(&record.name));
+    out.push_str(",\"process\":");
+    push_json_str(&mut out, &record.name);
+    out.push_str(&format!(
+        ",\"oom_score\":{},\"rss_kb\":{},\"swap_kb\":{},\"background_secs\":{},\"action\":\"{}\"",
+        record.oom,
+        record.mem.rss_kb,
+        record.mem.swap_kb,
+        record.background_secs,
+        if record.graceful {
+            "sigterm"
+        } else {
+            "sigkill"
+        }
+    ));
+    if !record.group.is_empty() {
+        out.push_str(",\"group\":[");
+        for (i, (_, name)) in record.group.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            push_json_str(&mut out, name);
+        }
+        out.push(']');
+    }
+    out.push('}');
+    out
+}
+
+pub fn now_fmt() -> String {
+    let dt = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+    dt.format(TIME_FMT)
+        .unwrap_or_else(|_| "time_err".to_string())
+}
@@ -1,458 +1,1668 @@
-use fxhash::FxHashSet;
 use itoa::Buffer as ItoaBuffer;
-use nix::fcntl::{open, openat, OFlag};
-use nix::sys::signal::{kill, Signal};
-use nix::sys::stat::{fstatat, Mode};
+use nix::fcntl::{open, OFlag};
+use nix::sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout};
+use nix::sys::signal::{SigSet, Signal};
+use nix::sys::signalfd::{SfdFlags, SignalFd};
+use nix::sys::stat::Mode;
 use nix::sys::time::TimeSpec;
 use nix::sys::timerfd::{ClockId, Expiration, TimerFd, TimerFlags, TimerSetTimeFlags};
-use nix::unistd::Pid;
 
 use std::env;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufWriter, Read, Write};
-use std::os::unix::io::{FromRawFd, RawFd};
+use std::fs;
+use std::io::Write;
 use std::path::Path;
-use std::process::Command;
 use std::time::{Duration, Instant};
 
-use time::macros::format_description;
-use time::{format_description::FormatItem, Date, OffsetDateTime};
+use mem_cleaner::adaptive::AdaptiveInterval;
+use mem_cleaner::boottime_secs;
+use mem_cleaner::cli::{Cli, Subcommand};
+use mem_cleaner::config::{
+    config_value, describe_config, load_config, parse_config_text, AppConfig, ConfigFormat,
+    FrozenAction, IdleMode, THRESHOLD_RANGE,
+};
+use mem_cleaner::control::ControlServer;
+use mem_cleaner::daemonize::{self, LockError, PidFile};
+use mem_cleaner::device::DeviceState;
+use mem_cleaner::display::ScreenState;
+use mem_cleaner::engine::{
+    continue_cleanup, drop_kill_queue, format_duration, perform_cleanup, perform_cleanup_blocking,
+    select_candidates, CleanerState, CycleTrigger, ScanReport, ScannerResources, PAUSE_PROPERTY,
+};
+use mem_cleaner::http::HttpServer;
+use mem_cleaner::input::InputIdleMonitor;
+use mem_cleaner::logfile::{LogBackend, Logger};
+use mem_cleaner::platform::Platform;
+use mem_cleaner::proc::ProcFs;
+use mem_cleaner::procevents::ProcEvents;
+use mem_cleaner::protocol::{self, CommandError, CommandResult, ErrorCode};
+use mem_cleaner::psi::{self, PsiTrigger, PSI_COOLDOWN_SECS};
+use mem_cleaner::reload::{self, ConfigWatch};
+use mem_cleaner::selfstat::{SelfReporter, SleepAudit};
+use mem_cleaner::session::SESSION;
+use mem_cleaner::trigger::TriggerFile;
+use mem_cleaner::watchdog::CycleGuard;
+use mem_cleaner::{
+    abi, backup, broadcast, control, dedup, doctor, errlog, events, hook, http, logcat, memory,
+    metrics, privilege, schedule, schema, update, watchdog, whitelist,
+};
 
-// --- 常量配置 ---
-const OOM_SCORE_THRESHOLD: i32 = 800; // 只有大于此值的进程才会被检查
-const DEFAULT_INTERVAL: u64 = 60;
-const DOZE_CACHE_TTL_SECS: u64 = 30; // Doze 状态缓存时间，避免频繁 fork
+// epoll 事件标识
+const EVENT_TIMER: u64 = 0;
+const EVENT_CONTROL: u64 = 1;
+const EVENT_HTTP: u64 = 2;
+const EVENT_SIGNAL: u64 = 3;
+const EVENT_TRIGGER: u64 = 4;
+const EVENT_CONFIG: u64 = 5;
+const EVENT_PSI: u64 = 6;
+const EVENT_PROC: u64 = 7;
+const EVENT_KILL_QUEUE: u64 = 8;
+const RECENT_DUMP_PATH: &str = "/data/local/tmp/mem_cleaner.recent";
+const STATS_TOP: usize = 10; // stats 命令列出的包数
+const SLEEP_LOG_MIN_SECS: u64 = 600; // 休眠超过这么久才记日志，短暂熄屏不值得记
 
-// --- 结构体定义 ---
-/// 白名单匹配规则：支持完全匹配或前缀匹配
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum WhitelistRule {
-    Exact(String),  // 完全匹配
-    Prefix(String), // 前缀匹配（对应 xxx:* 格式）
-}
-
-struct AppConfig {
-    interval: u64,
-    whitelist: FxHashSet<WhitelistRule>, // 规则集合
-}
-
-/// 扫描资源复用池
-struct ScannerResources {
-    path_buf: String,    // 复用路径字符串 "/proc/12345/..."
-    file_buf: Vec<u8>,   // 复用文件读取 buffer
-    cmdline_buf: String, // 复用 cmdline 解析 buffer
-}
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
 
-impl ScannerResources {
-    fn new() -> Self {
-        Self {
-            path_buf: String::with_capacity(64),
-            file_buf: Vec::with_capacity(128),
-            cmdline_buf: String::with_capacity(128),
+    // 客户端模式：mem_cleaner [--socket <path>] [--token <secret>] logs clear | history clear | stats | whitelist | set ...
+    let mut socket_path = control::DEFAULT_SOCKET_PATH.to_string();
+    let mut token = None;
+    while args.len() > 2 && matches!(args[1].as_str(), "--socket" | "--token") {
+        let value = args.remove(2);
+        if args.remove(1) == "--socket" {
+            socket_path = value;
+        } else {
+            token = Some(value);
+        }
+    }
+    if args.len() > 1
+        && matches!(
+            args[1].as_str(),
+            "logs"
+                | "history"
+                | "stats"
+                | "recent"
+                | "status"
+                | "candidates"
+                | "whitelist"
+                | "blacklist-once"
+                | "clean"
+                | "reload"
+                | "set"
+                | "query"
+                | "state"
+                | "metrics"
+                | "pause"
+                | "resume"
+        )
+    {
+        let command = args[1..].join(" ");
+        match control::send_command(&socket_path, token.as_deref(), &command) {
+            Ok(reply) => {
+                print!("{}", reply);
+                std::process::exit(if reply.starts_with("OK") { 0 } else { 1 });
+            }
+            Err(e) => {
+                eprintln!(
+                    "Failed to reach daemon at {}: {} (is control_socket enabled in the config?)",
+                    socket_path, e
+                );
+                std::process::exit(1);
+            }
         }
     }
-}
 
-/// 智能日志管理器
-struct Logger {
-    path: std::path::PathBuf,
-    last_write_date: Option<Date>,
-}
+    if args.len() > 1 && matches!(args[1].as_str(), "--version" | "-V") {
+        println!("{}", update::version_string());
+        return;
+    }
 
-impl Logger {
-    fn new(path: Option<String>) -> Option<Self> {
-        path.map(|p| Self {
-            path: std::path::PathBuf::from(p),
-            last_write_date: None,
-        })
+    // 检查新版本：mem_cleaner check-update <config_path>，需配置 check_update: on
+    if args.len() > 1 && args[1] == "check-update" {
+        std::process::exit(check_update(args.get(2).map(String::as_str)));
     }
 
-    fn open_writer(&mut self) -> Option<BufWriter<File>> {
-        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
-        let today = now.date();
+    // 环境自检：mem_cleaner doctor [config_path]
+    if args.len() > 1 && args[1] == "doctor" {
+        let ok = doctor::run(args.get(2).map(String::as_str));
+        std::process::exit(if ok { 0 } else { 1 });
+    }
 
-        let mut should_truncate = false;
+    // 模拟模式：mem_cleaner simulate <config_path>，只打印不清理
+    if args.len() > 2 && args[1] == "simulate" {
+        simulate(&load_config(&args[2]));
+        return;
+    }
 
-        if self.last_write_date != Some(today) {
-            if let Ok(meta) = fs::metadata(&self.path) {
-                if let Ok(mtime) = meta.modified() {
-                    let mtime_dt = OffsetDateTime::from(mtime);
-                    let file_date = mtime_dt.date();
-                    if file_date != today {
-                        should_truncate = true;
-                    }
-                } else {
-                    should_truncate = false;
-                }
-            } else {
-                should_truncate = true;
+    let cli = match Cli::parse(&args[1..]) {
+        Ok(cli) => cli,
+        Err(e) => {
+            if args.len() > 1 {
+                eprintln!("{}", e);
             }
-            self.last_write_date = Some(today);
+            usage(&args[0]);
         }
+    };
+    let config_path = &cli.config;
+    match cli.command {
+        Subcommand::CheckConfig => std::process::exit(check_config(&cli)),
+        Subcommand::Once => {
+            run_once(&cli);
+            return;
+        }
+        Subcommand::Run => {}
+    }
 
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .append(!should_truncate)
-            .truncate(should_truncate)
-            .open(&self.path)
-            .ok()?;
+    println!("Starting Daemon...");
+    let mut config = load_config(config_path);
+    cli.apply(&mut config);
+    errlog::init(config.error_log.clone(), config.error_log_max_kb);
+    require_dry_run_log(&cli, &config);
 
-        Some(BufWriter::new(file))
+    // 两个实例会争抢同一批进程和同一个日志；在绑定 socket、创建线程之前确认只有自己
+    let mut pid_file = match config.pid_file.as_deref().map(PidFile::acquire) {
+        Some(Ok(file)) => Some(file),
+        Some(Err(LockError::Running(pid))) => {
+            eprintln!(
+                "Another instance is already running (pid {}), see {}",
+                pid.map_or_else(|| "unknown".to_string(), |p| p.to_string()),
+                config.pid_file.as_deref().unwrap_or_default()
+            );
+            std::process::exit(1);
+        }
+        Some(Err(LockError::Io(e))) => {
+            errlog::warn(
+                "pidfile",
+                &format!(
+                    "Failed to lock pid file {}: {}, single-instance check disabled",
+                    config.pid_file.as_deref().unwrap_or_default(),
+                    e
+                ),
+            );
+            None
+        }
+        None => None,
+    };
+    if cli.daemon {
+        println!("Detaching into the background");
+        if let Err(e) = daemonize::daemonize() {
+            eprintln!("Failed to daemonize: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Some(file) = &mut pid_file {
+        if let Err(e) = file.write_pid() {
+            errlog::warn("pidfile", &format!("Failed to write pid file: {}", e));
+        }
+    }
+    println!("Interval: {}s", config.interval);
+    if config.dry_run {
+        println!("Dry run: candidates are logged, nothing is killed");
+    }
+    println!("Whitelist: {} rules", config.whitelist.len());
+    for rule in &config.system_targets {
+        println!("WARNING: monitoring system-UID target {}", rule);
     }
 
-    fn write_startup(&mut self) {
-        if let Some(mut writer) = self.open_writer() {
-            let _ = writeln!(writer, "=== 启动时间: {} ===", now_fmt());
-            let _ = writeln!(writer, "⚡ 进程压制已启动 ⚡");
-            let _ = writeln!(writer);
+    logcat::set_enabled(config.log_backend != LogBackend::File);
+    let mut logger = Logger::new(
+        cli.log.clone().or_else(|| config.log_path.clone()),
+        config.log_backend,
+        config.fsync_logs,
+    );
+    if let Some(l) = &mut logger {
+        l.apply_config(&config);
+        l.write_startup(&config);
+    }
+    SESSION.start(boottime_secs());
+    if let Some(stalled) = watchdog::take_reexec() {
+        errlog::warn("watchdog", "Restarted by watchdog");
+        if let Some(l) = &mut logger {
+            l.write_restart(stalled);
         }
     }
 
-    fn write_cleanup(&mut self, killed_list: &[String]) {
-        if killed_list.is_empty() {
-            return;
+    // panic 时也把本次统计落盘；清理周期内的 panic 会被接住，由 write_recovery 记录
+    let panic_logger = logger.clone();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(mut l) = panic_logger.clone().filter(|_| !watchdog::guarded()) {
+            l.write_session_summary(&format!("panic: {}", info), SESSION.totals(boottime_secs()));
         }
-        if let Some(mut writer) = self.open_writer() {
-            let _ = writeln!(writer, "=== 清理时间: {} ===", now_fmt());
-            for pkg in killed_list {
-                let _ = writeln!(writer, "已清理: {}", pkg);
-            }
-            let _ = writeln!(writer);
+        errlog::error("panic", &info.to_string());
+        default_hook(info);
+    }));
+
+    events::set_recent_capacity(config.recent_events);
+
+    // 必须在创建任何线程之前屏蔽，否则信号可能被投递到其他线程走默认处理
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGTERM);
+    signals.add(Signal::SIGINT);
+    signals.add(Signal::SIGHUP); // 重新加载配置
+    signals.add(Signal::SIGUSR1); // 转储最近事件与进程表
+    signals.thread_block().expect("Failed to block signals");
+    let mut signal_fd =
+        SignalFd::with_flags(&signals, SfdFlags::SFD_CLOEXEC).expect("Failed to create signalfd");
+
+    // TimerFD 保留（作为兜底与周期性任务）
+    let timer = TimerFd::new(ClockId::CLOCK_BOOTTIME, TimerFlags::empty())
+        .expect("Failed to create timerfd");
+    arm_timer(&timer, config.interval).expect("Failed to set timer");
+    // kill_delay_ms 的单次定时器：到期后处理本轮剩下的候选
+    let kill_timer = TimerFd::new(ClockId::CLOCK_BOOTTIME, TimerFlags::empty())
+        .expect("Failed to create timerfd");
+
+    // 预打开 /proc 目录 fd
+    let procfs = open_proc();
+
+    let mut device = DeviceState::new();
+    let mut res = ScannerResources::new();
+    let platform = Platform::detect(&mut res.freezer);
+    let capabilities = platform.describe(&res.freezer);
+    println!("Platform: {}", capabilities);
+    if let Some(l) = &mut logger {
+        l.write_platform(&capabilities);
+    }
+    if let Some(mode) = proc_hidepid() {
+        let warning = format!(
+            "/proc mounted with hidepid={}, processes of other users may be invisible or unreadable",
+            mode
+        );
+        errlog::warn("proc", &format!("WARNING: {}", warning));
+        if let Some(l) = &mut logger {
+            l.write_hidepid(&mode);
         }
     }
-}
+    if config.frozen_action != FrozenAction::Kill && !res.freezer.available() {
+        println!("No cgroup freezer found, frozen process check disabled");
+    }
 
-// --- Doze 缓存 (减少 fork 开销) ---
-struct DozeCache {
-    last_checked: Option<Instant>,
-    is_deep: bool,
-    ttl: Duration,
-}
+    let control = config.control_socket.as_deref().and_then(|path| {
+        match ControlServer::bind(path, config.access.clone()) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                errlog::error(
+                    "control",
+                    &format!("Failed to bind control socket {}: {}", path, e),
+                );
+                None
+            }
+        }
+    });
 
-impl DozeCache {
-    fn new(ttl: Duration) -> Self {
-        Self {
-            last_checked: None,
-            is_deep: false,
-            ttl,
+    let http = config.http_api.as_deref().and_then(|addr| {
+        match HttpServer::bind(addr, config.access.clone()) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                errlog::error(
+                    "http",
+                    &format!("Failed to start HTTP API on {}: {}", addr, e),
+                );
+                None
+            }
         }
-    }
+    });
+
+    let trigger = config
+        .trigger_file
+        .as_deref()
+        .and_then(|path| match TriggerFile::watch(path) {
+            Ok(trigger) => Some(trigger),
+            Err(e) => {
+                errlog::error(
+                    "trigger",
+                    &format!("Failed to watch trigger file {}: {}", path, e),
+                );
+                None
+            }
+        });
+
+    let config_watch = match ConfigWatch::watch(config_path) {
+        Ok(watch) => Some(watch),
+        Err(e) => {
+            errlog::warn(
+                "reload",
+                &format!("Failed to watch config {}: {}", config_path, e),
+            );
+            None
+        }
+    };
 
-    fn is_deep_doze_cached(&mut self) -> bool {
-        let now = Instant::now();
-        if let Some(t) = self.last_checked {
-            if now.duration_since(t) < self.ttl {
-                return self.is_deep;
+    let psi_trigger =
+        config
+            .psi_threshold
+            .and_then(|threshold| match PsiTrigger::register(threshold) {
+                Ok(trigger) => {
+                    println!("PSI trigger: some stall >= {}% per 2s window", threshold);
+                    Some(trigger)
+                }
+                Err(e) => {
+                    errlog::warn(
+                        "psi",
+                        &format!(
+                            "Failed to register PSI trigger ({}), checking avg10 every interval",
+                            e
+                        ),
+                    );
+                    None
+                }
+            });
+
+    let proc_events = if config.proc_events {
+        match ProcEvents::open() {
+            Ok(events) => {
+                println!("Process events: netlink proc connector");
+                Some(events)
+            }
+            Err(e) => {
+                errlog::warn(
+                    "procevents",
+                    &format!("Proc connector unavailable ({}), relying on /proc scans", e),
+                );
+                None
             }
         }
+    } else {
+        None
+    };
+
+    // 需要创建文件的初始化（socket、日志）都已完成，此后收缩权限；必须早于创建任何线程
+    let kept_caps = config
+        .drop_privileges
+        .then(|| drop_privileges(&config))
+        .flatten();
+
+    if config.broadcast {
+        match broadcast::spawn() {
+            Ok(()) => println!("Broadcasting kill events via am"),
+            Err(e) => errlog::error(
+                "broadcast",
+                &format!("Failed to start broadcast thread: {}", e),
+            ),
+        }
+    }
 
-        let state = is_device_in_deep_doze();
-        self.last_checked = Some(now);
-        self.is_deep = state;
-        state
+    if let Some(path) = &config.hook {
+        let timeout = Duration::from_millis(config.hook_timeout_ms);
+        match hook::spawn(path.clone(), timeout) {
+            Ok(()) => println!("Kill hook: {}", path),
+            Err(e) => errlog::error("hook", &format!("Failed to start hook thread: {}", e)),
+        }
     }
-}
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <config_path> [log_path]", args[0]);
-        std::process::exit(1);
+    if config.watchdog_intervals > 0 {
+        if let Err(e) = watchdog::spawn(
+            config.interval,
+            config.watchdog_intervals,
+            config.watchdog_reexec,
+            logger.clone(),
+        ) {
+            errlog::error("watchdog", &format!("Failed to start watchdog: {}", e));
+        }
     }
 
-    let config_path = &args[1];
-    let log_path = if args.len() > 2 {
-        Some(args[2].clone())
+    let mut idle_mode = config.idle_mode;
+    let input_monitor = if idle_mode != IdleMode::Doze {
+        let monitor = InputIdleMonitor::spawn();
+        match &monitor {
+            Some(m) => println!("Input idle detection: {} devices", m.device_count()),
+            None => {
+                eprintln!("No readable input devices, falling back to doze detection");
+                idle_mode = IdleMode::Doze;
+            }
+        }
+        monitor
     } else {
         None
     };
 
-    println!("Starting Daemon...");
-    let config = load_config(config_path);
-    println!("Interval: {}s", config.interval);
-
-    let mut logger = Logger::new(log_path);
-    if let Some(l) = &mut logger {
-        l.write_startup();
+    match config.quirks.profile {
+        Some(profile) => println!("Quirks profile: {}", profile),
+        None => println!("Quirks profile: none"),
     }
 
-    // TimerFD 保留（作为兜底与周期性任务）
-    let timer = TimerFd::new(ClockId::CLOCK_BOOTTIME, TimerFlags::empty())
-        .expect("Failed to create timerfd");
-    let interval_spec = TimeSpec::new(config.interval as i64, 0);
-    timer
-        .set(
-            Expiration::Interval(interval_spec),
-            TimerSetTimeFlags::empty(),
+    let screen = ScreenState::detect();
+    println!("Screen state: {}", screen.backend_name());
+
+    let mut daemon = Daemon {
+        state: CleanerState::new(&config),
+        config,
+        config_text: fs::read_to_string(config_path).unwrap_or_default(),
+        kept_caps,
+        config_path: config_path.clone(),
+        cli: cli.clone(),
+        timer,
+        kill_timer,
+        logger,
+        res,
+        screen,
+        platform,
+        procfs,
+        adaptive: AdaptiveInterval::new(),
+        guard: CycleGuard::new(),
+    };
+    if let Some(path) = &daemon.config.table_state {
+        let (table, history) = daemon.state.restore_state(path, &daemon.procfs);
+        println!(
+            "Restored {} process table entries and {} package histories from {}",
+            table, history, path
+        );
+    }
+    if proc_events.is_some() {
+        daemon.state.table.track_events();
+    }
+    // epoll 同时等待定时器、控制 socket、HTTP 接口、触发文件、配置文件、PSI、进程事件与退出信号
+    let epoll = Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC).expect("Failed to create epoll");
+    epoll
+        .add(
+            &daemon.timer,
+            EpollEvent::new(EpollFlags::EPOLLIN, EVENT_TIMER),
         )
-        .expect("Failed to set timer");
+        .expect("Failed to watch timerfd");
+    epoll
+        .add(
+            &daemon.kill_timer,
+            EpollEvent::new(EpollFlags::EPOLLIN, EVENT_KILL_QUEUE),
+        )
+        .expect("Failed to watch timerfd");
+    if let Some(server) = &control {
+        let _ = epoll.add(server, EpollEvent::new(EpollFlags::EPOLLIN, EVENT_CONTROL));
+    }
+    if let Some(server) = &http {
+        let _ = epoll.add(server, EpollEvent::new(EpollFlags::EPOLLIN, EVENT_HTTP));
+    }
+    if let Some(trigger) = &trigger {
+        let _ = epoll.add(trigger, EpollEvent::new(EpollFlags::EPOLLIN, EVENT_TRIGGER));
+    }
+    if let Some(watch) = &config_watch {
+        let _ = epoll.add(watch, EpollEvent::new(EpollFlags::EPOLLIN, EVENT_CONFIG));
+    }
+    if let Some(trigger) = &psi_trigger {
+        let _ = epoll.add(trigger, EpollEvent::new(EpollFlags::EPOLLPRI, EVENT_PSI));
+    }
+    if let Some(events) = &proc_events {
+        let _ = epoll.add(events, EpollEvent::new(EpollFlags::EPOLLIN, EVENT_PROC));
+    }
+    epoll
+        .add(
+            &signal_fd,
+            EpollEvent::new(EpollFlags::EPOLLIN, EVENT_SIGNAL),
+        )
+        .expect("Failed to watch signalfd");
+    let mut events = [EpollEvent::empty(); 8];
 
-    // 预打开 /proc 目录 fd
-    let proc_fd = match open(
+    let mut self_reporter = SelfReporter::new(boottime_secs());
+    let mut sleep_audit = SleepAudit::new();
+    let mut last_psi_cleanup = None;
+    let mut proc_event_buf = Vec::new();
+    let exit_reason = 'main: loop {
+        let n = match epoll.wait(&mut events, EpollTimeout::NONE) {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+        self_reporter.on_wakeup();
+        if let Some(usage) = self_reporter.due(boottime_secs()) {
+            if let Some(l) = &mut daemon.logger {
+                l.write_self_usage(&usage);
+            }
+        }
+        if let Some((stats, period_secs)) = daemon.state.stats.due(boottime_secs()) {
+            if let Some(l) = &mut daemon.logger {
+                l.write_stats_report(&stats, period_secs);
+            }
+        }
+
+        for ev in &events[..n] {
+            match ev.data() {
+                EVENT_CONTROL => {
+                    if let Some((stream, request)) = control.as_ref().and_then(|c| c.accept()) {
+                        let (result, command) = match request.command {
+                            Ok(command) => (daemon.handle_command(&command), command),
+                            Err(rejected) => (Err(rejected), String::new()),
+                        };
+                        control::respond(
+                            stream,
+                            &protocol::format_reply(request.version, &result, &command),
+                        );
+                    }
+                }
+                EVENT_SIGNAL => {
+                    if let Ok(Some(info)) = signal_fd.read_signal() {
+                        if info.ssi_signo == Signal::SIGUSR1 as u32 {
+                            dump_recent();
+                            if let Some(l) = &mut daemon.logger {
+                                l.write_table(&daemon.state.table, boottime_secs());
+                            }
+                            continue;
+                        }
+                        if info.ssi_signo == Signal::SIGHUP as u32 {
+                            let (applied, restart) = daemon.reload_config(true);
+                            println!(
+                                "Received SIGHUP, reloaded config: {} applied, {} need restart",
+                                applied.len(),
+                                restart.len()
+                            );
+                            continue;
+                        }
+                        let name = Signal::try_from(info.ssi_signo as i32)
+                            .map_or("signal", |s| s.as_str());
+                        break 'main name;
+                    }
+                }
+                EVENT_HTTP => {
+                    if let Some((stream, request)) = http.as_ref().and_then(|h| h.accept()) {
+                        match request.to_command() {
+                            Ok(command) => {
+                                let result = daemon.handle_command(&command);
+                                // 抓取端要的是原样的指标文本，不加 OK 前缀
+                                if let (Ok(body), "metrics") = (&result, command.as_str()) {
+                                    http::respond_with(stream, 200, metrics::CONTENT_TYPE, body);
+                                    continue;
+                                }
+                                let status = match &result {
+                                    Ok(_) => 200,
+                                    Err(e) if e.code == ErrorCode::Failed => 500,
+                                    Err(_) => 400,
+                                };
+                                let reply = protocol::format_reply(None, &result, &command);
+                                http::respond(stream, status, &reply);
+                            }
+                            Err(status) => http::respond(stream, status, "ERR"),
+                        }
+                    }
+                }
+                EVENT_TRIGGER => {
+                    // 外部显式请求，不做空闲判定
+                    if trigger.as_ref().is_some_and(|t| t.take()) {
+                        daemon.cleanup(CycleTrigger::Manual);
+                    }
+                }
+                EVENT_PROC => {
+                    if let Some(events) = &proc_events {
+                        if !events.read(&mut proc_event_buf) {
+                            daemon.state.table.events_lost();
+                        }
+                        for event in proc_event_buf.drain(..) {
+                            daemon.state.apply_proc_event(event);
+                        }
+                    }
+                }
+                EVENT_PSI => {
+                    // 压力持续时每个窗口都会通知，限频；内存告急时不做空闲判定
+                    let now = boottime_secs();
+                    if last_psi_cleanup.is_none_or(|t| now.saturating_sub(t) >= PSI_COOLDOWN_SECS) {
+                        last_psi_cleanup = Some(now);
+                        daemon.cleanup(CycleTrigger::Psi);
+                    }
+                }
+                EVENT_KILL_QUEUE => {
+                    let _ = daemon.kill_timer.wait();
+                    daemon.continue_cleanup();
+                }
+                EVENT_CONFIG => {
+                    if config_watch.as_ref().is_some_and(|w| w.changed()) {
+                        daemon.reload_config(false);
+                    }
+                }
+                _ => {
+                    let _ = daemon.timer.wait();
+
+                    // 休眠期间到期的周期都被跳过了，醒来后的几分钟往往正是内存最紧张的时候
+                    let slept = sleep_audit.tick();
+                    let interval = daemon.config.interval;
+                    let catch_up = daemon.config.catch_up_after_resume && slept >= interval;
+                    if slept >= SLEEP_LOG_MIN_SECS.max(interval) {
+                        if let Some(l) = &mut daemon.logger {
+                            l.write_sleep(slept, slept / interval, catch_up);
+                        }
+                    }
+
+                    let input_idle = input_monitor
+                        .as_ref()
+                        .is_some_and(|m| m.idle_secs() >= daemon.config.input_idle_secs);
+                    let idle = match idle_mode {
+                        IdleMode::Doze => device.is_deep_idle(daemon.config.doze_cache_secs),
+                        IdleMode::Input => input_idle,
+                        IdleMode::Any => {
+                            input_idle || device.is_deep_idle(daemon.config.doze_cache_secs)
+                        }
+                    };
+                    // 启用 PSI 时定时周期只在压力达标时清理；读不到 PSI 则照常按定时清理
+                    let pressured = daemon.config.psi_threshold.is_none_or(|threshold| {
+                        psi::some_avg10().is_none_or(|avg10| avg10 >= threshold)
+                    });
+                    // 只在熄屏时清理：亮屏说明正在使用；读不到屏幕状态时照常清理
+                    let in_use =
+                        daemon.config.only_when_screen_off && daemon.update_screen() == Some(true);
+                    if catch_up {
+                        daemon.cleanup(CycleTrigger::Resume);
+                    } else if !idle && pressured && !in_use {
+                        daemon.cleanup(CycleTrigger::Timer);
+                    }
+                    daemon.adapt_interval();
+                    watchdog::beat();
+                }
+            }
+        }
+    };
+
+    println!("Received {}, exiting", exit_reason);
+    daemon.state.frozen.thaw_all(&daemon.res.freezer);
+    if let Some(l) = &mut daemon.logger {
+        l.write_session_summary(exit_reason, SESSION.totals(boottime_secs()));
+    }
+    if let Some(path) = &daemon.config.whitelist_stats {
+        daemon
+            .state
+            .whitelist_hits
+            .save(path, whitelist::unix_secs(), true);
+    }
+    if let Some(path) = &daemon.config.table_state {
+        daemon.state.save_state(path, boottime_secs(), true);
+    }
+}
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {} [run|once|check-config] --config <path> [--log <path>] [--interval <secs>] [--threshold <adj>] [--dry-run] [--verbose] [--daemon]",
+        program
+    );
+    eprintln!("       {} <config_path> [log_path]", program);
+    eprintln!(
+        "       {} [--socket <path>] [--token <secret>] logs clear | history clear | stats [package] | recent | status | candidates | whitelist | clean | reload | metrics",
+        program
+    );
+    eprintln!(
+        "       {} [--socket <path>] set <interval|threshold> <value> [persist]",
+        program
+    );
+    eprintln!(
+        "       {} [--socket <path>] query <stats|config|table> [schema=N]",
+        program
+    );
+    eprintln!(
+        "       {} [--socket <path>] state <export|import> [path]",
+        program
+    );
+    eprintln!(
+        "       {} [--socket <path>] pause [seconds] | resume",
+        program
+    );
+    eprintln!(
+        "       {} [--socket <path>] blacklist-once <package>",
+        program
+    );
+    eprintln!("       {} simulate <config_path>", program);
+    eprintln!("       {} doctor [config_path]", program);
+    eprintln!("       {} --version | check-update <config_path>", program);
+    std::process::exit(1);
+}
+
+fn open_proc() -> ProcFs {
+    match open(
         Path::new("/proc"),
         OFlag::O_DIRECTORY | OFlag::O_RDONLY,
         Mode::empty(),
     ) {
-        Ok(fd) => fd,
+        Ok(fd) => ProcFs::new(fd),
         Err(e) => {
             eprintln!("Failed to open /proc: {}", e);
             std::process::exit(1);
         }
-    };
-
-    let mut doze_cache = DozeCache::new(Duration::from_secs(DOZE_CACHE_TTL_SECS));
-    let mut resources = ScannerResources::new();
+    }
+}
 
-    loop {
-        let _ = timer.wait();
+/// check-config：打印生效配置（含命令行覆盖与默认值）；读不到文件或有警告时返回 1
+fn check_config(cli: &Cli) -> i32 {
+    if let Err(e) = fs::read_to_string(&cli.config) {
+        eprintln!("Failed to read {}: {}", cli.config, e);
+        return 1;
+    }
+    let mut config = load_config(&cli.config);
+    cli.apply(&mut config);
+    for line in describe_config(&config) {
+        println!("{}", line);
+    }
+    let problems = doctor::config_problems(&config);
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    if errlog::reported() > 0 || !problems.is_empty() {
+        1
+    } else {
+        0
+    }
+}
 
-        if doze_cache.is_deep_doze_cached() {
-            continue;
-        }
+/// once：清理一轮后退出；带 --dry-run 时只列出候选
+fn run_once(cli: &Cli) {
+    let mut config = load_config(&cli.config);
+    cli.apply(&mut config);
+    if cli.dry_run {
+        simulate(&config);
+        return;
+    }
+    errlog::init(config.error_log.clone(), config.error_log_max_kb);
+    require_dry_run_log(cli, &config);
+    logcat::set_enabled(config.log_backend != LogBackend::File);
+    let mut logger = Logger::new(
+        cli.log.clone().or_else(|| config.log_path.clone()),
+        config.log_backend,
+        config.fsync_logs,
+    );
+    if let Some(l) = &mut logger {
+        l.apply_config(&config);
+    }
+    let procfs = open_proc();
+    let mut res = ScannerResources::new();
+    Platform::detect(&mut res.freezer);
+    let mut state = CleanerState::new(&config);
+    let killed = perform_cleanup_blocking(
+        &config,
+        &mut logger,
+        &mut res,
+        &mut state,
+        &procfs,
+        CycleTrigger::Manual,
+    );
+    println!("Killed {} processes", killed);
+}
 
-        perform_cleanup(&config.whitelist, &mut logger, &mut resources, proc_fd);
+/// 演练的结果只写进日志：没有日志时启动即报错，免得演练了却什么也看不到
+fn require_dry_run_log(cli: &Cli, config: &AppConfig) {
+    let has_log =
+        config.log_backend != LogBackend::File || cli.log.is_some() || config.log_path.is_some();
+    if config.dry_run && !has_log {
+        eprintln!("dry_run needs a log for its candidates: set log_path or pass --log");
+        std::process::exit(1);
     }
 }
 
-/// 检查进程是否在白名单中（支持完全匹配和前缀匹配）
-fn is_in_whitelist(cmdline: &str, whitelist: &FxHashSet<WhitelistRule>) -> bool {
-    if whitelist.contains(&WhitelistRule::Exact(cmdline.to_string())) {
-        return true;
+/// check-update 的退出码：0 已是最新，1 出错或未启用，2 有新版本
+fn check_update(config_path: Option<&str>) -> i32 {
+    let Some(config) = config_path.map(load_config) else {
+        eprintln!("Usage: mem_cleaner check-update <config_path>");
+        return 1;
+    };
+    if !config.check_update {
+        eprintln!("Update check is disabled, set check_update: on in the config to allow it");
+        return 1;
     }
-    for rule in whitelist {
-        if let WhitelistRule::Prefix(prefix) = rule {
-            if cmdline.starts_with(prefix) {
-                return true;
-            }
+    println!("Current: {}", update::version_string());
+    match update::check(&config.update_url) {
+        Ok((latest, true)) => {
+            println!("Newer version available: {}", latest);
+            2
+        }
+        Ok((latest, false)) => {
+            println!("Up to date (latest release {})", latest);
+            0
         }
+        Err(e) => {
+            eprintln!("Update check failed: {}", e);
+            1
+        }
+    }
+}
+
+/// SIGUSR1：把最近事件写到固定位置，不依赖 socket 客户端
+fn dump_recent() {
+    let mut content = String::new();
+    for line in events::recent() {
+        content.push_str(&line);
+        content.push('\n');
+    }
+    match fs::write(RECENT_DUMP_PATH, content) {
+        Ok(()) => println!("Recent events written to {}", RECENT_DUMP_PATH),
+        Err(e) => errlog::error(
+            "recent",
+            &format!("Failed to write {}: {}", RECENT_DUMP_PATH, e),
+        ),
     }
-    false
 }
 
-/// 核心清理逻辑：严格遵循“漏斗模型”进行极致性能过滤
-fn perform_cleanup(
-    whitelist: &FxHashSet<WhitelistRule>,
-    logger: &mut Option<Logger>,
-    res: &mut ScannerResources,
-    proc_fd: RawFd,
-) {
-    let proc_dir = match fs::read_dir("/proc") {
-        Ok(d) => d,
-        Err(_) => return,
+/// 只保留清理所需的 capability，可选再装上 seccomp 黑名单；返回实际保留的 capability
+fn drop_privileges(config: &AppConfig) -> Option<Vec<u32>> {
+    let keep = privilege::required_caps(config);
+    let kept = match privilege::drop_capabilities(&keep) {
+        Ok(()) => {
+            let names: Vec<&str> = keep.iter().map(|&c| privilege::cap_name(c)).collect();
+            println!("Dropped privileges, keeping {}", names.join(", "));
+            Some(keep)
+        }
+        Err(e) => {
+            errlog::error("privilege", &format!("Failed to drop capabilities: {}", e));
+            None
+        }
     };
+    if config.seccomp {
+        if let Err(e) = privilege::install_seccomp() {
+            errlog::error(
+                "privilege",
+                &format!("Failed to install seccomp filter: {}", e),
+            );
+        }
+    }
+    kept
+}
 
-    let mut killed_list: Vec<String> = Vec::new();
-    let mut itoa_buf = ItoaBuffer::new();
+fn arm_timer(timer: &TimerFd, interval: u64) -> nix::Result<()> {
+    timer.set(
+        Expiration::Interval(TimeSpec::new(interval as i64, 0)),
+        TimerSetTimeFlags::empty(),
+    )
+}
 
-    for entry in proc_dir {
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+/// 主循环持有的全部运行时对象，控制命令在其上执行
+struct Daemon {
+    config: AppConfig,
+    config_path: String,
+    config_text: String,         // 上次加载时的配置文件内容
+    kept_caps: Option<Vec<u32>>, // drop_privileges 后保留的 capability，未收缩时为 None
+    cli: Cli,                    // 命令行覆盖项，热加载后重新套用
+    timer: TimerFd,
+    kill_timer: TimerFd, // kill_delay_ms 的单次定时器
+    logger: Option<Logger>,
+    res: ScannerResources,
+    state: CleanerState,
+    screen: ScreenState,
+    platform: Platform,
+    procfs: ProcFs,
+    adaptive: AdaptiveInterval,
+    guard: CycleGuard,
+}
 
-        let file_name = entry.file_name();
-        let file_name_bytes = file_name.as_encoded_bytes();
+impl Daemon {
+    fn cleanup(&mut self, trigger: CycleTrigger) -> usize {
+        self.update_screen();
+        self.guarded(|config, logger, res, state, procfs| {
+            perform_cleanup(config, logger, res, state, procfs, trigger)
+        })
+    }
 
-        // 只处理纯数字的 PID 目录
-        if file_name_bytes.is_empty() || !file_name_bytes.iter().all(|b| b.is_ascii_digit()) {
-            continue;
+    /// 间隔击杀到时：处理上一轮排队的候选
+    fn continue_cleanup(&mut self) -> usize {
+        self.guarded(|config, logger, res, state, procfs| {
+            continue_cleanup(config, logger, res, state, procfs)
+        })
+    }
+
+    /// 在看门狗保护下执行一批清理，之后按需重设定时器、写指标
+    fn guarded(
+        &mut self,
+        run: impl FnOnce(
+            &AppConfig,
+            &mut Option<Logger>,
+            &mut ScannerResources,
+            &mut CleanerState,
+            &ProcFs,
+        ) -> usize,
+    ) -> usize {
+        let interval = self.state.interval(&self.config);
+        let started = Instant::now();
+        let result = watchdog::isolate(|| {
+            run(
+                &self.config,
+                &mut self.logger,
+                &mut self.res,
+                &mut self.state,
+                &self.procfs,
+            )
+        });
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let panic = result.as_ref().err().map(String::as_str);
+        if let Some(recovery) = self
+            .guard
+            .record(panic, elapsed_ms, self.config.max_cycle_ms)
+        {
+            errlog::error(
+                "watchdog",
+                &format!(
+                    "Cleanup cycle failed ({:?}){}",
+                    recovery.fault,
+                    if recovery.reset_table {
+                        ", resetting process table"
+                    } else {
+                        ""
+                    }
+                ),
+            );
+            if recovery.reset_table {
+                self.state.table.reset();
+            }
+            if let Some(l) = &mut self.logger {
+                l.write_recovery(&recovery);
+            }
+        }
+        let killed = result.unwrap_or(0);
+        // 进出 schedule 时段或低电量档可能换了周期
+        if self.state.interval(&self.config) != interval {
+            self.rearm_timer();
+        }
+        if self.state.kill_queue.is_some() {
+            let delay = Duration::from_millis(self.config.kill_delay_ms);
+            if let Err(e) = self.kill_timer.set(
+                Expiration::OneShot(TimeSpec::from_duration(delay)),
+                TimerSetTimeFlags::empty(),
+            ) {
+                errlog::error("timer", &format!("Failed to set kill delay timer: {}", e));
+                drop_kill_queue(&mut self.logger, &mut self.state);
+            }
         }
+        if let Some(path) = &self.config.metrics_path {
+            let text = metrics::render(&self.state, memory::read_mem_available_kb());
+            if !metrics::write(path, &text) {
+                dedup::warn("metrics", &format!("Failed to write metrics to {}", path));
+            }
+        }
+        killed
+    }
 
-        let pid_str = unsafe { std::str::from_utf8_unchecked(file_name_bytes) };
-        let pid: i32 = match pid_str.parse() {
-            Ok(p) => p,
-            Err(_) => continue,
-        };
+    /// 启用熄屏策略时检测屏幕状态，熄屏/亮屏切换时按新状态重设清理周期；读不到时返回 None
+    fn update_screen(&mut self) -> Option<bool> {
+        if !self.config.screen_aware() {
+            self.state.screen_off = false;
+            return None;
+        }
+        let on = self.screen.is_on();
+        let off = on == Some(false);
+        if off != self.state.screen_off {
+            self.state.screen_off = off;
+            self.rearm_timer();
+        }
+        on
+    }
 
-        // 使用 itoa 将 pid 转为字符串（零分配）
-        let pid_s = itoa_buf.format(pid);
-
-        // ==========================================
-        // 漏斗第 1 层：查 UID (最轻量，仅 1 次 fstatat syscall)
-        // 过滤掉 30%~40% 的底层系统进程 (UID < 10000)
-        // ==========================================
-        let pid_path = Path::new(pid_s);
-        match fstatat(Some(proc_fd), pid_path, nix::fcntl::AtFlags::empty()) {
-            Ok(stat) => {
-                if stat.st_uid < 10000 {
-                    continue; // 核心系统进程，直接跳过
-                }
+    fn rearm_timer(&mut self) {
+        self.adaptive.reset();
+        self.set_timer(self.state.interval(&self.config));
+    }
+
+    fn set_timer(&self, interval: u64) {
+        match arm_timer(&self.timer, interval) {
+            Ok(()) => watchdog::set_interval(interval),
+            Err(e) => errlog::error("timer", &format!("Failed to set timer: {}", e)),
+        }
+    }
+
+    /// 自适应周期：每次定时器到期后按本次采样决定下一次间隔
+    fn adapt_interval(&mut self) {
+        if !self.config.adaptive_interval {
+            return;
+        }
+        let before = watchdog::interval();
+        let next = self.adaptive.next(
+            &self.config,
+            self.state.interval(&self.config),
+            self.state.screen_off,
+            memory::read_mem_available_kb(),
+            self.state.table.pids(&self.procfs).len(),
+        );
+        if next != before {
+            self.set_timer(next);
+        }
+    }
+
+    /// 重新读取配置文件并换入；进程表、击杀历史等运行状态保留
+    /// 返回 (已生效的改动, 需重启才生效的改动)；force 为 false 时文件内容没变就不加载
+    fn reload_config(&mut self, force: bool) -> (Vec<String>, Vec<String>) {
+        // set ... persist、state import 写回配置文件也会触发，内容没变就不重复加载
+        let text = fs::read_to_string(&self.config_path).unwrap_or_default();
+        if !force && text == self.config_text {
+            return (Vec::new(), Vec::new());
+        }
+        self.config_text = text;
+        let mut new = load_config(&self.config_path);
+        self.cli.apply(&mut new);
+        errlog::init(new.error_log.clone(), new.error_log_max_kb);
+        let rearm = new.interval_for(self.state.screen_off)
+            != self.config.interval_for(self.state.screen_off);
+        if let Some(l) = &mut self.logger {
+            l.apply_config(&new);
+        }
+        if new.dry_run && self.logger.is_none() {
+            errlog::warn(
+                "config",
+                "dry_run enabled without a log: candidates will not be recorded",
+            );
+        }
+        events::set_recent_capacity(new.recent_events);
+        self.state.apply_config(&new);
+        let (applied, mut restart) = reload::diff(&self.config, &new);
+        // 收缩掉的 capability 取不回来，新配置用到的冻结、降级等处理会一直 EPERM，直到重启
+        if let Some(kept) = &self.kept_caps {
+            let missing = privilege::missing_caps(kept, &new);
+            if !missing.is_empty() {
+                let names: Vec<&str> = missing.iter().map(|&c| privilege::cap_name(c)).collect();
+                let line = format!("capabilities: {} (dropped at startup)", names.join(", "));
+                errlog::warn(
+                    "config",
+                    &format!("Reloaded config needs {}; restart to apply", line),
+                );
+                restart.push(line);
             }
-            Err(_) => continue,
         }
+        self.config = new;
+        if rearm {
+            self.rearm_timer();
+        }
+        if applied.is_empty() && restart.is_empty() {
+            return (applied, restart);
+        }
+        println!(
+            "Config reloaded: {} changes applied, {} need restart",
+            applied.len(),
+            restart.len()
+        );
+        if let Some(l) = &mut self.logger {
+            l.write_reload(&applied, &restart);
+        }
+        (applied, restart)
+    }
 
-        // ==========================================
-        // 漏斗第 2 层：查 oom_score_adj (较轻量，3 次 syscall + 简单解析)
-        // 过滤掉前台和活跃 App (score < 800)
-        // ==========================================
-        res.path_buf.clear();
-        res.path_buf.push_str(pid_s);
-        res.path_buf.push_str("/oom_score_adj");
-
-        let score = {
-            let p_oom = Path::new(&res.path_buf);
-            match openat(Some(proc_fd), p_oom, OFlag::O_RDONLY, Mode::empty()) {
-                Ok(fd) => {
-                    let mut f = unsafe { File::from_raw_fd(fd) };
-                    res.file_buf.clear();
-                    let _ = f.read_to_end(&mut res.file_buf);
-                    let s = std::str::from_utf8(&res.file_buf).ok().map(|s| s.trim());
-                    s.and_then(|s| s.parse::<i32>().ok())
+    /// 处理控制命令（socket / HTTP）；Ok 为回复正文，由调用方按请求格式加上 OK 前缀或编码为 JSON
+    fn handle_command(&mut self, command: &str) -> CommandResult {
+        if let Some(args) = command.strip_prefix("set ") {
+            return self.handle_set(args);
+        }
+        if let Some(args) = command.strip_prefix("state ") {
+            return self.handle_state(args);
+        }
+        if let Some(args) = command.strip_prefix("query ") {
+            let now = boottime_secs();
+            return schema::query(
+                args,
+                &self.config,
+                &mut self.state,
+                SESSION.totals(now),
+                now,
+            );
+        }
+        match command {
+            "hello" => Ok(protocol::hello()),
+            "status" => self.status(),
+            "candidates" => Ok(self.candidates()),
+            "metrics" => Ok(metrics::render(
+                &self.state,
+                memory::read_mem_available_kb(),
+            )),
+            "whitelist" => Ok(self.whitelist_hits()),
+            "clean" => Ok(format!("killed {}", self.cleanup(CycleTrigger::Manual))),
+            "reload" => {
+                let (applied, restart) = self.reload_config(true);
+                let mut reply = format!(
+                    "reloaded: {} applied, {} need restart\n",
+                    applied.len(),
+                    restart.len()
+                );
+                for line in applied {
+                    reply.push_str(&format!("applied {}\n", line));
                 }
-                Err(_) => None,
+                for line in restart {
+                    reply.push_str(&format!("restart {}\n", line));
+                }
+                Ok(reply)
             }
-        };
-
-        if let Some(s) = score {
-            if s < OOM_SCORE_THRESHOLD {
-                continue; // 活跃进程，跳过
+            "logs clear" => match self.logger.as_mut().map(|l| l.clear()) {
+                Some(true) => Ok("log cleared".to_string()),
+                Some(false) => Err(CommandError::new(
+                    ErrorCode::Failed,
+                    "failed to truncate log",
+                )),
+                None => Err(CommandError::new(
+                    ErrorCode::Unavailable,
+                    "logging disabled",
+                )),
+            },
+            "history clear" => {
+                self.state.reset_history();
+                // 不等下次保存，免得重启后又恢复出清掉的历史
+                if let Some(path) = &self.config.table_state {
+                    self.state.save_state(path, boottime_secs(), true);
+                }
+                Ok("history cleared".to_string())
             }
-        } else {
-            continue;
-        }
-
-        // ==========================================
-        // 漏斗第 3 层：查 cmdline 并匹配白名单 (最重，涉及字符串操作)
-        // 只有高危驻留后台 App 才会走到这一步
-        // ==========================================
-        res.path_buf.clear();
-        res.path_buf.push_str(pid_s);
-        res.path_buf.push_str("/cmdline");
-
-        let cmd_ok = match openat(
-            Some(proc_fd),
-            Path::new(&res.path_buf),
-            OFlag::O_RDONLY,
-            Mode::empty(),
-        ) {
-            Ok(fd) => {
-                let mut f = unsafe { File::from_raw_fd(fd) };
-                res.file_buf.clear();
-                if f.read_to_end(&mut res.file_buf).is_err() {
-                    false
-                } else {
-                    let slice = res.file_buf.split(|&c| c == 0).next().unwrap_or(&[]);
-                    res.cmdline_buf.clear();
-                    res.cmdline_buf.push_str(&String::from_utf8_lossy(slice));
-                    !res.cmdline_buf.is_empty()
+            "pause" => {
+                self.state.paused_until = Some(u64::MAX);
+                Ok("paused until resume".to_string())
+            }
+            "resume" => match self.state.paused_until.take() {
+                Some(_) => Ok("resumed".to_string()),
+                None => Ok("not paused".to_string()),
+            },
+            _ if command.starts_with("stats ") => Ok(self.package_stats(command[6..].trim())),
+            _ if command.starts_with("blacklist-once ") => {
+                let package = command["blacklist-once ".len()..].trim();
+                if package.is_empty() || package.contains(char::is_whitespace) {
+                    return Err(CommandError::new(
+                        ErrorCode::InvalidArgument,
+                        format!("invalid package: {}", package),
+                    ));
                 }
+                self.state.kill_once.insert(package.to_string());
+                Ok(format!("{} will be killed at the next cycle", package))
             }
-            Err(_) => false,
+            _ if command.starts_with("pause ") => match command[6..].trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => {
+                    self.state.paused_until = Some(boottime_secs() + secs);
+                    Ok(format!("paused for {}", format_duration(secs)))
+                }
+                _ => Err(CommandError::new(
+                    ErrorCode::InvalidArgument,
+                    format!("invalid pause duration: {}", &command[6..]),
+                )),
+            },
+            "recent" => {
+                let recent = events::recent();
+                let mut reply = format!("{} recent events\n", recent.len());
+                for line in recent {
+                    reply.push_str(&line);
+                    reply.push('\n');
+                }
+                Ok(reply)
+            }
+            "stats" => {
+                let stats = &self.state.stats.total;
+                let mut reply = format!(
+                    "cycles: {} (avg {}ms)\nkills: {}, reclaimed ~{}MB\n",
+                    stats.cycles,
+                    stats.avg_scan_ms(),
+                    stats.kills,
+                    stats.freed_kb / 1024
+                );
+                for (package, kills, kb) in stats.top(STATS_TOP) {
+                    reply.push_str(&format!("{} x{} ({}MB)\n", package, kills, kb / 1024));
+                }
+                let mut relaunches: Vec<(&str, u32)> = self.state.relaunch.annoyances().collect();
+                relaunches.sort_by_key(|r| std::cmp::Reverse(r.1));
+                let total: u32 = relaunches.iter().map(|r| r.1).sum();
+                reply.push_str(&format!("user relaunches: {}\n", total));
+                for (package, count) in relaunches {
+                    reply.push_str(&format!("{} {}\n", package, count));
+                }
+                Ok(reply)
+            }
+            _ => Err(CommandError::new(
+                ErrorCode::UnknownCommand,
+                format!("unknown command: {}", command),
+            )),
+        }
+    }
+
+    /// set <interval|threshold> <value> [persist]：立即生效，带 persist 时同时写回配置文件
+    fn handle_set(&mut self, args: &str) -> CommandResult {
+        let config = &mut self.config;
+        let parts: Vec<&str> = args.split_whitespace().collect();
+        let invalid = |message: String| Err(CommandError::new(ErrorCode::InvalidArgument, message));
+        let (key, value, persist) = match parts.as_slice() {
+            [key, value] => (*key, *value, false),
+            [key, value, "persist"] => (*key, *value, true),
+            _ => return invalid("usage: set <interval|threshold> <value> [persist]".to_string()),
         };
 
-        if !cmd_ok {
-            continue;
+        match key {
+            "interval" => match value.parse::<u64>() {
+                Ok(secs) if secs > 0 => {
+                    let old = std::mem::replace(&mut config.interval, secs);
+                    let interval = self.state.interval(config);
+                    if let Err(e) = arm_timer(&self.timer, interval) {
+                        config.interval = old;
+                        return Err(CommandError::new(
+                            ErrorCode::Failed,
+                            format!("failed to set timer: {}", e),
+                        ));
+                    }
+                    watchdog::set_interval(interval);
+                    self.adaptive.reset();
+                }
+                _ => return invalid(format!("invalid interval: {}", value)),
+            },
+            "threshold" => match value.parse::<i32>() {
+                Ok(t) if THRESHOLD_RANGE.contains(&t) => config.oom_threshold = t,
+                _ => {
+                    return invalid(format!(
+                        "invalid threshold: {} (must be {}..={})",
+                        value,
+                        THRESHOLD_RANGE.start(),
+                        THRESHOLD_RANGE.end()
+                    ))
+                }
+            },
+            _ => return invalid(format!("unknown setting: {}", key)),
         }
 
-        let cmdline = &res.cmdline_buf;
-
-        if cmdline.is_empty() {
-            continue;
+        if persist {
+            if let Err(e) = persist_config_value(&self.config_path, key, value) {
+                return Err(CommandError::new(
+                    ErrorCode::Failed,
+                    format!("{} set to {} but not saved: {}", key, value, e),
+                ));
+            }
+            return Ok(format!("{} set to {} (saved)", key, value));
         }
+        Ok(format!("{} set to {}", key, value))
+    }
 
-        // 白名单过滤
-        if is_in_whitelist(cmdline, whitelist) {
-            continue;
+    /// state <export|import> [path]：备份/恢复配置与学到的状态；导入的配置写回配置文件，由热加载换入
+    fn handle_state(&mut self, args: &str) -> CommandResult {
+        let (action, path) = match args.split_once(' ') {
+            Some((action, path)) => (action, Some(path.trim())),
+            None => (args, None),
+        };
+        let usage = || {
+            Err(CommandError::new(
+                ErrorCode::InvalidArgument,
+                "usage: state <export|import> [path]",
+            ))
+        };
+        if !matches!(action, "export" | "import") {
+            return usage();
+        }
+        let path = backup::resolve_path(path)
+            .map_err(|e| CommandError::new(ErrorCode::InvalidArgument, e))?;
+        let path = path.to_string_lossy();
+        let now = boottime_secs();
+        let failed = |message: String| Err(CommandError::new(ErrorCode::Failed, message));
+        match action {
+            "export" => {
+                let text = backup::export(&self.state, &self.config_path, now);
+                match write_atomic(&path, &text) {
+                    Ok(()) => Ok(format!("state exported to {}", path)),
+                    Err(e) => failed(format!("failed to write {}: {}", path, e)),
+                }
+            }
+            "import" => {
+                // 读取失败只报路径，不回显内容
+                let text = match fs::read_to_string(&*path) {
+                    Ok(text) => text,
+                    Err(e) => return failed(format!("failed to read {}: {}", path, e)),
+                };
+                let summary = backup::import(&text, &mut self.state, now)
+                    .map_err(|e| CommandError::new(ErrorCode::InvalidArgument, e))?;
+                let mut reply = format!(
+                    "imported {} packages, {} cooldowns",
+                    summary.packages, summary.cooldowns
+                );
+                if let Some(config) = summary.config {
+                    let current = fs::read_to_string(&self.config_path).unwrap_or_default();
+                    // 备份里的 token 已被抹掉，沿用当前配置中的
+                    let config = backup::restore_secrets(&config, &current);
+                    if config != current {
+                        // 有警告的配置不写回，免得一份坏备份覆盖掉能用的配置
+                        let (_, warnings) = parse_config_text(&self.config_path, &config);
+                        if let Some(first) = warnings.first() {
+                            return failed(format!(
+                                "{}, but config not restored: {} ({} problems)",
+                                reply,
+                                first,
+                                warnings.len()
+                            ));
+                        }
+                        if let Err(e) = write_atomic(&self.config_path, &config) {
+                            return failed(format!("{}, but config not restored: {}", reply, e));
+                        }
+                        reply.push_str(", config restored");
+                    }
+                }
+                Ok(reply)
+            }
+            _ => usage(),
         }
+    }
 
-        // 仅杀带有 ':' 的进程 (通常是 App 的后台服务进程，如 com.xxx.app:push)
-        if !cmdline.contains(':') {
-            continue;
+    fn status(&mut self) -> CommandResult {
+        let now = boottime_secs();
+        let state = &mut self.state;
+        // 与 perform_cleanup 的暂停判定一致：pause 命令、暂停属性、充电暂停、schedule 停用时段
+        let mode = match state.paused_until.filter(|&until| now < until) {
+            Some(u64::MAX) => "paused".to_string(),
+            Some(until) => format!(
+                "paused ({} left)",
+                format_duration(until.saturating_sub(now))
+            ),
+            None if state.pause_property.is_set(now) => format!("paused ({})", PAUSE_PROPERTY),
+            None if state.charger_pause => "paused (on charger)".to_string(),
+            None => match schedule::active(&self.config.schedule).filter(|w| !w.enabled) {
+                Some(window) => format!("paused (schedule {})", window),
+                None if self.config.dry_run => "running (dry run)".to_string(),
+                None => "running".to_string(),
+            },
+        };
+        Ok(format!(
+            "{}\nplatform: {}\nscreen: {} ({})\nzram: {}\ninterval: {}s\nthreshold: {}\ntracked: {} processes\nkills: {}\ncycles: {} (avg {}ms, reclaimed ~{}MB)\nlast freed: {}MB\nhidden: {}\nprotected: {}\nfrozen: {}\nrecent apps: {}\nstorms: {}\npsi: {}\nabi: {}\nforeign namespaces: {}\nlog: {}\n",
+            mode,
+            self.platform.describe(&self.res.freezer),
+            match self.screen.is_on() {
+                Some(true) => "on",
+                Some(false) => "off",
+                None => "unknown",
+            },
+            self.screen.backend_name(),
+            self.config
+                .quirks
+                .zram_used_kb()
+                .map_or_else(|| "unavailable".to_string(), |kb| format!("{}MB", kb / 1024)),
+            state.interval(&self.config),
+            match state.threshold(&self.config, memory::read_mem_available_kb()) {
+                t if state.battery_rule.is_some_and(|r| r.threshold == Some(t)) => {
+                    format!("{} (battery, base {})", t, self.config.oom_threshold)
+                }
+                t if state.schedule.is_some_and(|w| w.threshold == Some(t)) => {
+                    format!("{} (schedule, base {})", t, self.config.oom_threshold)
+                }
+                t if t == self.config.oom_threshold => t.to_string(),
+                t if state.screen_off && t == self.config.screen_off_threshold => {
+                    format!("{} (screen off, base {})", t, self.config.oom_threshold)
+                }
+                t => format!("{} (tier, base {})", t, self.config.oom_threshold),
+            },
+            state.table.len(),
+            state.history.total_kills(),
+            state.stats.total.cycles,
+            state.stats.total.avg_scan_ms(),
+            state.stats.total.freed_kb / 1024,
+            state.last_freed_kb / 1024,
+            state.hidden.len(),
+            state.protected,
+            state.frozen.len(),
+            if state.recent_apps.enabled() {
+                state.recent_apps.iter().collect::<Vec<_>>().join(", ")
+            } else {
+                "off".to_string()
+            },
+            {
+                let mut storms: Vec<String> = state
+                    .throttle
+                    .storms(now)
+                    .map(|(p, left)| format!("{} ({} left)", p, format_duration(left)))
+                    .collect();
+                storms.sort();
+                if storms.is_empty() {
+                    "none".to_string()
+                } else {
+                    storms.join(", ")
+                }
+            },
+            match (psi::some_avg10(), self.config.psi_threshold) {
+                (None, _) => "unavailable".to_string(),
+                (Some(avg10), None) => format!("some avg10 {:.2}%", avg10),
+                (Some(avg10), Some(t)) => format!("some avg10 {:.2}% (threshold {}%)", avg10, t),
+            },
+            {
+                let c = abi::census(self.procfs.fd());
+                format!(
+                    "32-bit {} processes ({}MB), 64-bit {} processes ({}MB)",
+                    c.procs_32,
+                    c.rss_32_kb / 1024,
+                    c.procs_64,
+                    c.rss_64_kb / 1024
+                )
+            },
+            if state.foreign_ns.is_empty() {
+                "none".to_string()
+            } else {
+                let mut ns: Vec<_> = state.foreign_ns.iter().collect();
+                ns.sort();
+                ns.iter()
+                    .map(|(inode, n)| format!("{} ({} processes)", inode, n))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            },
+            match &self.logger {
+                None => "disabled".to_string(),
+                Some(l) if l.health.is_full() => format!(
+                    "filesystem full, {} records not written",
+                    l.health.dropped()
+                ),
+                Some(_) => "ok".to_string(),
+            }
+        ))
+    }
+
+    /// stats <package>：该包的清理统计（配置了 table_state 时跨重启累计），以及它当前的进程和各自状态
+    fn package_stats(&self, package: &str) -> String {
+        let now = boottime_secs();
+        let state = &self.state;
+        let mut reply = match state.history.get(package) {
+            Some(h) => format!(
+                "{}: killed {}, last {} ago, avg oom {}, avg freed {}MB\n",
+                package,
+                h.kills,
+                format_duration(now.saturating_sub(h.last_kill)),
+                h.avg_oom(),
+                h.avg_freed_kb() / 1024
+            ),
+            None => format!("{}: never killed\n", package),
+        };
+        if let Some((_, n)) = state.relaunch.annoyances().find(|(p, _)| *p == package) {
+            reply.push_str(&format!("user relaunches: {}\n", n));
+        }
+        if let Some(secs) = state.relaunch.cooldown_remaining(package, now) {
+            reply.push_str(&format!("cooldown: {} left\n", format_duration(secs)));
         }
 
-        // 击杀目标进程
-        if kill(Pid::from_raw(pid), Signal::SIGKILL).is_ok() {
-            killed_list.push(cmdline.clone());
+        for (pid, name) in package_processes(package) {
+            let proc_file = |file: &str| fs::read_to_string(format!("/proc/{}/{}", pid, file));
+            let oom = proc_file("oom_score_adj")
+                .map_or_else(|_| "?".to_string(), |s| s.trim().to_string());
+            let rss_mb = proc_file("status")
+                .ok()
+                .and_then(|s| memory::status_field_kb(s.as_bytes(), "VmRSS"))
+                .map_or(0, |kb| kb / 1024);
+            let status = if let Some(secs) = state.respawn.backoff_remaining(&name, now) {
+                format!("respawn backoff {} left", format_duration(secs))
+            } else if let Some(node) = state.table.get(pid) {
+                format!(
+                    "background {}, idle {}",
+                    format_duration(now.saturating_sub(node.background_since)),
+                    format_duration(now.saturating_sub(node.last_active))
+                )
+            } else {
+                "not a candidate".to_string()
+            };
+            reply.push_str(&format!(
+                "{} {} oom {} RSS {}MB {}\n",
+                pid, name, oom, rss_mb, status
+            ));
         }
+        reply
     }
 
-    if !killed_list.is_empty() {
-        if let Some(l) = logger {
-            l.write_cleanup(&killed_list);
+    /// whitelist：各白名单条目的命中次数，从未命中的排在最前
+    fn whitelist_hits(&self) -> String {
+        let now = whitelist::unix_secs();
+        let hits = &self.state.whitelist_hits;
+        let mut rules: Vec<_> = self
+            .config
+            .whitelist
+            .iter()
+            .map(|r| (r.to_string(), hits.get(r)))
+            .collect();
+        rules.sort_by(|a, b| a.1.count.cmp(&b.1.count).then_with(|| a.0.cmp(&b.0)));
+        let mut reply = format!(
+            "{} rules, counting for {}\n",
+            rules.len(),
+            format_duration(now.saturating_sub(hits.since()))
+        );
+        for (rule, h) in rules {
+            if h.count == 0 {
+                reply.push_str(&format!("{} never\n", rule));
+            } else {
+                reply.push_str(&format!(
+                    "{} {} hits, last {} ago\n",
+                    rule,
+                    h.count,
+                    format_duration(now.saturating_sub(h.last_hit))
+                ));
+            }
         }
+        reply
     }
-}
 
-fn is_device_in_deep_doze() -> bool {
-    if let Ok(output) = Command::new("cmd")
-        .args(&["deviceidle", "get", "deep"])
-        .output()
-    {
-        let s = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        return s == "IDLE";
+    /// 按当前配置列出本轮会被清理的进程（不动手）
+    fn candidates(&mut self) -> String {
+        let mut scan = ScanReport::default();
+        let avail_kb = memory::read_mem_available_kb();
+        let candidates = select_candidates(
+            &self.config,
+            &mut self.res,
+            &mut self.state,
+            &self.procfs,
+            avail_kb,
+            true,
+            &mut scan,
+        );
+        let mut reply = format!("{} candidates\n", candidates.len());
+        for c in candidates {
+            reply.push_str(&format!(
+                "{} {} {} {}{}\n",
+                c.pid,
+                c.oom,
+                c.mem,
+                c.name,
+                if c.is_32bit { " [32-bit]" } else { "" }
+            ));
+        }
+        reply
     }
-    false
 }
 
-// --- 配置加载与时间 ---
-
-fn load_config(path: &str) -> AppConfig {
-    let mut interval = DEFAULT_INTERVAL;
-    let mut whitelist: FxHashSet<WhitelistRule> = FxHashSet::default();
-
-    // 内置默认白名单（完全匹配）
-    whitelist.insert(WhitelistRule::Exact("com.android.systemui".to_string()));
-    whitelist.insert(WhitelistRule::Exact("android".to_string()));
-    whitelist.insert(WhitelistRule::Exact("com.android.phone".to_string()));
-
-    if let Ok(content) = fs::read_to_string(path) {
-        let mut in_whitelist_mode = false;
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+/// 属于该包的进程（进程名为包名或 "包名:xxx"）
+fn package_processes(package: &str) -> Vec<(i32, String)> {
+    let Ok(dir) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut found: Vec<(i32, String)> = dir
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<i32>().ok()?;
+            let cmdline = fs::read(entry.path().join("cmdline")).ok()?;
+            let name = String::from_utf8_lossy(cmdline.split(|&b| b == 0).next()?).to_string();
+            (mem_cleaner::history::package_name(&name) == package).then_some((pid, name))
+        })
+        .collect();
+    found.sort();
+    found
+}
 
-            if line.starts_with("interval:") {
-                if let Some(val_part) = line.split(':').nth(1) {
-                    if let Ok(val) = val_part.trim().parse::<u64>() {
-                        interval = val;
-                    }
-                }
-                in_whitelist_mode = false;
-            } else if line.starts_with("whitelist:") {
-                in_whitelist_mode = true;
-                if let Some(val_part) = line.split(':').nth(1) {
-                    parse_whitelist_rules(val_part, &mut whitelist);
-                }
-            } else if in_whitelist_mode {
-                parse_whitelist_rules(line, &mut whitelist);
+/// 改写配置文件中 key 所在行（保留其余内容与注释），不存在则追加
+///
+/// TOML 配置写成 `key = value`，只改第一个表头之前的顶层键，新键也插在表头之前。
+fn persist_config_value(path: &str, key: &str, value: &str) -> std::io::Result<()> {
+    let content = fs::read_to_string(path)?;
+    let toml = ConfigFormat::detect(path, &content) == ConfigFormat::Toml;
+    let setting = if toml {
+        format!("{} = {}", key, value)
+    } else {
+        format!("{}: {}", key, value)
+    };
+    let mut replaced = false;
+    let mut in_table = false;
+    let mut out = String::with_capacity(content.len() + 32);
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if toml && !in_table && trimmed.starts_with('[') {
+            in_table = true;
+            if !replaced {
+                out.push_str(&setting);
+                out.push('\n');
+                replaced = true;
             }
         }
+        let matches = if toml {
+            !in_table
+                && trimmed
+                    .split_once('=')
+                    .is_some_and(|(k, _)| k.trim() == key)
+        } else {
+            config_value(trimmed, key).is_some()
+        };
+        if !replaced && matches {
+            out.push_str(&setting);
+            replaced = true;
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    if !replaced {
+        out.push_str(&setting);
+        out.push('\n');
     }
+    write_atomic(path, &out)
+}
 
-    AppConfig {
-        interval,
-        whitelist,
+/// 先写临时文件再 rename，避免写到一半掉电留下残缺文件；rename 也不会顺着符号链接写到别处
+fn write_atomic(path: &str, content: &str) -> std::io::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    let _ = fs::remove_file(&tmp);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)?;
+    file.write_all(content.as_bytes())?;
+    // 配置里有 token，通常是 0600：沿用原文件的权限
+    if let Ok(meta) = fs::metadata(path) {
+        file.set_permissions(meta.permissions())?;
     }
+    drop(file);
+    fs::rename(&tmp, path)
 }
 
-/// 解析白名单规则（支持 xxx:* 前缀匹配 和 xxx 完全匹配）
-fn parse_whitelist_rules(line: &str, whitelist: &mut FxHashSet<WhitelistRule>) {
-    for part in line.split(',') {
-        let pkg = part.trim();
-        if pkg.is_empty() {
-            continue;
-        }
+/// 按给定配置完整跑一轮候选选择，打印会被清理的进程及预计的 MemAvailable
+fn simulate(config: &AppConfig) {
+    let procfs = open_proc();
+    let mut res = ScannerResources::new();
+    let mut state = CleanerState::new(config);
+    let avail_kb = memory::read_mem_available_kb();
+    let mut scan = ScanReport::default();
+    let candidates = select_candidates(
+        config, &mut res, &mut state, &procfs, avail_kb, true, &mut scan,
+    );
 
-        if let Some(prefix) = pkg.strip_suffix(":*") {
-            if prefix.contains(':') || !prefix.is_empty() {
-                whitelist.insert(WhitelistRule::Prefix(prefix.to_string()));
-            } else {
-                whitelist.insert(WhitelistRule::Exact(pkg.to_string()));
-            }
+    println!("{:>7} {:>5} {:>9}  NAME", "PID", "OOM", "PSS(MB)");
+    let mut itoa_buf = ItoaBuffer::new();
+    let mut freed_kb = 0;
+    for c in &candidates {
+        // 杀掉进程真正能回收的是 PSS 而不是 RSS
+        let pid_s = itoa_buf.format(c.pid);
+        let pss_kb = if res.read_proc_file(&procfs, pid_s, "smaps_rollup") {
+            memory::status_field_kb(&res.file_buf, "Pss").unwrap_or(c.mem.rss_kb)
         } else {
-            whitelist.insert(WhitelistRule::Exact(pkg.to_string()));
-        }
+            c.mem.rss_kb
+        } + c.mem.gpu_kb;
+        freed_kb += pss_kb;
+        println!(
+            "{:>7} {:>5} {:>9.1}  {}",
+            c.pid,
+            c.oom,
+            pss_kb as f64 / 1024.0,
+            c.name
+        );
     }
-}
 
-static TIME_FMT: &[FormatItem<'static>] =
-    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+    println!(
+        "{} candidates, estimated {:.1} MB freed",
+        candidates.len(),
+        freed_kb as f64 / 1024.0
+    );
+    if let Some(avail_kb) = avail_kb {
+        println!(
+            "MemAvailable: {} MB -> {} MB",
+            avail_kb / 1024,
+            (avail_kb + freed_kb) / 1024
+        );
+    }
+}
 
-fn now_fmt() -> String {
-    let dt = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
-    dt.format(TIME_FMT)
-        .unwrap_or_else(|_| "time_err".to_string())
+/// /proc 的 hidepid 挂载选项，未启用（0/off）时返回 None
+fn proc_hidepid() -> Option<String> {
+    let mounts = fs::read_to_string("/proc/mounts").ok()?;
+    mounts
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|f| f.len() > 3 && f[1] == "/proc" && f[2] == "proc")
+        .flat_map(|f| f[3].split(',').map(str::to_string).collect::<Vec<_>>())
+        .filter_map(|opt| opt.strip_prefix("hidepid=").map(str::to_string))
+        .find(|mode| mode != "0" && mode != "off")
 }
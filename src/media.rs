@@ -0,0 +1,113 @@
+use fxhash::FxHashSet;
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+// 播放状态变化快，缓存比小部件短得多
+const MEDIA_CACHE_TTL: Duration = Duration::from_secs(15);
+
+/// 正在播放音频或持有前台服务（下载、导航、播放通知）的 App：用户明确希望它们继续运行
+#[derive(Default)]
+pub struct MediaActivity {
+    playing_uids: FxHashSet<u32>,
+    foreground_packages: FxHashSet<String>,
+    last_refresh: Option<Instant>,
+}
+
+impl MediaActivity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// uid 正在播放，或 package 有前台服务
+    pub fn contains(&mut self, uid: u32, package: &str) -> bool {
+        if self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= MEDIA_CACHE_TTL)
+        {
+            self.refresh();
+        }
+        self.playing_uids.contains(&uid) || self.foreground_packages.contains(package)
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        if let Ok(output) = Command::new("dumpsys").arg("audio").output() {
+            self.playing_uids = parse_audio_players(&String::from_utf8_lossy(&output.stdout));
+        }
+        if let Ok(output) = Command::new("dumpsys")
+            .args(["activity", "services"])
+            .output()
+        {
+            self.foreground_packages =
+                parse_foreground_services(&String::from_utf8_lossy(&output.stdout));
+        }
+    }
+}
+
+/// 解析 `dumpsys audio` 的播放器列表，取处于 started 状态的 UID：
+///
+/// ```text
+///   ID:15 -- type:android.media.MediaPlayer -- u/pid:10123/4567 -- state:started -- attr:...
+/// ```
+fn parse_audio_players(text: &str) -> FxHashSet<u32> {
+    text.lines()
+        .filter(|line| line.contains("state:started"))
+        .filter_map(|line| {
+            let rest = &line[line.find("u/pid:")? + "u/pid:".len()..];
+            rest.split('/').next()?.trim().parse().ok()
+        })
+        .collect()
+}
+
+/// 解析 `dumpsys activity services`，取 isForeground=true 的服务所属包：
+///
+/// ```text
+///   * ServiceRecord{9f3c1e u0 com.foo.music/.PlaybackService}
+///     isForeground=true foregroundId=1 foregroundNoti=Notification(...)
+/// ```
+fn parse_foreground_services(text: &str) -> FxHashSet<String> {
+    let mut packages = FxHashSet::default();
+    let mut current: Option<&str> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(record) = line.strip_prefix("* ServiceRecord{") {
+            current = record
+                .trim_end_matches('}')
+                .split_whitespace()
+                .last()
+                .and_then(|component| component.split_once('/'))
+                .map(|(pkg, _)| pkg);
+        } else if line.starts_with("isForeground=true") {
+            if let Some(pkg) = current {
+                packages.insert(pkg.to_string());
+            }
+        }
+    }
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn started_players_only() {
+        let dump = "  ID:15 -- type:android.media.MediaPlayer -- u/pid:10123/4567 -- state:started -- attr:x\n\
+                    \x20 ID:16 -- type:android.media.SoundPool -- u/pid:10200/5000 -- state:paused -- attr:y\n";
+        let uids = parse_audio_players(dump);
+        assert!(uids.contains(&10123));
+        assert!(!uids.contains(&10200));
+    }
+
+    #[test]
+    fn foreground_services_by_package() {
+        let dump = "  * ServiceRecord{9f3c1e u0 com.foo.music/.PlaybackService}\n\
+                    \x20   isForeground=true foregroundId=1\n\
+                    \x20 * ServiceRecord{1a2b3c u0 com.bar/.Sync}\n\
+                    \x20   isForeground=false foregroundId=0\n";
+        let packages = parse_foreground_services(dump);
+        assert!(packages.contains("com.foo.music"));
+        assert!(!packages.contains("com.bar"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// 每分钟最多调用多少次 dumpsys meminfo：每次要经 binder 让目标进程自己统计，耗时上百毫秒
+const DUMPSYS_PER_MINUTE: u32 = 10;
+const DUMPSYS_WINDOW: Duration = Duration::from_secs(60);
+
+/// `dumpsys meminfo <pid> -d` 的 App Summary（单位 kB）
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AppMeminfo {
+    pub total_pss_kb: u64,
+    pub java_kb: u64,
+    pub native_kb: u64,
+    pub graphics_kb: u64,
+}
+
+impl fmt::Display for AppMeminfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PSS {}MB (Java {}MB, Native {}MB, Graphics {}MB)",
+            self.total_pss_kb / 1024,
+            self.java_kb / 1024,
+            self.native_kb / 1024,
+            self.graphics_kb / 1024
+        )
+    }
+}
+
+/// 限频的 dumpsys meminfo 调用
+#[derive(Default)]
+pub struct MeminfoSampler {
+    window_start: Option<Instant>,
+    used: u32,
+}
+
+impl MeminfoSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 超出频率限制或调用失败时返回 None，由调用方退回 RSS 等估算值
+    pub fn sample(&mut self, pid_s: &str) -> Option<AppMeminfo> {
+        let now = Instant::now();
+        if self
+            .window_start
+            .is_none_or(|t| now.duration_since(t) >= DUMPSYS_WINDOW)
+        {
+            self.window_start = Some(now);
+            self.used = 0;
+        }
+        if self.used >= DUMPSYS_PER_MINUTE {
+            return None;
+        }
+        self.used += 1;
+
+        let output = Command::new("dumpsys")
+            .args(["meminfo", pid_s, "-d"])
+            .output()
+            .ok()?;
+        parse_app_summary(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// 解析 App Summary 段，各行取第一列（PSS）：
+///
+/// ```text
+///  App Summary
+///                        Pss(KB)                        Rss(KB)
+///            Java Heap:    12345                          23456
+///          Native Heap:     3456                           4567
+///             Graphics:     7890                           7890
+///            TOTAL PSS:    45678            TOTAL RSS:    56789
+/// ```
+///
+/// 老版本的合计行写作 "TOTAL:"
+fn parse_app_summary(text: &str) -> Option<AppMeminfo> {
+    let summary = &text[text.find("App Summary")?..];
+    let mut info = AppMeminfo::default();
+    let mut found_total = false;
+    for line in summary.lines() {
+        let Some((key, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(kb) = rest
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        match key.trim() {
+            "Java Heap" => info.java_kb = kb,
+            "Native Heap" => info.native_kb = kb,
+            "Graphics" => info.graphics_kb = kb,
+            "TOTAL PSS" | "TOTAL" => {
+                info.total_pss_kb = kb;
+                found_total = true;
+                break;
+            }
+            _ => {}
+        }
+    }
+    found_total.then_some(info)
+}
@@ -0,0 +1,150 @@
+use fxhash::FxHashSet;
+
+use std::fmt;
+use std::fs;
+
+const KGSL_PROC_DIR: &str = "/sys/class/kgsl/kgsl/proc";
+
+/// 进程内存占用（单位 kB）
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProcMemory {
+    pub rss_kb: u64,
+    pub swap_kb: u64, // 已换出到 zram 的部分
+    pub gpu_kb: u64,  // kgsl + dmabuf 图形缓冲，RSS 中看不到
+}
+
+impl ProcMemory {
+    /// 进程退出后实际归还的内存
+    pub fn resident_kb(&self) -> u64 {
+        self.rss_kb + self.gpu_kb
+    }
+
+    /// 用于策略判断的占用：zram 中的页按压缩后的代价折算
+    pub fn effective_kb(&self, swap_weight: f32) -> u64 {
+        self.rss_kb + self.gpu_kb + (self.swap_kb as f32 * swap_weight) as u64
+    }
+}
+
+impl fmt::Display for ProcMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RSS {}MB", self.rss_kb / 1024)?;
+        if self.swap_kb > 0 {
+            write!(f, ", Swap {}MB", self.swap_kb / 1024)?;
+        }
+        if self.gpu_kb > 0 {
+            write!(f, ", GPU {}MB", self.gpu_kb / 1024)?;
+        }
+        Ok(())
+    }
+}
+
+/// 从 /proc/<pid>/status（或 meminfo 等同格式）内容中取 "Key:   123 kB" 字段
+pub fn status_field_kb(status: &[u8], key: &str) -> Option<u64> {
+    // 按字节逐行匹配：Name 行的 comm 可能不是合法 UTF-8，不能让它拖累整个文件
+    status.split(|&b| b == b'\n').find_map(|line| {
+        let rest = line.strip_prefix(key.as_bytes())?.strip_prefix(b":")?;
+        std::str::from_utf8(rest)
+            .ok()?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    })
+}
+
+/// Java 堆与 native 堆的 PSS 之和，按 /proc/<pid>/smaps 中的映射名归类
+///
+/// 用 PSS 而非 RSS：zygote 预加载的堆与其他 App 共享，不该算在某一个进程头上
+pub fn smaps_heap_kb(smaps: &[u8]) -> u64 {
+    let mut total = 0;
+    let mut in_heap = false;
+    for line in smaps.split(|&b| b == b'\n') {
+        if let Some(rest) = line.strip_prefix(b"Pss:") {
+            if in_heap {
+                total += std::str::from_utf8(rest)
+                    .ok()
+                    .and_then(|s| s.split_whitespace().next()?.parse::<u64>().ok())
+                    .unwrap_or(0);
+            }
+        } else if is_mapping_header(line) {
+            in_heap = HEAP_MAPPINGS
+                .iter()
+                .any(|name| line.windows(name.len()).any(|w| w == name.as_bytes()));
+        }
+    }
+    total
+}
+
+/// ART 堆空间、bionic/scudo/jemalloc 分配器以及传统 brk 堆
+const HEAP_MAPPINGS: &[&str] = &[
+    "[anon:dalvik-",
+    "[anon:libc_malloc",
+    "[anon:scudo:",
+    "[heap]",
+];
+
+/// 映射头形如 "12c00000-32c00000 rw-p 00000000 00:00 0   [anon:dalvik-main space]"
+fn is_mapping_header(line: &[u8]) -> bool {
+    line.split(|&b| b == b' ').next().is_some_and(|range| {
+        range.contains(&b'-') && range.iter().all(|&b| b == b'-' || b.is_ascii_hexdigit())
+    })
+}
+
+/// 系统当前可用内存 (/proc/meminfo MemAvailable)
+pub fn read_mem_available_kb() -> Option<u64> {
+    let content = fs::read("/proc/meminfo").ok()?;
+    status_field_kb(&content, "MemAvailable")
+}
+
+/// 进程持有的图形内存：Adreno 走 kgsl 统计，其余走 fdinfo 中的 dmabuf 条目
+pub fn read_gpu_kb(pid_s: &str) -> u64 {
+    let kgsl = read_kgsl_bytes(pid_s);
+    let dmabuf = read_dmabuf_bytes(pid_s);
+    (kgsl + dmabuf) / 1024
+}
+
+fn read_kgsl_bytes(pid_s: &str) -> u64 {
+    ["gpumem_mapped", "gpumem_unmapped"]
+        .iter()
+        .filter_map(|name| fs::read_to_string(format!("{}/{}/{}", KGSL_PROC_DIR, pid_s, name)).ok())
+        .filter_map(|s| s.trim().parse::<u64>().ok())
+        .sum()
+}
+
+fn read_dmabuf_bytes(pid_s: &str) -> u64 {
+    let dir = match fs::read_dir(format!("/proc/{}/fdinfo", pid_s)) {
+        Ok(d) => d,
+        Err(_) => return 0,
+    };
+
+    // 同一个 buffer 可能被多个 fd 引用，按 inode 去重
+    let mut seen: FxHashSet<u64> = FxHashSet::default();
+    let mut total = 0;
+
+    for entry in dir.flatten() {
+        let content = match fs::read_to_string(entry.path()) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        if !content.contains("exp_name:") {
+            continue;
+        }
+
+        let mut size = None;
+        let mut ino = None;
+        for line in content.lines() {
+            if let Some(v) = line.strip_prefix("size:") {
+                size = v.trim().parse::<u64>().ok();
+            } else if let Some(v) = line.strip_prefix("ino:") {
+                ino = v.trim().parse::<u64>().ok();
+            }
+        }
+
+        if let Some(size) = size {
+            if ino.is_none_or(|i| seen.insert(i)) {
+                total += size;
+            }
+        }
+    }
+    total
+}
@@ -0,0 +1,178 @@
+//! Prometheus 文本格式的运行指标：配置 metrics_path 时每轮清理后写成文件，
+//! 开启 http_api 时也可由 GET /metrics 抓取
+//!
+//! 计数器从守护进程启动时算起，不跨重启累计。
+
+use fxhash::FxHashMap;
+
+use std::fmt::Write;
+use std::fs;
+
+use crate::engine::CleanerState;
+
+/// Prometheus 抓取时期望的 Content-Type
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 按当前状态渲染全部指标；avail_kb 读不到时省略该项
+pub fn render(state: &CleanerState, avail_kb: Option<u64>) -> String {
+    let stats = &state.stats;
+    let total = &stats.total;
+    let mut out = String::new();
+
+    metric(&mut out, "cycles_total", "counter", "Cleanup cycles run");
+    let _ = writeln!(out, "mem_cleaner_cycles_total {}", total.cycles);
+    metric(&mut out, "kills_total", "counter", "Processes killed");
+    let _ = writeln!(out, "mem_cleaner_kills_total {}", total.kills);
+    metric(
+        &mut out,
+        "freed_bytes_total",
+        "counter",
+        "Memory freed, estimated from RSS before the kill",
+    );
+    let _ = writeln!(
+        out,
+        "mem_cleaner_freed_bytes_total {}",
+        total.freed_kb * 1024
+    );
+
+    metric(
+        &mut out,
+        "package_kills_total",
+        "counter",
+        "Kills per package",
+    );
+    let mut packages: Vec<(&String, &(u32, u64))> = total.packages.iter().collect();
+    packages.sort_by(|a, b| a.0.cmp(b.0));
+    for (package, (kills, _)) in packages {
+        let _ = writeln!(
+            out,
+            "mem_cleaner_package_kills_total{{package=\"{}\"}} {}",
+            escape_label(package),
+            kills
+        );
+    }
+
+    metric(
+        &mut out,
+        "last_kill_timestamp_seconds",
+        "gauge",
+        "Unix time of the last kill, 0 if none yet",
+    );
+    let _ = writeln!(
+        out,
+        "mem_cleaner_last_kill_timestamp_seconds {}",
+        stats.last_kill_at
+    );
+    metric(
+        &mut out,
+        "scan_duration_seconds",
+        "gauge",
+        "Duration of the last cleanup cycle",
+    );
+    let _ = writeln!(
+        out,
+        "mem_cleaner_scan_duration_seconds {:.3}",
+        stats.last_scan_ms as f64 / 1000.0
+    );
+    metric(
+        &mut out,
+        "scan_seconds_total",
+        "counter",
+        "Total time spent in cleanup cycles",
+    );
+    let _ = writeln!(
+        out,
+        "mem_cleaner_scan_seconds_total {:.3}",
+        total.scan_ms as f64 / 1000.0
+    );
+
+    metric(
+        &mut out,
+        "table_processes",
+        "gauge",
+        "Tracked processes by /proc state",
+    );
+    let mut by_state: FxHashMap<u8, usize> = FxHashMap::default();
+    for (_, node) in state.table.iter() {
+        *by_state.entry(node.state).or_default() += 1;
+    }
+    let mut by_state: Vec<(u8, usize)> = by_state.into_iter().collect();
+    by_state.sort();
+    for (st, count) in by_state {
+        let _ = writeln!(
+            out,
+            "mem_cleaner_table_processes{{state=\"{}\"}} {}",
+            st as char, count
+        );
+    }
+
+    if let Some(avail_kb) = avail_kb {
+        metric(&mut out, "mem_available_bytes", "gauge", "MemAvailable");
+        let _ = writeln!(out, "mem_cleaner_mem_available_bytes {}", avail_kb * 1024);
+    }
+    out
+}
+
+/// 先写临时文件再 rename，采集端不会读到半截的文件
+pub fn write(path: &str, text: &str) -> bool {
+    let tmp = format!("{}.tmp", path);
+    if fs::write(&tmp, text).is_err() || fs::rename(&tmp, path).is_err() {
+        let _ = fs::remove_file(&tmp);
+        return false;
+    }
+    true
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP mem_cleaner_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE mem_cleaner_{} {}", name, kind);
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+    use crate::table::ProcStat;
+
+    #[test]
+    fn renders_prometheus_text() {
+        let config = parse_config("");
+        let mut state = CleanerState::new(&config);
+        state.stats.record_cycle(250);
+        state.stats.record_kill("com.foo", 2048);
+        state.stats.record_kill("com.foo", 1024);
+        state.table.begin_cycle();
+        let stat = ProcStat {
+            cpu_ticks: 0,
+            start_time: 100,
+            state: b'S',
+        };
+        state.table.observe(1, &stat, 10);
+        state.table.observe(2, &stat, 10);
+
+        let text = render(&state, Some(1024));
+        assert!(
+            text.contains("# TYPE mem_cleaner_kills_total counter\nmem_cleaner_kills_total 2\n")
+        );
+        assert!(text.contains("mem_cleaner_package_kills_total{package=\"com.foo\"} 2\n"));
+        assert!(text.contains("mem_cleaner_freed_bytes_total 3145728\n"));
+        assert!(text.contains("mem_cleaner_scan_duration_seconds 0.250\n"));
+        assert!(text.contains("mem_cleaner_table_processes{state=\"S\"} 2\n"));
+        assert!(text.contains("mem_cleaner_mem_available_bytes 1048576\n"));
+        assert!(text
+            .lines()
+            .all(|l| l.starts_with('#') || l.split(' ').count() == 2));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
@@ -0,0 +1,99 @@
+//! 命名空间识别：容器、沙盒中的进程与守护进程不在同一 PID/user 命名空间，
+//! 比较 /proc/<pid>/ns 下的 inode 即可区分，默认不去碰它们
+
+use nix::fcntl::AtFlags;
+use nix::sys::stat::fstatat;
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::RawFd;
+use std::path::Path;
+
+/// 其他命名空间中进程的处理方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NamespacePolicy {
+    Isolate,         // 只处理与守护进程同一命名空间的进程
+    All,             // 不区分命名空间
+    Allow(Vec<u64>), // 除本命名空间外，只进入列出的命名空间（inode）
+}
+
+impl NamespacePolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "isolate" => Some(Self::Isolate),
+            "all" => Some(Self::All),
+            _ => {
+                let list = s.strip_prefix("allow")?.trim();
+                let inodes: Option<Vec<u64>> = list
+                    .split(',')
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.parse().ok())
+                    .collect();
+                inodes.filter(|i| !i.is_empty()).map(Self::Allow)
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Isolate => "isolate".to_string(),
+            Self::All => "all".to_string(),
+            Self::Allow(inodes) => format!(
+                "allow {}",
+                inodes
+                    .iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+
+    /// 该命名空间中的进程是否可以处理
+    pub fn allows(&self, inode: u64) -> bool {
+        match self {
+            Self::Isolate => false,
+            Self::All => true,
+            Self::Allow(inodes) => inodes.contains(&inode),
+        }
+    }
+}
+
+/// 守护进程自身所在的命名空间
+#[derive(Debug, Clone, Copy)]
+pub struct HostNamespaces {
+    pid: u64,
+    user: u64,
+}
+
+impl HostNamespaces {
+    pub fn detect() -> Option<Self> {
+        let inode = |ns: &str| fs::metadata(format!("/proc/self/ns/{}", ns)).map(|m| m.ino());
+        Some(Self {
+            pid: inode("pid").ok()?,
+            user: inode("user").ok()?,
+        })
+    }
+
+    /// 进程不在本命名空间时返回它的命名空间 inode（优先 PID 命名空间）；读不到时视为同一命名空间
+    pub fn foreign(&self, proc_fd: RawFd, path_buf: &mut String, pid_s: &str) -> Option<u64> {
+        let mut inode = |ns: &str| {
+            path_buf.clear();
+            path_buf.push_str(pid_s);
+            path_buf.push_str("/ns/");
+            path_buf.push_str(ns);
+            fstatat(
+                Some(proc_fd),
+                Path::new(path_buf.as_str()),
+                AtFlags::empty(),
+            )
+            .ok()
+            .map(|st| st.st_ino)
+        };
+        if let Some(pid) = inode("pid").filter(|&i| i != self.pid) {
+            return Some(pid);
+        }
+        inode("user").filter(|&i| i != self.user)
+    }
+}
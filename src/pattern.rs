@@ -0,0 +1,160 @@
+//! 名单规则里的通配符与正则：glob 自己实现（只有 `*` 和 `?`），正则用 libc 的 POSIX ERE，
+//! 配置加载时编译一次；regexec 要 NUL 结尾的字符串，匹配时复用线程内的缓冲区，不逐次分配
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// `*` 匹配任意长度（含空），`?` 匹配单个字节（包名、进程名都是 ASCII）
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let (p, t) = (pattern.as_bytes(), text.as_bytes());
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack = None; // (最近一个 `*` 之后的位置, 它当前吞到的文本位置)
+    while ti < t.len() {
+        match p.get(pi) {
+            Some(b'*') => {
+                pi += 1;
+                backtrack = Some((pi, ti));
+            }
+            Some(&c) if c == b'?' || c == t[ti] => {
+                pi += 1;
+                ti += 1;
+            }
+            _ => match backtrack {
+                Some((star_pi, star_ti)) => {
+                    pi = star_pi;
+                    ti = star_ti + 1;
+                    backtrack = Some((star_pi, star_ti + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == b'*')
+}
+
+thread_local! {
+    // 扫描循环里每个候选都要匹配一遍，补 NUL 用的缓冲区只在变长时重新分配
+    static MATCH_BUF: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Compiled(libc::regex_t);
+
+// 编译后的 regex_t 只读，POSIX 保证可在多线程中同时 regexec
+unsafe impl Send for Compiled {}
+unsafe impl Sync for Compiled {}
+
+impl Drop for Compiled {
+    fn drop(&mut self) {
+        unsafe { libc::regfree(&mut self.0) };
+    }
+}
+
+/// 整串匹配的 POSIX 扩展正则；相等与哈希只看原文
+#[derive(Clone)]
+pub struct Regex {
+    source: String,
+    compiled: Arc<Compiled>,
+}
+
+impl Regex {
+    pub fn new(source: &str) -> Result<Self, String> {
+        let anchored = CString::new(format!("^({})$", source))
+            .map_err(|_| "pattern contains NUL".to_string())?;
+        let mut compiled: libc::regex_t = unsafe { std::mem::zeroed() };
+        let ret = unsafe {
+            libc::regcomp(
+                &mut compiled,
+                anchored.as_ptr(),
+                libc::REG_EXTENDED | libc::REG_NOSUB,
+            )
+        };
+        if ret != 0 {
+            return Err(format!("invalid regex (error {})", ret));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            compiled: Arc::new(Compiled(compiled)),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        if text.as_bytes().contains(&0) {
+            return false;
+        }
+        MATCH_BUF.with(|buf| {
+            let mut buf = buf.borrow_mut();
+            buf.clear();
+            buf.extend_from_slice(text.as_bytes());
+            buf.push(0);
+            let ptr = buf.as_ptr() as *const libc::c_char;
+            unsafe { libc::regexec(&self.compiled.0, ptr, 0, std::ptr::null_mut(), 0) == 0 }
+        })
+    }
+}
+
+impl std::fmt::Debug for Regex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Regex({:?})", self.source)
+    }
+}
+
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Regex {}
+
+impl Hash for Regex {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regex_rejects_invalid_patterns() {
+        assert!(Regex::new("com.(foo").is_err());
+        assert!(Regex::new("a\0b").is_err());
+        assert!(Regex::new("com\\.foo(\\..*)?").is_ok());
+    }
+
+    #[test]
+    fn regex_matches_whole_string() {
+        let re = Regex::new("com\\.foo|org\\.bar").unwrap();
+        assert!(re.is_match("com.foo"));
+        assert!(re.is_match("org.bar"));
+        assert!(!re.is_match("com.foo.app"));
+        assert!(!re.is_match("xorg.bar"));
+        assert!(!re.is_match("com.foo\0"));
+        // 缓冲区复用：较长的文本之后再匹配较短的
+        assert!(!re.is_match("com.foo.very.long.package.name"));
+        assert!(re.is_match("com.foo"));
+    }
+
+    #[test]
+    fn glob_edge_cases() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("**", "com.foo"));
+        assert!(!glob_match("?", ""));
+        assert!(glob_match("com.?oo", "com.foo"));
+        assert!(!glob_match("com.?oo", "com.fooo"));
+        assert!(glob_match("com.*:push", "com.tencent.mm:push"));
+        assert!(!glob_match("com.*:push", "com.tencent.mm:pushd"));
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(glob_match("a*?", "ab"));
+        assert!(!glob_match("a*?", "a"));
+    }
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::process::Command;
+
+use crate::freezer::Freezer;
+
+/// 启动时按 API level 与实际探测结果选出的各项后端能力
+pub struct Platform {
+    pub sdk: Option<u32>,
+    pub am_compact: bool, // `am compact` 命令（Android 14+）
+    pub psi: bool,        // /proc/pressure/memory
+}
+
+impl Platform {
+    /// 探测系统能力，并据此收窄冻结器后端：
+    /// Android 11 的应用冻结走 cgroup v1 freezer，12 起改为 cgroup v2 的 cgroup.freeze，更早的版本没有应用冻结
+    pub fn detect(freezer: &mut Freezer) -> Self {
+        let sdk = read_sdk();
+        if let Some(sdk) = sdk {
+            freezer.select_for_sdk(sdk);
+        }
+        Self {
+            sdk,
+            am_compact: sdk.is_some_and(|s| s >= 34),
+            psi: fs::read_to_string("/proc/pressure/memory").is_ok(),
+        }
+    }
+
+    /// 能力矩阵，写入启动日志
+    pub fn describe(&self, freezer: &Freezer) -> String {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        format!(
+            "sdk={} freezer={} am_compact={} psi={}",
+            self.sdk
+                .map_or_else(|| "unknown".to_string(), |s| s.to_string()),
+            freezer.backend_name(),
+            yes_no(self.am_compact),
+            yes_no(self.psi)
+        )
+    }
+}
+
+fn read_sdk() -> Option<u32> {
+    let output = Command::new("getprop")
+        .arg("ro.build.version.sdk")
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
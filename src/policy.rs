@@ -0,0 +1,181 @@
+//! 按名字与配置做出的判定：白名单匹配、GMS 进程族识别、杀不掉时的升级处理
+//!
+//! 只依赖传入的名字和配置，不读 /proc，漏斗与控制命令共用。
+
+use fxhash::FxHashSet;
+
+use crate::config::{AppConfig, SurvivorAction, WhitelistRule};
+use crate::pattern::glob_match;
+
+const GMS_PROCESS_PREFIXES: &[&str] = &[
+    "com.google.android.gms",
+    "com.google.android.gsf",
+    "com.google.process.gapps",
+    "com.google.process.gservices",
+];
+const SURVIVOR_RETRIES: u32 = 2; // 普通击杀失败这么多次后开始升级处理
+pub const PER_USER_RANGE: u32 = 100_000; // 每个 Android 用户占用的 UID 区间
+
+/// UID 所属的 Android 用户（0 为机主，工作资料、分身多为 10 以上）
+pub fn android_user(uid: u32) -> u32 {
+    uid / PER_USER_RANGE
+}
+
+/// 检查进程是否在白名单中（支持完全匹配和前缀匹配）
+pub fn is_in_whitelist(cmdline: &str, uid: u32, whitelist: &FxHashSet<WhitelistRule>) -> bool {
+    whitelist_match(cmdline, uid, whitelist).is_some()
+}
+
+/// 命中的白名单规则
+pub fn whitelist_match<'a>(
+    cmdline: &str,
+    uid: u32,
+    whitelist: &'a FxHashSet<WhitelistRule>,
+) -> Option<&'a WhitelistRule> {
+    if let Some(rule) = whitelist.get(&WhitelistRule::Exact(cmdline.to_string())) {
+        return Some(rule);
+    }
+    whitelist
+        .iter()
+        .find(|rule| !matches!(rule, WhitelistRule::Exact(_)) && rule_matches(rule, cmdline, uid))
+}
+
+fn rule_matches(rule: &WhitelistRule, cmdline: &str, uid: u32) -> bool {
+    match rule {
+        WhitelistRule::Exact(name) => cmdline == name,
+        WhitelistRule::Prefix(prefix) => cmdline.starts_with(prefix.as_str()),
+        WhitelistRule::Glob(pattern) => glob_match(pattern, cmdline),
+        WhitelistRule::Regex(re) => re.is_match(cmdline),
+        WhitelistRule::Uid(rule_uid) => uid == *rule_uid,
+        WhitelistRule::User(user, inner) => {
+            android_user(uid) == *user && rule_matches(inner, cmdline, uid)
+        }
+    }
+}
+
+pub fn is_gms_process(name: &str) -> bool {
+    GMS_PROCESS_PREFIXES.iter().any(|p| {
+        name.strip_prefix(p)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(':'))
+    })
+}
+
+/// 截断名字的保守匹配：任何包含该片段的白名单条目都视为命中
+pub fn may_be_whitelisted<'a>(
+    partial: &str,
+    uid: u32,
+    whitelist: &'a FxHashSet<WhitelistRule>,
+) -> Option<&'a WhitelistRule> {
+    whitelist.iter().find(|rule| may_match(rule, partial, uid))
+}
+
+fn may_match(rule: &WhitelistRule, partial: &str, uid: u32) -> bool {
+    match rule {
+        WhitelistRule::Exact(name) => name.contains(partial),
+        WhitelistRule::Prefix(prefix) => {
+            // com.tencent.mm:* 对应的 comm 形如 "mm:push"
+            let tail = prefix.rsplit('.').next().unwrap_or(prefix);
+            prefix.contains(partial) || partial.starts_with(tail)
+        }
+        WhitelistRule::Glob(pattern) => pattern.contains(partial) || glob_match(pattern, partial),
+        WhitelistRule::Regex(re) => re.is_match(partial),
+        WhitelistRule::Uid(rule_uid) => uid == *rule_uid,
+        WhitelistRule::User(user, inner) => {
+            android_user(uid) == *user && may_match(inner, partial, uid)
+        }
+    }
+}
+
+/// 已存活 survived 次时应采取的升级处理；处理链走完后一直沿用最后一项
+pub fn survivor_action(config: &AppConfig, survived: u32) -> Option<SurvivorAction> {
+    let step = survived.checked_sub(SURVIVOR_RETRIES)? as usize;
+    config
+        .survivor_actions
+        .get(step)
+        .or(config.survivor_actions.last())
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+
+    fn rules(list: &[&str]) -> FxHashSet<WhitelistRule> {
+        list.iter()
+            .filter_map(|r| WhitelistRule::parse(r))
+            .collect()
+    }
+
+    #[test]
+    fn whitelist_matching() {
+        let whitelist = rules(&["com.exact", "com.prefix:*", "com.*.glob"]);
+        assert!(is_in_whitelist("com.exact", 10100, &whitelist));
+        assert!(!is_in_whitelist("com.exact:push", 10100, &whitelist));
+        assert!(is_in_whitelist("com.prefix:push", 10100, &whitelist));
+        assert!(is_in_whitelist("com.x.glob", 10100, &whitelist));
+        assert!(!is_in_whitelist("com.other:push", 10100, &whitelist));
+    }
+
+    #[test]
+    fn regex_rules_match_whole_name() {
+        let whitelist = rules(&["re:com\\.(tencent|alibaba)\\.[a-z]+(:push)?"]);
+        assert!(is_in_whitelist("com.tencent.mm", 10100, &whitelist));
+        assert!(is_in_whitelist("com.alibaba.app:push", 10100, &whitelist));
+        assert!(!is_in_whitelist("com.tencent.mm:tools", 10100, &whitelist));
+        assert!(!is_in_whitelist("xcom.tencent.mm", 10100, &whitelist));
+        assert!(rules(&["re:com.(foo"]).is_empty());
+    }
+
+    #[test]
+    fn uid_and_user_scoped_rules() {
+        let whitelist = rules(&["1010123", "user:10/com.work:*"]);
+        assert!(is_in_whitelist("com.any", 1010123, &whitelist));
+        assert!(!is_in_whitelist("com.any", 10123, &whitelist));
+        assert!(is_in_whitelist("com.work:push", 1010200, &whitelist));
+        assert!(!is_in_whitelist("com.work:push", 10200, &whitelist));
+        assert_eq!(android_user(1010200), 10);
+    }
+
+    #[test]
+    fn truncated_names_match_conservatively() {
+        let whitelist = rules(&["com.tencent.mm:*"]);
+        assert!(may_be_whitelisted("mm:push", 10100, &whitelist).is_some());
+        assert!(may_be_whitelisted("other", 10100, &whitelist).is_none());
+    }
+
+    #[test]
+    fn gms_process_family() {
+        assert!(is_gms_process("com.google.android.gms"));
+        assert!(is_gms_process("com.google.android.gms:persistent"));
+        assert!(!is_gms_process("com.google.android.gmsx"));
+        assert!(!is_gms_process("com.google.android.youtube"));
+    }
+
+    #[test]
+    fn survivor_actions_escalate_then_repeat_last() {
+        let config = parse_config("survivor_actions: thaw_kill, force_stop\n");
+        assert_eq!(survivor_action(&config, SURVIVOR_RETRIES - 1), None);
+        assert_eq!(
+            survivor_action(&config, SURVIVOR_RETRIES),
+            Some(SurvivorAction::ThawKill)
+        );
+        assert_eq!(
+            survivor_action(&config, SURVIVOR_RETRIES + 1),
+            Some(SurvivorAction::ForceStop)
+        );
+        assert_eq!(
+            survivor_action(&config, SURVIVOR_RETRIES + 5),
+            Some(SurvivorAction::ForceStop)
+        );
+    }
+
+    #[test]
+    fn survivors_are_ignored_unless_escalation_is_configured() {
+        let config = parse_config("");
+        assert_eq!(
+            survivor_action(&config, SURVIVOR_RETRIES),
+            Some(SurvivorAction::Ignore)
+        );
+    }
+}
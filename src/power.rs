@@ -0,0 +1,200 @@
+//! 电量与充电状态：每轮清理读一次 /sys/class/power_supply，按规则调整清理力度
+//!
+//! ```text
+//! on_charger: pause
+//! battery_below: 20 -> threshold 600, 10 -> threshold 400 interval 30
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::config::THRESHOLD_RANGE;
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// 接上电源时的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargerPolicy {
+    Normal, // 照常清理
+    Pause,  // 只扫描不清理，与 pause 相同
+}
+
+impl ChargerPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pause" => Some(Self::Pause),
+            "normal" | "off" => Some(Self::Normal),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Pause => "pause",
+        }
+    }
+}
+
+/// 电量低于 below% 时的覆盖项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryRule {
+    pub below: u8,
+    pub threshold: Option<i32>,
+    pub interval: Option<u64>,
+}
+
+impl BatteryRule {
+    /// "20 -> threshold 600 [interval 30]"
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let (below, overrides) = rule
+            .split_once("->")
+            .ok_or_else(|| format!("expected '<percent> -> ...': {}", rule.trim()))?;
+        let below = match below.trim().parse::<u8>() {
+            Ok(p) if (1..=100).contains(&p) => p,
+            _ => return Err(format!("invalid battery percent: {}", below.trim())),
+        };
+        let mut parsed = Self {
+            below,
+            threshold: None,
+            interval: None,
+        };
+        let tokens: Vec<&str> = overrides.split_whitespace().collect();
+        if tokens.is_empty() || !tokens.len().is_multiple_of(2) {
+            return Err(format!(
+                "expected '<setting> <value>' pairs: {}",
+                overrides.trim()
+            ));
+        }
+        for pair in tokens.chunks(2) {
+            match (pair[0], pair[1]) {
+                ("threshold", v) => match v.parse::<i32>() {
+                    Ok(t) if THRESHOLD_RANGE.contains(&t) => parsed.threshold = Some(t),
+                    _ => return Err(format!("invalid threshold: {}", v)),
+                },
+                ("interval", v) => match v.parse::<u64>() {
+                    Ok(secs) if secs > 0 => parsed.interval = Some(secs),
+                    _ => return Err(format!("invalid interval: {}", v)),
+                },
+                (key, _) => return Err(format!("unknown setting: {}", key)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for BatteryRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ->", self.below)?;
+        if let Some(t) = self.threshold {
+            write!(f, " threshold {}", t)?;
+        }
+        if let Some(secs) = self.interval {
+            write!(f, " interval {}", secs)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按 below 从低到高排好的规则中，电量落入的最低一档
+pub fn rule_for(rules: &[BatteryRule], capacity: u8) -> Option<BatteryRule> {
+    rules.iter().find(|r| capacity < r.below).copied()
+}
+
+/// 一次读取的电源状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerSupply {
+    pub capacity: Option<u8>, // 电池电量百分比，没有电池时为 None
+    pub charging: bool,       // 正在充电、已充满，或有外接电源在线
+}
+
+impl PowerSupply {
+    pub fn read() -> Self {
+        Self::read_from(Path::new(POWER_SUPPLY_DIR))
+    }
+
+    fn read_from(dir: &Path) -> Self {
+        let mut supply = Self::default();
+        let Ok(entries) = fs::read_dir(dir) else {
+            return supply;
+        };
+        let read = |path: &Path, name: &str| {
+            fs::read_to_string(path.join(name))
+                .ok()
+                .map(|s| s.trim().to_string())
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match read(&path, "type").as_deref() {
+                Some("Battery") => {
+                    if supply.capacity.is_none() {
+                        supply.capacity = read(&path, "capacity").and_then(|c| c.parse().ok());
+                    }
+                    if matches!(read(&path, "status").as_deref(), Some("Charging" | "Full")) {
+                        supply.charging = true;
+                    }
+                }
+                // Mains/USB/Wireless 等外接电源
+                Some(_) if read(&path, "online").as_deref() == Some("1") => {
+                    supply.charging = true;
+                }
+                _ => {}
+            }
+        }
+        supply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rules_and_picks_lowest_band() {
+        let rule = BatteryRule::parse("10 -> threshold 400 interval 30").unwrap();
+        assert_eq!(rule.threshold, Some(400));
+        assert_eq!(rule.interval, Some(30));
+        assert_eq!(rule.to_string(), "10 -> threshold 400 interval 30");
+        assert!(BatteryRule::parse("20 threshold 600").is_err());
+        assert!(BatteryRule::parse("20 -> threshold").is_err());
+        assert!(BatteryRule::parse("0 -> threshold 600").is_err());
+
+        let rules = [rule, BatteryRule::parse("20 -> threshold 600").unwrap()];
+        assert_eq!(rule_for(&rules, 5).unwrap().below, 10);
+        assert_eq!(rule_for(&rules, 15).unwrap().below, 20);
+        assert_eq!(rule_for(&rules, 20), None);
+    }
+
+    #[test]
+    fn reads_battery_and_external_supplies() {
+        let dir = std::env::temp_dir().join(format!("mc_power_{}", std::process::id()));
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.join(name);
+            fs::create_dir_all(&path).unwrap();
+            for (file, content) in files {
+                fs::write(path.join(file), format!("{}\n", content)).unwrap();
+            }
+        };
+        supply(
+            "battery",
+            &[
+                ("type", "Battery"),
+                ("capacity", "15"),
+                ("status", "Discharging"),
+            ],
+        );
+        supply("usb", &[("type", "USB"), ("online", "0")]);
+        assert_eq!(
+            PowerSupply::read_from(&dir),
+            PowerSupply {
+                capacity: Some(15),
+                charging: false
+            }
+        );
+
+        supply("ac", &[("type", "Mains"), ("online", "1")]);
+        assert!(PowerSupply::read_from(&dir).charging);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,248 @@
+use std::fs;
+use std::io;
+
+use crate::config::{AppConfig, CleanAction, FrozenAction, GmsPolicy, SurvivorAction};
+
+// linux/capability.h
+pub const CAP_DAC_OVERRIDE: u32 = 1;
+pub const CAP_DAC_READ_SEARCH: u32 = 2;
+pub const CAP_KILL: u32 = 5;
+pub const CAP_SYS_PTRACE: u32 = 19;
+pub const CAP_SYS_RESOURCE: u32 = 24;
+pub const CAP_SYS_NICE: u32 = 23;
+
+/// 按配置里会用到的每种处理方式推出 drop_privileges 后要保留的 capability
+///
+/// 只在启动时按初始配置算一次：丢掉的 capability 无法再取回，热加载新开启的
+/// 冻结、降级、whitelist_oom_adj、带 % 的日志路径或策略脚本需要重启才能生效，见 [`missing_caps`]
+pub fn required_caps(config: &AppConfig) -> Vec<u32> {
+    let mut keep = vec![
+        CAP_KILL,
+        CAP_SYS_PTRACE, // 读其他 UID 的 /proc/<pid>/smaps_rollup、fdinfo
+        CAP_DAC_READ_SEARCH,
+        CAP_SYS_NICE,
+    ];
+    let actions: Vec<CleanAction> = std::iter::once(config.action)
+        .chain(config.policies.values().filter_map(|p| p.action))
+        .collect();
+    // 策略脚本可以给任何候选换成冻结或降级
+    let script = config.policy_script.is_some();
+    let demote =
+        script || actions.contains(&CleanAction::Demote) || config.gms_policy == GmsPolicy::Demote;
+    let freeze = script || actions.contains(&CleanAction::Freeze);
+    let thaw = config.frozen_action == FrozenAction::ThawKill
+        || config.survivor_actions.contains(&SurvivorAction::ThawKill);
+    let log_template = config.log_path.as_deref().is_some_and(|p| p.contains('%'));
+    // 写 App 的 oom_score_adj、system 所有的 cgroup.freeze、在非 root 目录下新建日志都绕不开 DAC
+    if demote || freeze || thaw || config.whitelist_oom_adj.is_some() || log_template {
+        keep.push(CAP_DAC_OVERRIDE);
+    }
+    // 改写其他进程的 oom_score_adj（尤其是调低到其最小值以下）需要 CAP_SYS_RESOURCE
+    if demote || config.whitelist_oom_adj.is_some() {
+        keep.push(CAP_SYS_RESOURCE);
+    }
+    keep
+}
+
+/// 新配置需要、但启动时已经丢掉的 capability
+pub fn missing_caps(kept: &[u32], config: &AppConfig) -> Vec<u32> {
+    required_caps(config)
+        .into_iter()
+        .filter(|cap| !kept.contains(cap))
+        .collect()
+}
+
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+#[repr(C)]
+struct CapHeader {
+    version: u32,
+    pid: i32,
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct CapData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+pub fn cap_name(cap: u32) -> &'static str {
+    match cap {
+        CAP_DAC_OVERRIDE => "CAP_DAC_OVERRIDE",
+        CAP_DAC_READ_SEARCH => "CAP_DAC_READ_SEARCH",
+        CAP_KILL => "CAP_KILL",
+        CAP_SYS_PTRACE => "CAP_SYS_PTRACE",
+        CAP_SYS_NICE => "CAP_SYS_NICE",
+        CAP_SYS_RESOURCE => "CAP_SYS_RESOURCE",
+        _ => "CAP_?",
+    }
+}
+
+/// 只保留 keep 中的 capability：先收缩 bounding set（fork 出的 dumpsys 等也受限），再设置本线程能力集
+///
+/// 能力集按线程生效，必须在创建其他线程之前调用
+pub fn drop_capabilities(keep: &[u32]) -> io::Result<()> {
+    let last_cap = fs::read_to_string("/proc/sys/kernel/cap_last_cap")
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .unwrap_or(40);
+    for cap in (0..=last_cap).filter(|c| !keep.contains(c)) {
+        if unsafe { libc::prctl(libc::PR_CAPBSET_DROP, cap as libc::c_ulong, 0, 0, 0) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    // 旧内核没有 ambient 能力集，失败可以忽略
+    unsafe {
+        libc::prctl(
+            libc::PR_CAP_AMBIENT,
+            libc::PR_CAP_AMBIENT_CLEAR_ALL,
+            0,
+            0,
+            0,
+        );
+    }
+
+    let mut data = [CapData::default(); 2];
+    for &cap in keep {
+        let bit = 1 << (cap % 32);
+        data[(cap / 32) as usize].effective |= bit;
+        data[(cap / 32) as usize].permitted |= bit;
+    }
+    let mut header = CapHeader {
+        version: LINUX_CAPABILITY_VERSION_3,
+        pid: 0,
+    };
+    if unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// 经典 BPF 指令（linux/filter.h）
+const BPF_LD_W_ABS: u16 = 0x20;
+const BPF_JEQ_K: u16 = 0x15;
+const BPF_RET_K: u16 = 0x06;
+// struct seccomp_data 字段偏移
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "arm")]
+const AUDIT_ARCH: u32 = 0x4000_0028;
+#[cfg(target_arch = "x86")]
+const AUDIT_ARCH: u32 = 0x4000_0003;
+#[cfg(not(any(
+    target_arch = "aarch64",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "x86"
+)))]
+const AUDIT_ARCH: u32 = 0;
+
+/// 清理器永远用不到、一旦被滥用破坏力又很大的系统调用
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_chroot,
+    libc::SYS_reboot,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_kexec_load,
+    libc::SYS_swapon,
+    libc::SYS_swapoff,
+    libc::SYS_settimeofday,
+    libc::SYS_acct,
+    libc::SYS_ptrace,
+];
+
+fn bpf(code: u16, jt: u8, jf: u8, k: u32) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+/// 安装 seccomp 黑名单：上述系统调用返回 EPERM，其余放行；同样按线程生效
+pub fn install_seccomp() -> io::Result<()> {
+    if AUDIT_ARCH == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unsupported architecture",
+        ));
+    }
+
+    let n = DENIED_SYSCALLS.len();
+    let mut filter = vec![
+        bpf(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_ARCH),
+        // 非本机架构（compat 调用）直接放行：只拦截我们能正确识别调用号的情况
+        bpf(BPF_JEQ_K, 1, 0, AUDIT_ARCH),
+        bpf(BPF_RET_K, 0, 0, libc::SECCOMP_RET_ALLOW),
+        bpf(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_NR),
+    ];
+    for (i, &nr) in DENIED_SYSCALLS.iter().enumerate() {
+        // 命中时跳过剩余比较和 ALLOW，落到 ERRNO
+        filter.push(bpf(BPF_JEQ_K, (n - i) as u8, 0, nr as u32));
+    }
+    filter.push(bpf(BPF_RET_K, 0, 0, libc::SECCOMP_RET_ALLOW));
+    filter.push(bpf(
+        BPF_RET_K,
+        0,
+        0,
+        libc::SECCOMP_RET_ERRNO | libc::EPERM as u32,
+    ));
+
+    let prog = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_mut_ptr(),
+    };
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+            || libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER,
+                &prog as *const libc::sock_fprog,
+            ) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+
+    #[test]
+    fn caps_follow_configured_actions() {
+        let base = required_caps(&parse_config(""));
+        assert!(!base.contains(&CAP_DAC_OVERRIDE));
+        assert!(!base.contains(&CAP_SYS_RESOURCE));
+
+        let freeze = required_caps(&parse_config("action: freeze\n"));
+        assert!(freeze.contains(&CAP_DAC_OVERRIDE));
+        assert!(!freeze.contains(&CAP_SYS_RESOURCE));
+
+        let demote = required_caps(&parse_config("policy: com.foo oom=900 action=demote\n"));
+        assert!(demote.contains(&CAP_DAC_OVERRIDE));
+        assert!(demote.contains(&CAP_SYS_RESOURCE));
+
+        let thaw = required_caps(&parse_config("survivor_actions: thaw_kill, ignore\n"));
+        assert!(thaw.contains(&CAP_DAC_OVERRIDE));
+    }
+
+    #[test]
+    fn reload_reports_caps_dropped_at_startup() {
+        let kept = required_caps(&parse_config(""));
+        assert!(missing_caps(&kept, &parse_config("action: kill\n")).is_empty());
+        assert_eq!(
+            missing_caps(&kept, &parse_config("whitelist_oom_adj: -900\n")),
+            vec![CAP_DAC_OVERRIDE, CAP_SYS_RESOURCE]
+        );
+    }
+}
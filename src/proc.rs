@@ -0,0 +1,235 @@
+//! /proc 访问：守护进程读真实的 procfs，测试换成内存中的假进程
+//!
+//! 路径都相对于 /proc（形如 `1234/oom_score_adj`），与扫描时复用的 path_buf 写法一致。
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use std::path::Path;
+
+use nix::errno::Errno;
+use nix::fcntl::{openat, readlinkat, AtFlags, OFlag};
+use nix::sys::stat::{fstatat, Mode};
+
+/// 扫描漏斗用到的 /proc 读取
+pub trait ProcSource {
+    /// 当前所有进程的 pid
+    fn pids(&self) -> Vec<i32>;
+
+    /// 进程的 UID，即 /proc/<pid> 目录的属主
+    fn uid(&self, pid_s: &str) -> Result<u32, Errno>;
+
+    /// 把文件内容追加到 buf；hidepid 下不可读时返回 EACCES
+    fn read(&self, path: &str, buf: &mut Vec<u8>) -> Result<(), Errno>;
+
+    fn readlink(&self, path: &str) -> Option<OsString>;
+
+    /// 打开文件供之后反复 pread；不支持时返回 None，调用方每次按路径读取
+    fn open(&self, _path: &str) -> Option<OwnedFd> {
+        None
+    }
+
+    /// 真实 procfs 的目录 fd；ABI、命名空间等直接读 fd 的检查在没有时跳过
+    fn dir_fd(&self) -> Option<RawFd> {
+        None
+    }
+}
+
+/// 通过预先打开的 /proc 目录 fd 读取，路径解析不经过挂载点查找
+#[derive(Debug, Clone, Copy)]
+pub struct ProcFs {
+    fd: RawFd,
+}
+
+impl ProcFs {
+    pub fn new(fd: RawFd) -> Self {
+        Self { fd }
+    }
+
+    pub fn fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl ProcSource for ProcFs {
+    fn pids(&self) -> Vec<i32> {
+        let Ok(dir) = fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        dir.flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                let bytes = name.as_encoded_bytes();
+                // 只处理纯数字的 PID 目录
+                if bytes.is_empty() || !bytes.iter().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                std::str::from_utf8(bytes).ok()?.parse().ok()
+            })
+            .collect()
+    }
+
+    fn uid(&self, pid_s: &str) -> Result<u32, Errno> {
+        fstatat(Some(self.fd), Path::new(pid_s), AtFlags::empty()).map(|st| st.st_uid)
+    }
+
+    fn read(&self, path: &str, buf: &mut Vec<u8>) -> Result<(), Errno> {
+        let fd = openat(
+            Some(self.fd),
+            Path::new(path),
+            OFlag::O_RDONLY,
+            Mode::empty(),
+        )?;
+        let mut f = unsafe { File::from_raw_fd(fd) };
+        f.read_to_end(buf)
+            .map(|_| ())
+            .map_err(|e| e.raw_os_error().map_or(Errno::EIO, Errno::from_raw))
+    }
+
+    fn readlink(&self, path: &str) -> Option<OsString> {
+        readlinkat(Some(self.fd), Path::new(path)).ok()
+    }
+
+    fn open(&self, path: &str) -> Option<OwnedFd> {
+        // 长期持有，不能泄漏给 fork 出去的 dumpsys/am
+        let fd = openat(
+            Some(self.fd),
+            Path::new(path),
+            OFlag::O_RDONLY | OFlag::O_CLOEXEC,
+            Mode::empty(),
+        )
+        .ok()?;
+        Some(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    fn dir_fd(&self) -> Option<RawFd> {
+        Some(self.fd)
+    }
+}
+
+/// 内存中的假进程表，供测试构造各种进程组合
+#[cfg(test)]
+pub mod fake {
+    use std::collections::BTreeMap;
+    use std::ffi::OsString;
+
+    use fxhash::FxHashMap;
+    use nix::errno::Errno;
+
+    use super::ProcSource;
+
+    struct FakeProcess {
+        uid: u32,
+        files: FxHashMap<String, Vec<u8>>,
+        denied: bool, // hidepid：目录可见、内容不可读
+    }
+
+    #[derive(Default)]
+    pub struct FakeProc {
+        procs: BTreeMap<i32, FakeProcess>,
+    }
+
+    impl FakeProc {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// 添加一个进程，RSS 默认 100MB
+        pub fn spawn(&mut self, pid: i32, uid: u32, cmdline: &str, oom_adj: i32) -> &mut Self {
+            let mut files = FxHashMap::default();
+            files.insert("cmdline".to_string(), format!("{}\0", cmdline).into_bytes());
+            files.insert(
+                "oom_score_adj".to_string(),
+                format!("{}\n", oom_adj).into_bytes(),
+            );
+            files.insert(
+                "status".to_string(),
+                b"VmRSS:\t102400 kB\nVmSwap:\t0 kB\n".to_vec(),
+            );
+            self.procs.insert(
+                pid,
+                FakeProcess {
+                    uid,
+                    files,
+                    denied: false,
+                },
+            );
+            self.set_stat(pid, 1000)
+        }
+
+        pub fn set_file(&mut self, pid: i32, name: &str, content: &str) -> &mut Self {
+            if let Some(p) = self.procs.get_mut(&pid) {
+                p.files
+                    .insert(name.to_string(), content.as_bytes().to_vec());
+            }
+            self
+        }
+
+        pub fn set_rss_kb(&mut self, pid: i32, rss_kb: u64) -> &mut Self {
+            self.set_file(
+                pid,
+                "status",
+                &format!("VmRSS:\t{} kB\nVmSwap:\t0 kB\n", rss_kb),
+            )
+        }
+
+        pub fn set_oom_adj(&mut self, pid: i32, oom_adj: i32) -> &mut Self {
+            self.set_file(pid, "oom_score_adj", &format!("{}\n", oom_adj))
+        }
+
+        /// start_time 不同即视为 pid 被复用
+        pub fn set_stat(&mut self, pid: i32, start_time: u64) -> &mut Self {
+            // 状态字符之后的第 19 个字段是 starttime
+            let fields = (0..19)
+                .map(|i| {
+                    if i == 18 {
+                        start_time.to_string()
+                    } else {
+                        "0".to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.set_file(pid, "stat", &format!("{} (fake) S {}\n", pid, fields))
+        }
+
+        pub fn deny(&mut self, pid: i32) -> &mut Self {
+            if let Some(p) = self.procs.get_mut(&pid) {
+                p.denied = true;
+            }
+            self
+        }
+
+        pub fn exit(&mut self, pid: i32) -> &mut Self {
+            self.procs.remove(&pid);
+            self
+        }
+    }
+
+    impl ProcSource for FakeProc {
+        fn pids(&self) -> Vec<i32> {
+            self.procs.keys().copied().collect()
+        }
+
+        fn uid(&self, pid_s: &str) -> Result<u32, Errno> {
+            let pid: i32 = pid_s.parse().map_err(|_| Errno::ENOENT)?;
+            self.procs.get(&pid).map(|p| p.uid).ok_or(Errno::ENOENT)
+        }
+
+        fn read(&self, path: &str, buf: &mut Vec<u8>) -> Result<(), Errno> {
+            let (pid, name) = path.split_once('/').ok_or(Errno::ENOENT)?;
+            let pid: i32 = pid.parse().map_err(|_| Errno::ENOENT)?;
+            let p = self.procs.get(&pid).ok_or(Errno::ENOENT)?;
+            if p.denied {
+                return Err(Errno::EACCES);
+            }
+            buf.extend_from_slice(p.files.get(name).ok_or(Errno::ENOENT)?);
+            Ok(())
+        }
+
+        fn readlink(&self, _path: &str) -> Option<OsString> {
+            None
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! 进程事件：通过 netlink proc connector 接收内核的 fork/exec/exit 通知，由进程表增量维护
+//! pid 集合，每轮不必再列出整个 /proc；退出的进程也立即从跨周期状态中移除。内核未开启
+//! CONFIG_PROC_EVENTS 或没有权限时打开失败，照旧每轮扫描 /proc
+//!
+//! 事件丢失（接收缓冲溢出）时下一轮回退为完整扫描一次，重新作为起点。
+
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_EVENT_FORK: u32 = 0x0000_0001;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+const NLMSG_HDRLEN: usize = 16;
+const CN_MSG_LEN: usize = 20;
+// proc_event: what, cpu, timestamp_ns，之后是各事件的数据（exec/exit 以 pid、tgid 开头，
+// fork 为父进程的 pid、tgid 后接子进程的 pid、tgid）
+const EVENT_DATA_OFFSET: usize = NLMSG_HDRLEN + CN_MSG_LEN + 16;
+
+/// 进程级事件（线程的 fork/exit 不上报）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcEvent {
+    Fork(i32),
+    Exec(i32),
+    Exit(i32),
+}
+
+pub struct ProcEvents {
+    fd: OwnedFd,
+}
+
+impl ProcEvents {
+    /// 打开 netlink 套接字并订阅进程事件（需要 root）
+    pub fn open() -> io::Result<Self> {
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                libc::NETLINK_CONNECTOR,
+            )
+        };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = CN_IDX_PROC;
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // nlmsghdr + cn_msg + proc_cn_mcast_op
+        let mut msg = [0u8; NLMSG_HDRLEN + CN_MSG_LEN + 4];
+        let len = msg.len() as u32;
+        msg[0..4].copy_from_slice(&len.to_ne_bytes());
+        msg[4..6].copy_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        let cn = NLMSG_HDRLEN;
+        msg[cn..cn + 4].copy_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        msg[cn + 4..cn + 8].copy_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        msg[cn + 16..cn + 18].copy_from_slice(&4u16.to_ne_bytes());
+        msg[cn + CN_MSG_LEN..].copy_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+        let sent = unsafe {
+            libc::send(
+                fd.as_raw_fd(),
+                msg.as_ptr() as *const libc::c_void,
+                msg.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd })
+    }
+
+    /// 读出全部待处理事件追加到 events；期间接收缓冲溢出丢了事件时返回 false
+    pub fn read(&self, events: &mut Vec<ProcEvent>) -> bool {
+        let mut buf = [0u8; 4096];
+        let mut complete = true;
+        loop {
+            let n = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if n < 0 {
+                // 缓冲溢出丢了事件，继续读剩下的
+                if io::Error::last_os_error().raw_os_error() == Some(libc::ENOBUFS) {
+                    complete = false;
+                    continue;
+                }
+                return complete;
+            }
+            if n == 0 {
+                return complete;
+            }
+            parse(&buf[..n as usize], events);
+        }
+    }
+}
+
+fn parse(mut data: &[u8], events: &mut Vec<ProcEvent>) {
+    let u32_at =
+        |d: &[u8], off: usize| u32::from_ne_bytes([d[off], d[off + 1], d[off + 2], d[off + 3]]);
+    while data.len() >= NLMSG_HDRLEN {
+        let len = u32_at(data, 0) as usize;
+        if len < NLMSG_HDRLEN || len > data.len() {
+            return;
+        }
+        if len >= EVENT_DATA_OFFSET + 8 {
+            let what = u32_at(data, NLMSG_HDRLEN + CN_MSG_LEN);
+            let pid = u32_at(data, EVENT_DATA_OFFSET) as i32;
+            let tgid = u32_at(data, EVENT_DATA_OFFSET + 4) as i32;
+            let event = match what {
+                PROC_EVENT_EXEC if pid == tgid => Some(ProcEvent::Exec(pid)),
+                PROC_EVENT_EXIT if pid == tgid => Some(ProcEvent::Exit(pid)),
+                PROC_EVENT_FORK if len >= EVENT_DATA_OFFSET + 16 => {
+                    let child_pid = u32_at(data, EVENT_DATA_OFFSET + 8) as i32;
+                    let child_tgid = u32_at(data, EVENT_DATA_OFFSET + 12) as i32;
+                    (child_pid == child_tgid).then_some(ProcEvent::Fork(child_pid))
+                }
+                _ => None,
+            };
+            events.extend(event);
+        }
+        // 按 4 字节对齐到下一条消息
+        let next = (len + 3) & !3;
+        data = &data[next.min(data.len())..];
+    }
+}
+
+impl AsFd for ProcEvents {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proc::fake::FakeProc;
+    use crate::table::ProcessTable;
+
+    /// 按内核格式拼一条 nlmsghdr + cn_msg + proc_event；data 为事件数据中的各个 pid/tgid
+    fn message(what: u32, data: &[i32]) -> Vec<u8> {
+        let len = EVENT_DATA_OFFSET + data.len() * 4;
+        let mut msg = vec![0u8; len];
+        msg[0..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        msg[4..6].copy_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        let cn = NLMSG_HDRLEN;
+        msg[cn..cn + 4].copy_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        msg[cn + 4..cn + 8].copy_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        msg[cn + CN_MSG_LEN..cn + CN_MSG_LEN + 4].copy_from_slice(&what.to_ne_bytes());
+        for (i, v) in data.iter().enumerate() {
+            let off = EVENT_DATA_OFFSET + i * 4;
+            msg[off..off + 4].copy_from_slice(&v.to_ne_bytes());
+        }
+        msg
+    }
+
+    fn parse_all(messages: &[Vec<u8>]) -> Vec<ProcEvent> {
+        let mut events = Vec::new();
+        parse(&messages.concat(), &mut events);
+        events
+    }
+
+    #[test]
+    fn decodes_fork_exec_exit() {
+        let events = parse_all(&[
+            message(PROC_EVENT_FORK, &[1, 1, 200, 200]),
+            message(PROC_EVENT_EXEC, &[200, 200]),
+            message(PROC_EVENT_EXIT, &[200, 200, 0, 0]),
+        ]);
+        assert_eq!(
+            events,
+            vec![
+                ProcEvent::Fork(200),
+                ProcEvent::Exec(200),
+                ProcEvent::Exit(200)
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_thread_and_unknown_events() {
+        let events = parse_all(&[
+            message(PROC_EVENT_FORK, &[200, 200, 201, 200]), // 新线程
+            message(PROC_EVENT_EXIT, &[201, 200]),
+            message(0x0000_0004, &[200, 200]), // PROC_EVENT_UID
+            message(PROC_EVENT_EXEC, &[300, 300]),
+        ]);
+        assert_eq!(events, vec![ProcEvent::Exec(300)]);
+    }
+
+    #[test]
+    fn stops_at_truncated_or_overrun_messages() {
+        // 长度字段超出缓冲：前面完整的事件照常取出，之后的丢弃
+        let mut overrun = message(PROC_EVENT_EXIT, &[301, 301]);
+        overrun[0..4].copy_from_slice(&u32::MAX.to_ne_bytes());
+        let events = parse_all(&[
+            message(PROC_EVENT_EXEC, &[300, 300]),
+            overrun,
+            message(PROC_EVENT_EXEC, &[302, 302]),
+        ]);
+        assert_eq!(events, vec![ProcEvent::Exec(300)]);
+
+        // 缓冲在消息中间截断
+        let mut truncated = message(PROC_EVENT_EXEC, &[400, 400]);
+        truncated.truncate(EVENT_DATA_OFFSET + 2);
+        assert!(parse_all(&[truncated]).is_empty());
+
+        // 长度字段小于消息头，不能原地打转
+        let mut bogus = message(PROC_EVENT_EXEC, &[500, 500]);
+        bogus[0..4].copy_from_slice(&4u32.to_ne_bytes());
+        assert!(parse_all(&[bogus]).is_empty());
+
+        // 事件数据不完整：fork 缺子进程字段，exec 只有 pid
+        let short_fork = message(PROC_EVENT_FORK, &[1, 1]);
+        let short_exec = message(PROC_EVENT_EXEC, &[600]);
+        assert!(parse_all(&[short_fork, short_exec]).is_empty());
+        assert!(parse_all(&[vec![0u8; NLMSG_HDRLEN - 1]]).is_empty());
+    }
+
+    #[test]
+    fn skips_padding_between_messages() {
+        let mut first = message(PROC_EVENT_EXEC, &[700, 700]);
+        first.push(0xff); // 长度不按 4 字节对齐，后面补齐
+        let len = first.len() as u32;
+        first[0..4].copy_from_slice(&len.to_ne_bytes());
+        first.extend_from_slice(&[0; 3]);
+        let events = parse_all(&[first, message(PROC_EVENT_EXIT, &[701, 701])]);
+        assert_eq!(events, vec![ProcEvent::Exec(700), ProcEvent::Exit(701)]);
+    }
+
+    #[test]
+    fn parsed_events_drive_the_process_table() {
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(1, 10100, "com.a:push", 900)
+            .spawn(2, 10200, "com.b:push", 900);
+        let mut table = ProcessTable::default();
+        table.track_events();
+        let mut pids = table.pids(&procfs);
+        pids.sort_unstable();
+        assert_eq!(pids, vec![1, 2]);
+
+        for event in parse_all(&[
+            message(PROC_EVENT_FORK, &[1, 1, 3, 3]),
+            message(PROC_EVENT_EXIT, &[2, 2]),
+        ]) {
+            table.apply(event);
+        }
+        let mut pids = table.pids(&procfs);
+        pids.sort_unstable();
+        assert_eq!(pids, vec![1, 3]);
+
+        // 丢了事件（ENOBUFS）后回到完整扫描
+        table.events_lost();
+        let mut pids = table.pids(&procfs);
+        pids.sort_unstable();
+        assert_eq!(pids, vec![1, 2]);
+    }
+}
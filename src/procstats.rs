@@ -0,0 +1,97 @@
+use fxhash::FxHashMap;
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const PROCSTATS_TTL: Duration = Duration::from_secs(3600);
+
+/// 长期驻留缓存的"钉子户"指标：cached 时间占比 × 平均 PSS(MB)，来自 `dumpsys procstats`
+#[derive(Default)]
+pub struct CachedSquatters {
+    per_process: FxHashMap<String, f32>,
+    last_refresh: Option<Instant>,
+}
+
+impl CachedSquatters {
+    pub fn new() -> Self {
+        Self {
+            per_process: FxHashMap::default(),
+            last_refresh: None,
+        }
+    }
+
+    pub fn squat_score(&mut self, name: &str) -> f32 {
+        if self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= PROCSTATS_TTL)
+        {
+            self.refresh();
+        }
+        self.per_process.get(name).copied().unwrap_or(0.0)
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        let output = match Command::new("dumpsys")
+            .args(["procstats", "--hours", "24"])
+            .output()
+        {
+            Ok(o) => o,
+            Err(_) => return,
+        };
+        self.per_process = parse_procstats(&String::from_utf8_lossy(&output.stdout));
+    }
+}
+
+/// 解析形如以下的段落（后出现的 Summary 段会覆盖前面的同名条目）：
+///
+/// ```text
+///   * com.foo.app:push / u0a123 / v456:
+///          Cached: 83% (40MB-52MB-60MB/38MB-50MB-58MB/... over 12)
+/// ```
+fn parse_procstats(text: &str) -> FxHashMap<String, f32> {
+    let mut result = FxHashMap::default();
+    let mut current: Option<&str> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("* ") {
+            current = rest.split(" / ").next().filter(|_| rest.contains(" / "));
+            continue;
+        }
+        let (Some(name), Some(rest)) = (current, line.strip_prefix("Cached:")) else {
+            continue;
+        };
+        let rest = rest.trim();
+        let Some((pct, detail)) = rest.split_once('%') else {
+            continue;
+        };
+        let Ok(pct) = pct.trim().parse::<f32>() else {
+            continue;
+        };
+        // 括号内第一组 min-avg-max 取 avg
+        let avg_mb = detail
+            .trim_start_matches([' ', '('])
+            .split('/')
+            .next()
+            .and_then(|triple| triple.split('-').nth(1))
+            .and_then(parse_size_mb)
+            .unwrap_or(0.0);
+        result.insert(name.to_string(), pct / 100.0 * avg_mb);
+    }
+    result
+}
+
+fn parse_size_mb(s: &str) -> Option<f32> {
+    let s = s.trim();
+    let (num, factor) = if let Some(n) = s.strip_suffix("GB") {
+        (n, 1024.0)
+    } else if let Some(n) = s.strip_suffix("MB") {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix("KB") {
+        (n, 1.0 / 1024.0)
+    } else {
+        return None;
+    };
+    num.parse::<f32>().ok().map(|v| v * factor)
+}
@@ -0,0 +1,325 @@
+//! 控制 socket 协议，供第三方前端对接
+//!
+//! 每个连接发送一行请求、收到回复后关闭。请求格式：
+//!
+//! ```text
+//! [token=<secret> ]v<N> <command>    # 版本化请求，回复为一行 JSON
+//! [token=<secret> ]<command>         # 旧格式，回复为纯文本（首词 OK/ERR），不保证跨版本稳定
+//! ```
+//!
+//! 版本化回复：
+//!
+//! ```text
+//! {"protocol":1,"ok":true,"summary":"running","lines":["platform: ...", ...]}
+//! {"protocol":1,"ok":false,"error":"invalid_argument","message":"invalid interval: 0"}
+//! ```
+//!
+//! summary 为回复首行，lines 为其余各行。`v1 hello` 返回守护进程版本、支持的协议版本和命令列表，
+//! 前端应先握手，再按 min_protocol..=max_protocol 选择版本。error 取值见 [`ErrorCode`]，
+//! 同一协议版本内只增不改。
+//!
+//! `query <stats|config|table> [schema=N]` 的回复不带 summary/lines，而是把结构化数据放在 data 中：
+//!
+//! ```text
+//! {"protocol":1,"ok":true,"data":{"schema":1,"kills":12,...}}
+//! ```
+//!
+//! data 的格式见 [`crate::schema`]，版本范围同样在 hello 中给出（min_schema/max_schema）。
+
+use crate::{schema, update};
+
+/// 当前协议版本
+pub const PROTOCOL_VERSION: u32 = 1;
+/// 仍然支持的最低协议版本
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// 协议 v1 支持的命令
+pub const COMMANDS: &[&str] = &[
+    "hello",
+    "status",
+    "candidates",
+    "whitelist",
+    "clean",
+    "reload",
+    "stats",
+    "recent",
+    "pause",
+    "resume",
+    "set",
+    "blacklist-once",
+    "query",
+    "state",
+    "metrics",
+    "logs clear",
+    "history clear",
+];
+
+/// 错误码（JSON 中的 error 字段）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    UnknownCommand,
+    InvalidArgument,
+    Unauthorized, // token 错误或缺失
+    Forbidden,    // 对端 UID 不在允许列表
+    Unavailable,  // 功能未启用（如未配置日志）
+    Failed,       // 执行失败（写文件、设置定时器等）
+    UnsupportedProtocol,
+    UnsupportedSchema,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UnknownCommand => "unknown_command",
+            Self::InvalidArgument => "invalid_argument",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::Unavailable => "unavailable",
+            Self::Failed => "failed",
+            Self::UnsupportedProtocol => "unsupported_protocol",
+            Self::UnsupportedSchema => "unsupported_schema",
+        }
+    }
+}
+
+/// 命令执行失败
+#[derive(Debug, Clone)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// 命令的执行结果：Ok 为回复正文（不含 OK 前缀）
+pub type CommandResult = Result<String, CommandError>;
+
+/// 解析后的请求
+pub struct Request {
+    pub version: Option<u32>, // None 为旧的纯文本格式
+    pub command: Result<String, CommandError>,
+}
+
+impl Request {
+    /// 拆出 "v<N> " 版本前缀（token 已由调用方剥离）
+    pub fn parse(line: &str) -> Self {
+        let versioned = line
+            .strip_prefix('v')
+            .and_then(|rest| rest.split_once(' '))
+            .and_then(|(n, command)| Some((n.parse::<u32>().ok()?, command.trim())));
+        match versioned {
+            Some((version, _)) if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) => {
+                Self {
+                    version: Some(PROTOCOL_VERSION),
+                    command: Err(CommandError::new(
+                        ErrorCode::UnsupportedProtocol,
+                        format!(
+                            "protocol {} not supported, use {}..={}",
+                            version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+                        ),
+                    )),
+                }
+            }
+            Some((version, command)) => Self {
+                version: Some(version),
+                command: Ok(command.to_string()),
+            },
+            None => Self {
+                version: None,
+                command: Ok(line.to_string()),
+            },
+        }
+    }
+
+    /// 鉴权失败等在执行命令之前就确定的结果
+    pub fn rejected(version: Option<u32>, error: CommandError) -> Self {
+        Self {
+            version,
+            command: Err(error),
+        }
+    }
+}
+
+/// hello 命令的回复正文
+pub fn hello() -> String {
+    update::version_string()
+}
+
+/// 按请求使用的格式序列化结果
+pub fn format_reply(version: Option<u32>, result: &CommandResult, command: &str) -> String {
+    match version {
+        None => match result {
+            Ok(body) => format!("OK {}", body),
+            Err(e) => format!("ERR {}", e.message),
+        },
+        Some(version) => format_json(version, result, command),
+    }
+}
+
+fn format_json(version: u32, result: &CommandResult, command: &str) -> String {
+    let mut out = format!("{{\"protocol\":{}", version);
+    match result {
+        Ok(body) if command.starts_with("query ") => {
+            out.push_str(",\"ok\":true,\"data\":");
+            out.push_str(body);
+        }
+        Ok(body) => {
+            let mut lines = body.lines();
+            out.push_str(",\"ok\":true,\"summary\":");
+            push_json_str(&mut out, lines.next().unwrap_or(""));
+            out.push_str(",\"lines\":[");
+            for (i, line) in lines.enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_json_str(&mut out, line);
+            }
+            out.push(']');
+            if command == "hello" {
+                out.push_str(&format!(
+                    ",\"version\":\"{}\",\"git_hash\":\"{}\",\"min_protocol\":{},\"max_protocol\":{},\"commands\":[",
+                    update::VERSION,
+                    update::GIT_HASH,
+                    MIN_PROTOCOL_VERSION,
+                    PROTOCOL_VERSION
+                ));
+                for (i, c) in COMMANDS.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    push_json_str(&mut out, c);
+                }
+                out.push_str(&format!(
+                    "],\"min_schema\":{},\"max_schema\":{}",
+                    schema::MIN_SCHEMA_VERSION,
+                    schema::SCHEMA_VERSION
+                ));
+            }
+        }
+        Err(e) => {
+            out.push_str(&format!(
+                ",\"ok\":false,\"error\":\"{}\",\"message\":",
+                e.code.as_str()
+            ));
+            push_json_str(&mut out, &e.message);
+        }
+    }
+    out.push('}');
+    out
+}
+
+pub(crate) fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_version_prefix() {
+        let request = Request::parse("v1 status");
+        assert_eq!(request.version, Some(1));
+        assert_eq!(request.command.unwrap(), "status");
+
+        let legacy = Request::parse("status");
+        assert_eq!(legacy.version, None);
+        assert_eq!(legacy.command.unwrap(), "status");
+
+        // 不是 v<数字> 开头的按旧格式原样交给命令处理
+        assert_eq!(Request::parse("vx status").version, None);
+    }
+
+    #[test]
+    fn rejects_unsupported_versions_in_json() {
+        let request = Request::parse(&format!("v{} status", PROTOCOL_VERSION + 1));
+        assert_eq!(request.version, Some(PROTOCOL_VERSION));
+        let error = request.command.unwrap_err();
+        assert_eq!(error.code, ErrorCode::UnsupportedProtocol);
+
+        let reply = format_reply(request.version, &Err(error), "");
+        assert!(reply.starts_with(&format!(
+            "{{\"protocol\":{},\"ok\":false,\"error\":\"unsupported_protocol\"",
+            PROTOCOL_VERSION
+        )));
+        assert!(Request::parse("v0 status").command.is_err());
+    }
+
+    #[test]
+    fn formats_legacy_replies() {
+        assert_eq!(
+            format_reply(None, &Ok("paused".to_string()), "pause"),
+            "OK paused"
+        );
+        let error = CommandError::new(ErrorCode::InvalidArgument, "invalid interval: 0");
+        assert_eq!(
+            format_reply(None, &Err(error), "set"),
+            "ERR invalid interval: 0"
+        );
+    }
+
+    #[test]
+    fn formats_json_replies() {
+        let reply = format_reply(Some(1), &Ok("running\na: 1\nb: 2".to_string()), "status");
+        assert_eq!(
+            reply,
+            r#"{"protocol":1,"ok":true,"summary":"running","lines":["a: 1","b: 2"]}"#
+        );
+        let error = CommandError::new(ErrorCode::Forbidden, "uid 10123 not allowed");
+        assert_eq!(
+            format_reply(Some(1), &Err(error), ""),
+            r#"{"protocol":1,"ok":false,"error":"forbidden","message":"uid 10123 not allowed"}"#
+        );
+        // query 的正文已是 JSON，原样放进 data
+        assert_eq!(
+            format_reply(Some(1), &Ok(r#"{"schema":1}"#.to_string()), "query stats"),
+            r#"{"protocol":1,"ok":true,"data":{"schema":1}}"#
+        );
+    }
+
+    #[test]
+    fn hello_advertises_versions_and_commands() {
+        let reply = format_reply(Some(1), &Ok(hello()), "hello");
+        assert!(reply.contains(&format!(
+            "\"min_protocol\":{},\"max_protocol\":{}",
+            MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        )));
+        assert!(reply.contains("\"commands\":[\"hello\","));
+        assert!(reply.ends_with(&format!(
+            "\"min_schema\":{},\"max_schema\":{}}}",
+            schema::MIN_SCHEMA_VERSION,
+            schema::SCHEMA_VERSION
+        )));
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        let mut out = String::new();
+        push_json_str(&mut out, "a\"b\\c\td\u{1}é");
+        assert_eq!(out, r#""a\"b\\c\td\u0001é""#);
+
+        let reply = format_reply(Some(1), &Ok("x\ny\"z".to_string()), "status");
+        assert_eq!(
+            reply,
+            r#"{"protocol":1,"ok":true,"summary":"x","lines":["y\"z"]}"#
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! 内存压力（PSI）触发：向 /proc/pressure/memory 注册触发器，短时间内 some 停顿超过阈值时
+//! 内核让该 fd 变为可读（EPOLLPRI），主循环据此立即清理一轮，不必等下一个定时周期
+//!
+//! 内核没有 CONFIG_PSI 或注册失败时退回定时器：每个周期读一次 some avg10 判断。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::fs::OpenOptionsExt;
+
+pub const PSI_PATH: &str = "/proc/pressure/memory";
+const WINDOW_US: u64 = 2_000_000; // 触发器统计窗口；没有 CAP_SYS_RESOURCE 时内核只接受 2s 的整数倍
+pub const PSI_COOLDOWN_SECS: u64 = 10; // 压力持续时内核每个窗口都会通知，两轮清理至少相隔这么久
+
+/// 当前 some avg10（过去 10 秒内至少一个任务因内存停顿的时间占比，百分数）
+pub fn some_avg10() -> Option<f32> {
+    let content = fs::read_to_string(PSI_PATH).ok()?;
+    let line = content.lines().find(|l| l.starts_with("some "))?;
+    line.split_whitespace()
+        .find_map(|f| f.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+/// 已注册的 PSI 触发器；fd 关闭即注销
+pub struct PsiTrigger {
+    file: File,
+}
+
+impl PsiTrigger {
+    /// threshold 为每个窗口内 some 停顿的百分比
+    pub fn register(threshold: f32) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(PSI_PATH)?;
+        let stall_us = (WINDOW_US as f32 * threshold / 100.0) as u64;
+        file.write_all(format!("some {} {}\0", stall_us.max(1), WINDOW_US).as_bytes())?;
+        Ok(Self { file })
+    }
+}
+
+impl AsFd for PsiTrigger {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::process::Command;
+
+use nix::sys::signal::kill;
+use nix::unistd::Pid;
+
+use crate::errlog;
+
+pub const DEFAULT_ZRAM_DEVICE: &str = "zram0";
+pub const DEFAULT_TOP_APP_CPUSET: &str = "/top-app";
+
+/// 机型差异：zram 设备名、前台 cpuset 路径、会与我们重复杀进程的厂商清理服务
+#[derive(Debug, Clone)]
+pub struct Quirks {
+    pub profile: Option<&'static str>,
+    pub zram_device: String,
+    pub top_app_cpuset: String,
+    pub oem_killers: Vec<String>, // 这些进程在运行时让出清理，避免两边抢着杀
+}
+
+/// quirks: 段里显式写出的项，覆盖机型预设
+#[derive(Debug, Default)]
+pub struct QuirkOverrides {
+    profile: Option<String>,
+    zram_device: Option<String>,
+    top_app_cpuset: Option<String>,
+    oem_killers: Option<Vec<String>>,
+}
+
+impl QuirkOverrides {
+    /// 解析 quirks: 段中的一行，不是 quirk 项时返回 false
+    pub fn parse_line(&mut self, line: &str) -> bool {
+        let Some((key, value)) = line.split_once(':') else {
+            return false;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "profile" => self.profile = Some(value),
+            "zram_device" => self.zram_device = Some(value),
+            "top_app_cpuset" => self.top_app_cpuset = Some(value),
+            "oem_killers" => {
+                self.oem_killers = Some(if value == "none" {
+                    Vec::new()
+                } else {
+                    value
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// 选定机型预设（profile: auto 或未指定时按 ro.product.manufacturer 匹配），再叠加显式配置
+    pub fn resolve(self) -> Quirks {
+        let profile = match self.profile.as_deref() {
+            None | Some("auto") => detect_profile(),
+            Some("none") => None,
+            Some(name) => {
+                let found = PROFILES.iter().map(|p| p.0).find(|p| *p == name);
+                if found.is_none() {
+                    errlog::warn("config", &format!("Unknown quirks profile: {}", name));
+                }
+                found
+            }
+        };
+
+        let mut quirks = Quirks {
+            profile,
+            zram_device: DEFAULT_ZRAM_DEVICE.to_string(),
+            top_app_cpuset: DEFAULT_TOP_APP_CPUSET.to_string(),
+            oem_killers: Vec::new(),
+        };
+        if let Some((_, zram, killers)) = PROFILES.iter().find(|p| Some(p.0) == profile) {
+            if let Some(zram) = zram {
+                quirks.zram_device = zram.to_string();
+            }
+            quirks.oem_killers = killers.iter().map(|k| k.to_string()).collect();
+        }
+
+        if let Some(v) = self.zram_device {
+            quirks.zram_device = v;
+        }
+        if let Some(v) = self.top_app_cpuset {
+            quirks.top_app_cpuset = v;
+        }
+        if let Some(v) = self.oem_killers {
+            quirks.oem_killers = v;
+        }
+        quirks
+    }
+}
+
+/// 内置机型预设：名字、zram 设备名、厂商清理服务进程名
+const PROFILES: &[(&str, Option<&str>, &[&str])] = &[
+    ("samsung", Some("vnswap0"), &[]),
+    ("miui", None, &["com.miui.powerkeeper"]),
+    ("coloros", None, &["com.coloros.athena", "com.oplus.athena"]),
+];
+
+fn detect_profile() -> Option<&'static str> {
+    let output = Command::new("getprop")
+        .arg("ro.product.manufacturer")
+        .output()
+        .ok()?;
+    let manufacturer = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .to_ascii_lowercase();
+    match manufacturer.as_str() {
+        "samsung" => Some("samsung"),
+        "xiaomi" | "redmi" | "poco" => Some("miui"),
+        "oppo" | "oneplus" | "realme" => Some("coloros"),
+        _ => None,
+    }
+}
+
+impl Quirks {
+    /// 启动时找出正在运行的厂商清理服务
+    pub fn find_oem_killers(&self) -> Vec<(i32, String)> {
+        if self.oem_killers.is_empty() {
+            return Vec::new();
+        }
+        let Ok(dir) = fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        let mut found = Vec::new();
+        for entry in dir.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|s| s.parse::<i32>().ok())
+            else {
+                continue;
+            };
+            let Ok(cmdline) = fs::read(entry.path().join("cmdline")) else {
+                continue;
+            };
+            let name = String::from_utf8_lossy(cmdline.split(|&b| b == 0).next().unwrap_or(&[]))
+                .to_string();
+            if self.oem_killers.contains(&name) {
+                found.push((pid, name));
+            }
+        }
+        found
+    }
+
+    /// zram 已用内存（mm_stat 第 3 列 mem_used_total）
+    pub fn zram_used_kb(&self) -> Option<u64> {
+        let stat = fs::read_to_string(format!("/sys/block/{}/mm_stat", self.zram_device)).ok()?;
+        let bytes: u64 = stat.split_whitespace().nth(2)?.parse().ok()?;
+        Some(bytes / 1024)
+    }
+}
+
+pub fn any_alive(pids: &[(i32, String)]) -> bool {
+    pids.iter()
+        .any(|&(pid, _)| kill(Pid::from_raw(pid), None).is_ok())
+}
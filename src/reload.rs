@@ -0,0 +1,113 @@
+//! 配置热加载：监听配置文件，改动后换入新的 AppConfig，进程表、击杀历史等运行状态保留
+//!
+//! 白名单、阈值等每轮扫描时按当前配置重新判定，换入即对所有进程生效；
+//! socket、HTTP 接口、权限收缩等只在启动时初始化的项仍需重启，会在日志中注明。
+//! 启用 drop_privileges 时，新配置若用到启动时已丢掉的 capability（冻结、降级等），
+//! 同样列为需重启，相应处理在重启前会因 EPERM 失败。
+
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+
+use std::ffi::OsString;
+use std::io;
+use std::os::fd::{AsFd, BorrowedFd};
+use std::path::{Path, PathBuf};
+
+use crate::config::{describe_config, AppConfig};
+
+/// 这些项在启动时初始化，改动后需重启才生效
+pub const RESTART_KEYS: &[&str] = &[
+    "control_socket",
+    "control_token",
+    "control_uids",
+    "http_api",
+    "pid_file",
+    "trigger_file",
+    "broadcast",
+    "hook",
+    "hook_timeout_ms",
+    "drop_privileges",
+    "seccomp",
+    "idle_detect",
+    "watchdog_intervals",
+    "watchdog_reexec",
+    "log_path",
+    "log_backend",
+    "psi_threshold",
+    "proc_events",
+];
+
+/// 监听配置文件所在目录：编辑器大多先写临时文件再改名覆盖，直接监听文件会在改名后失效
+pub struct ConfigWatch {
+    inotify: Inotify,
+    name: OsString,
+}
+
+impl ConfigWatch {
+    pub fn watch(path: &str) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid path"));
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+        inotify.add_watch(
+            dir,
+            AddWatchFlags::IN_CLOSE_WRITE | AddWatchFlags::IN_MOVED_TO,
+        )?;
+        Ok(Self {
+            inotify,
+            name: name.to_os_string(),
+        })
+    }
+
+    /// 读出全部待处理事件，配置文件被写入或替换过则返回 true
+    pub fn changed(&self) -> bool {
+        let Ok(events) = self.inotify.read_events() else {
+            return false;
+        };
+        events
+            .iter()
+            .any(|e| e.name.as_deref() == Some(self.name.as_os_str()))
+    }
+}
+
+impl AsFd for ConfigWatch {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inotify.as_fd()
+    }
+}
+
+/// 新旧配置的差异：(已生效的改动, 需重启才生效的改动)，均为 "key: value" 形式
+pub fn diff(old: &AppConfig, new: &AppConfig) -> (Vec<String>, Vec<String>) {
+    let old_lines = describe_config(old);
+    let (mut applied, restart): (Vec<String>, Vec<String>) = describe_config(new)
+        .into_iter()
+        .filter(|line| !old_lines.contains(line))
+        .partition(|line| {
+            let key = line.split(':').next().unwrap_or("");
+            !RESTART_KEYS.contains(&key)
+        });
+    // describe_config 只给出白名单条数，增删的规则单独列出
+    let mut added: Vec<String> = new
+        .whitelist
+        .difference(&old.whitelist)
+        .map(|r| format!("+{}", r))
+        .collect();
+    let mut removed: Vec<String> = old
+        .whitelist
+        .difference(&new.whitelist)
+        .map(|r| format!("-{}", r))
+        .collect();
+    if !added.is_empty() || !removed.is_empty() {
+        added.sort();
+        removed.sort();
+        added.append(&mut removed);
+        applied.retain(|line| !line.starts_with("whitelist:"));
+        applied.push(format!("whitelist: {}", added.join(" ")));
+    }
+    (applied, restart)
+}
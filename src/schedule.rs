@@ -0,0 +1,159 @@
+//! 按时段覆盖清理策略：`schedule:` 段每行一个时段，按本地时间在每轮清理开始时判定
+//!
+//! ```text
+//! schedule:
+//! 01:00-06:00 threshold=500 interval=30
+//! 09:00-18:00 off
+//! ```
+//!
+//! 结束时刻早于开始时刻表示跨过午夜；多个时段重叠时先写的生效。
+
+use std::fmt;
+
+use crate::config::THRESHOLD_RANGE;
+
+use time::OffsetDateTime;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// 一个时段及其覆盖项；未写的项沿用常规配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleWindow {
+    start: u16, // 一天中的第几分钟
+    end: u16,   // 可以是 24:00（1440），显示时保留原写法
+    pub interval: Option<u64>,
+    pub threshold: Option<i32>,
+    pub enabled: bool, // false 时只扫描不清理，与 pause 相同
+}
+
+impl ScheduleWindow {
+    /// "HH:MM-HH:MM [threshold=N] [interval=N] [off|enabled=on|off]"
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut parts = line.split_whitespace();
+        let range = parts.next().unwrap_or("");
+        let (start, end) = range
+            .split_once('-')
+            .and_then(|(s, e)| Some((parse_time(s)? % MINUTES_PER_DAY, parse_time(e)?)))
+            .ok_or_else(|| format!("invalid time range: {}", range))?;
+        let mut window = Self {
+            start,
+            end,
+            interval: None,
+            threshold: None,
+            enabled: true,
+        };
+        for option in parts {
+            match option.split_once('=') {
+                None if option == "off" => window.enabled = false,
+                None if option == "on" => window.enabled = true,
+                Some(("threshold", v)) => match v.parse::<i32>() {
+                    Ok(t) if THRESHOLD_RANGE.contains(&t) => window.threshold = Some(t),
+                    _ => return Err(format!("invalid threshold: {}", v)),
+                },
+                Some(("interval", v)) => match v.parse::<u64>() {
+                    Ok(secs) if secs > 0 => window.interval = Some(secs),
+                    _ => return Err(format!("invalid interval: {}", v)),
+                },
+                Some(("enabled", v)) => match v {
+                    "on" | "true" | "1" => window.enabled = true,
+                    "off" | "false" | "0" => window.enabled = false,
+                    _ => return Err(format!("invalid enabled: {}", v)),
+                },
+                _ => return Err(format!("unknown option: {}", option)),
+            }
+        }
+        Ok(window)
+    }
+
+    /// minute 为一天中的第几分钟；结束时刻不含在内，起止相同表示全天
+    pub fn contains(&self, minute: u16) -> bool {
+        let end = self.end % MINUTES_PER_DAY;
+        if self.start == end {
+            true
+        } else if self.start < end {
+            (self.start..end).contains(&minute)
+        } else {
+            minute >= self.start || minute < end
+        }
+    }
+}
+
+impl fmt::Display for ScheduleWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hm = |m: u16| format!("{:02}:{:02}", m / 60, m % 60);
+        write!(f, "{}-{}", hm(self.start), hm(self.end))?;
+        if !self.enabled {
+            return write!(f, " off");
+        }
+        if let Some(t) = self.threshold {
+            write!(f, " threshold={}", t)?;
+        }
+        if let Some(secs) = self.interval {
+            write!(f, " interval={}", secs)?;
+        }
+        Ok(())
+    }
+}
+
+/// 当前本地时间所在的时段；未配置时不读时钟
+pub fn active(windows: &[ScheduleWindow]) -> Option<ScheduleWindow> {
+    if windows.is_empty() {
+        return None;
+    }
+    active_at(windows, local_minute())
+}
+
+pub fn active_at(windows: &[ScheduleWindow], minute: u16) -> Option<ScheduleWindow> {
+    windows.iter().find(|w| w.contains(minute)).copied()
+}
+
+fn local_minute() -> u16 {
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+    now.hour() as u16 * 60 + now.minute() as u16
+}
+
+/// "HH:MM"，24:00 表示一天结束
+fn parse_time(s: &str) -> Option<u16> {
+    let (h, m) = s.split_once(':')?;
+    let (h, m) = (h.parse::<u16>().ok()?, m.parse::<u16>().ok()?);
+    let minute = h.checked_mul(60)? + m;
+    (m < 60 && minute <= MINUTES_PER_DAY).then_some(minute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_renders_windows() {
+        let night = ScheduleWindow::parse("01:00-06:00 threshold=500 interval=30").unwrap();
+        assert_eq!(night.threshold, Some(500));
+        assert_eq!(night.interval, Some(30));
+        assert!(night.enabled);
+        assert_eq!(night.to_string(), "01:00-06:00 threshold=500 interval=30");
+
+        let work = ScheduleWindow::parse("09:00-18:00 off").unwrap();
+        assert!(!work.enabled);
+        assert_eq!(work.to_string(), "09:00-18:00 off");
+
+        assert!(ScheduleWindow::parse("9-18").is_err());
+        assert!(ScheduleWindow::parse("09:00-25:00").is_err());
+        assert!(ScheduleWindow::parse("09:00-18:00 threshold=2000").is_err());
+        assert!(ScheduleWindow::parse("09:00-18:00 colour=red").is_err());
+    }
+
+    #[test]
+    fn windows_may_wrap_midnight_and_first_match_wins() {
+        let windows = [
+            ScheduleWindow::parse("22:00-06:00 threshold=500").unwrap(),
+            ScheduleWindow::parse("00:00-24:00 off").unwrap(),
+        ];
+        assert_eq!(windows[1].to_string(), "00:00-24:00 off");
+        assert!(windows[0].contains(23 * 60));
+        assert!(windows[0].contains(60));
+        assert!(!windows[0].contains(6 * 60));
+        assert_eq!(active_at(&windows, 60).unwrap().threshold, Some(500));
+        assert!(!active_at(&windows, 12 * 60).unwrap().enabled);
+        assert_eq!(active_at(&windows[..1], 12 * 60), None);
+    }
+}
@@ -0,0 +1,271 @@
+//! 供设置 App 查询的数据结构及其 JSON 编码
+//!
+//! 与控制协议（[`crate::protocol`]）分开演进：协议版本管请求/回复的外壳，schema 版本管
+//! `query` 返回的 data 内容。每个快照都带 `"schema":N`，App 通过 hello 回复中的
+//! min_schema..=max_schema 选择版本，用 `query <what> schema=N` 请求。
+//! 同一 schema 版本内只增加字段，不删除、不改含义。
+
+use crate::config::AppConfig;
+use crate::engine::CleanerState;
+use crate::protocol::{push_json_str, CommandError, ErrorCode};
+use crate::session::SessionTotals;
+use crate::table::ProcessTable;
+
+/// 当前 schema 版本
+pub const SCHEMA_VERSION: u32 = 1;
+/// 仍然支持的最低 schema 版本
+pub const MIN_SCHEMA_VERSION: u32 = 1;
+
+/// query 支持的对象
+pub const QUERIES: &[&str] = &["stats", "config", "table"];
+
+/// 运行统计
+#[derive(Debug, Clone, Default)]
+pub struct StatsSnapshot {
+    pub uptime_secs: u64,
+    pub kills: u64,
+    pub freed_kb: u64,
+    pub last_freed_kb: u64,
+    pub paused: bool,
+    pub hidden_pids: usize, // hidepid 下读不到的进程数
+    pub cycles: u64,
+    pub scan_ms: u64, // 清理周期累计耗时
+}
+
+/// 对 App 有意义的配置项
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSnapshot {
+    pub interval: u64,
+    pub threshold: i32,
+    pub hard_threshold: Option<i32>,
+    pub threshold_metric: &'static str, // "adj" 或 "oom_score"
+    pub min_size_kb: u64,
+    pub min_heap_kb: u64,
+    pub min_pss_kb: u64,
+    pub kill_delay_ms: u64,
+    pub comfort_kb: u64,
+    pub target_available_kb: u64,
+    pub protect_widgets: bool,
+    pub battery_stats: bool,
+    pub whitelist: Vec<String>, // 规则原文，前缀规则带 ":*"
+}
+
+impl ConfigSnapshot {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut whitelist: Vec<String> = config.whitelist.iter().map(|r| r.to_string()).collect();
+        whitelist.sort();
+        Self {
+            interval: config.interval,
+            threshold: config.oom_threshold,
+            hard_threshold: config.hard_threshold,
+            threshold_metric: config.threshold_metric.as_str(),
+            min_size_kb: config.min_size_kb,
+            min_heap_kb: config.min_heap_kb,
+            min_pss_kb: config.min_pss_kb,
+            kill_delay_ms: config.kill_delay_ms,
+            comfort_kb: config.comfort_kb,
+            target_available_kb: config.target_available_kb,
+            protect_widgets: config.protect_widgets,
+            battery_stats: config.battery_stats,
+            whitelist,
+        }
+    }
+}
+
+/// 进程表中的一项
+#[derive(Debug, Clone)]
+pub struct TableEntry {
+    pub pid: i32,
+    pub idle_secs: u64,
+    pub background_secs: u64,
+    pub rss_kb: u64,
+}
+
+/// 进程表快照，按 pid 排序
+#[derive(Debug, Clone, Default)]
+pub struct TableSnapshot {
+    pub entries: Vec<TableEntry>,
+}
+
+impl TableSnapshot {
+    pub fn from_table(table: &ProcessTable, now: u64) -> Self {
+        let mut entries: Vec<TableEntry> = table
+            .iter()
+            .map(|(pid, node)| TableEntry {
+                pid,
+                idle_secs: now.saturating_sub(node.last_active),
+                background_secs: now.saturating_sub(node.background_since),
+                rss_kb: node.rss_kb,
+            })
+            .collect();
+        entries.sort_by_key(|e| e.pid);
+        Self { entries }
+    }
+}
+
+/// 按指定 schema 版本编码为一个 JSON 对象
+pub trait ToJson {
+    fn to_json(&self, schema: u32) -> String;
+}
+
+impl ToJson for StatsSnapshot {
+    fn to_json(&self, schema: u32) -> String {
+        let mut obj = JsonObject::new(schema);
+        obj.u64("uptime_secs", self.uptime_secs);
+        obj.u64("kills", self.kills);
+        obj.u64("freed_kb", self.freed_kb);
+        obj.u64("last_freed_kb", self.last_freed_kb);
+        obj.bool("paused", self.paused);
+        obj.u64("hidden_pids", self.hidden_pids as u64);
+        obj.u64("cycles", self.cycles);
+        obj.u64("scan_ms", self.scan_ms);
+        obj.finish()
+    }
+}
+
+impl ToJson for ConfigSnapshot {
+    fn to_json(&self, schema: u32) -> String {
+        let mut obj = JsonObject::new(schema);
+        obj.u64("interval", self.interval);
+        obj.raw("threshold", &self.threshold.to_string());
+        obj.raw(
+            "hard_threshold",
+            &self
+                .hard_threshold
+                .map_or_else(|| "null".to_string(), |t| t.to_string()),
+        );
+        obj.str("threshold_metric", self.threshold_metric);
+        obj.u64("min_size_kb", self.min_size_kb);
+        obj.u64("min_heap_kb", self.min_heap_kb);
+        obj.u64("min_pss_kb", self.min_pss_kb);
+        obj.u64("kill_delay_ms", self.kill_delay_ms);
+        obj.u64("comfort_kb", self.comfort_kb);
+        obj.u64("target_available_kb", self.target_available_kb);
+        obj.bool("protect_widgets", self.protect_widgets);
+        obj.bool("battery_stats", self.battery_stats);
+        let mut list = String::from("[");
+        for (i, rule) in self.whitelist.iter().enumerate() {
+            if i > 0 {
+                list.push(',');
+            }
+            push_json_str(&mut list, rule);
+        }
+        list.push(']');
+        obj.raw("whitelist", &list);
+        obj.finish()
+    }
+}
+
+impl ToJson for TableSnapshot {
+    fn to_json(&self, schema: u32) -> String {
+        let mut list = String::from("[");
+        for (i, e) in self.entries.iter().enumerate() {
+            if i > 0 {
+                list.push(',');
+            }
+            list.push_str(&format!(
+                "{{\"pid\":{},\"idle_secs\":{},\"background_secs\":{},\"rss_kb\":{}}}",
+                e.pid, e.idle_secs, e.background_secs, e.rss_kb
+            ));
+        }
+        list.push(']');
+        let mut obj = JsonObject::new(schema);
+        obj.raw("processes", &list);
+        obj.finish()
+    }
+}
+
+/// 解析 "<what> [schema=N]"，返回查询对象与 schema 版本
+pub fn parse_query(args: &str) -> Result<(&str, u32), CommandError> {
+    let invalid = |message: String| Err(CommandError::new(ErrorCode::InvalidArgument, message));
+    let mut parts = args.split_whitespace();
+    let what = parts.next().unwrap_or("");
+    if !QUERIES.contains(&what) {
+        return invalid(format!("unknown query: {}", what));
+    }
+    let schema = match parts.next() {
+        None => SCHEMA_VERSION,
+        Some(arg) => match arg.strip_prefix("schema=").map(str::parse::<u32>) {
+            Some(Ok(n)) => n,
+            _ => return invalid(format!("invalid argument: {}", arg)),
+        },
+    };
+    if !(MIN_SCHEMA_VERSION..=SCHEMA_VERSION).contains(&schema) {
+        return Err(CommandError::new(
+            ErrorCode::UnsupportedSchema,
+            format!(
+                "schema {} not supported, use {}..={}",
+                schema, MIN_SCHEMA_VERSION, SCHEMA_VERSION
+            ),
+        ));
+    }
+    Ok((what, schema))
+}
+
+/// 执行 `query <args>`，返回 data 部分的 JSON；控制 socket 与 C 接口共用
+pub fn query(
+    args: &str,
+    config: &AppConfig,
+    state: &mut CleanerState,
+    session: SessionTotals,
+    now: u64,
+) -> Result<String, CommandError> {
+    let (what, schema) = parse_query(args)?;
+    Ok(match what {
+        "stats" => StatsSnapshot {
+            uptime_secs: session.uptime_secs,
+            kills: session.kills,
+            freed_kb: session.freed_kb,
+            last_freed_kb: state.last_freed_kb,
+            paused: state.is_paused(now),
+            hidden_pids: state.hidden.len(),
+            cycles: state.stats.total.cycles,
+            scan_ms: state.stats.total.scan_ms,
+        }
+        .to_json(schema),
+        "config" => ConfigSnapshot::from_config(config).to_json(schema),
+        _ => TableSnapshot::from_table(&state.table, now).to_json(schema),
+    })
+}
+
+/// 逐个字段拼出 JSON 对象，首个字段固定为 schema
+struct JsonObject {
+    out: String,
+}
+
+impl JsonObject {
+    fn new(schema: u32) -> Self {
+        Self {
+            out: format!("{{\"schema\":{}", schema),
+        }
+    }
+
+    fn key(&mut self, key: &str) {
+        self.out.push(',');
+        push_json_str(&mut self.out, key);
+        self.out.push(':');
+    }
+
+    fn u64(&mut self, key: &str, value: u64) {
+        self.raw(key, &value.to_string());
+    }
+
+    fn bool(&mut self, key: &str, value: bool) {
+        self.raw(key, if value { "true" } else { "false" });
+    }
+
+    fn str(&mut self, key: &str, value: &str) {
+        self.key(key);
+        push_json_str(&mut self.out, value);
+    }
+
+    fn raw(&mut self, key: &str, json: &str) {
+        self.key(key);
+        self.out.push_str(json);
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push('}');
+        self.out
+    }
+}
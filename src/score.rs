@@ -0,0 +1,126 @@
+use fxhash::FxHashMap;
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::errlog;
+
+const BUCKET_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// 打分权重：score = Σ 权重 × 指标，达到阈值的候选按分数从高到低击杀
+#[derive(Debug, Clone)]
+pub struct ScoreWeights {
+    pub oom: f32,       // oom_score_adj
+    pub oom_score: f32, // 内核计算的 oom_score（含内存占用）
+    pub pss: f32,       // 每 MB PSS
+    pub idle: f32,      // 每分钟无 CPU 活动
+    pub bucket: f32,    // 待机分组 (10 active .. 50 never，按 /10 计)
+    pub history: f32,   // 本次运行内该包已被杀次数
+    pub battery: f32,   // 每 mAh 耗电（自上次充满）
+    pub procstats: f32, // 24h 内 cached 时间占比 × 平均 PSS(MB)
+    pub threshold: f32,
+}
+
+/// 单个候选进程的打分输入
+pub struct ScoreInputs {
+    pub oom: i32,
+    pub pss_kb: u64,
+    pub idle_secs: u64,
+    pub bucket: u32,
+    pub kills: u32,
+    pub drain_mah: f32,
+    pub squat: f32,
+    pub oom_score: i32,
+}
+
+impl ScoreWeights {
+    /// 解析 "oom=1, pss=0.5, idle=0.2" 格式，未出现的权重为 0
+    pub fn parse(value: &str, threshold: f32) -> Self {
+        let mut weights = Self {
+            oom: 0.0,
+            oom_score: 0.0,
+            pss: 0.0,
+            idle: 0.0,
+            bucket: 0.0,
+            history: 0.0,
+            battery: 0.0,
+            procstats: 0.0,
+            threshold,
+        };
+        for part in value.split(',') {
+            let Some((key, val)) = part.split_once('=') else {
+                continue;
+            };
+            let Ok(val) = val.trim().parse::<f32>() else {
+                errlog::warn("config", &format!("Invalid score weight: {}", part.trim()));
+                continue;
+            };
+            match key.trim() {
+                "oom" => weights.oom = val,
+                "oom_score" => weights.oom_score = val,
+                "pss" => weights.pss = val,
+                "idle" => weights.idle = val,
+                "bucket" => weights.bucket = val,
+                "history" => weights.history = val,
+                "battery" => weights.battery = val,
+                "procstats" => weights.procstats = val,
+                other => errlog::warn("config", &format!("Unknown score weight: {}", other)),
+            }
+        }
+        weights
+    }
+
+    pub fn score(&self, input: &ScoreInputs) -> f32 {
+        self.oom * input.oom as f32
+            + self.oom_score * input.oom_score as f32
+            + self.pss * (input.pss_kb / 1024) as f32
+            + self.idle * (input.idle_secs / 60) as f32
+            + self.bucket * (input.bucket / 10) as f32
+            + self.history * input.kills as f32
+            + self.battery * input.drain_mah
+            + self.procstats * input.squat
+    }
+}
+
+/// 待机分组缓存：一次 `am get-standby-bucket` 取全部包，按 TTL 刷新
+#[derive(Default)]
+pub struct StandbyBuckets {
+    buckets: FxHashMap<String, u32>,
+    last_refresh: Option<Instant>,
+}
+
+impl StandbyBuckets {
+    pub fn new() -> Self {
+        Self {
+            buckets: FxHashMap::default(),
+            last_refresh: None,
+        }
+    }
+
+    pub fn get(&mut self, package: &str) -> u32 {
+        if self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= BUCKET_CACHE_TTL)
+        {
+            self.refresh();
+        }
+        self.buckets.get(package).copied().unwrap_or(0)
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        let output = match Command::new("am").arg("get-standby-bucket").output() {
+            Ok(o) => o,
+            Err(_) => return,
+        };
+        self.buckets.clear();
+        // 每行形如 "com.foo.app: 10"
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let Some((pkg, bucket)) = line.rsplit_once(':') {
+                if let Ok(b) = bucket.trim().parse::<u32>() {
+                    self.buckets.insert(pkg.trim().to_string(), b);
+                }
+            }
+        }
+    }
+}
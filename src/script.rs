@@ -0,0 +1,211 @@
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use std::fs;
+
+use crate::dedup;
+
+pub const DEFAULT_MAX_OPERATIONS: u64 = 100_000;
+
+/// 内嵌的 Rhai 策略脚本：脚本定义 `fn decide(c)`，对内置策略选出的每个候选返回处理方式
+///
+/// c 的字段: pid name pkg（包名，package 是 Rhai 保留字） oom rss_kb swap_kb gpu_kb score background_secs
+/// 返回 "keep" / "kill" / "freeze" / "demote"，返回 () 或其他值时按内置策略处理
+///
+/// 脚本在进程内执行，每次调用受 max_operations 限制，死循环会被打断而不会卡住主循环
+pub struct PolicyScript {
+    path: String,
+    engine: Engine,
+    ast: AST,
+}
+
+/// 脚本给出的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptAction {
+    Keep, // 放过
+    Kill,
+    Freeze,
+    Demote,
+}
+
+/// 传给脚本的候选属性
+pub struct ScriptCandidate<'a> {
+    pub pid: i32,
+    pub name: &'a str,
+    pub package: &'a str,
+    pub oom: i32,
+    pub rss_kb: u64,
+    pub swap_kb: u64,
+    pub gpu_kb: u64,
+    pub score: f32,
+    pub background_secs: u64,
+}
+
+impl ScriptAction {
+    fn from_dynamic(value: &Dynamic) -> Option<Self> {
+        match value.clone().into_string().ok()?.as_str() {
+            "keep" => Some(Self::Keep),
+            "kill" => Some(Self::Kill),
+            "freeze" => Some(Self::Freeze),
+            "demote" => Some(Self::Demote),
+            _ => None,
+        }
+    }
+}
+
+impl PolicyScript {
+    /// 读取并编译脚本；读不到、语法错误或没有定义 decide 时返回错误说明
+    pub fn load(path: &str, max_operations: u64) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::compile(path, &source, max_operations)
+    }
+
+    fn compile(path: &str, source: &str, max_operations: u64) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(max_operations.max(1))
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(4096)
+            .set_max_array_size(1024)
+            .set_max_map_size(256);
+        // print/debug 默认写 stdout，守护进程下没人看
+        engine.on_print(|_| {});
+        engine.on_debug(|_, _, _| {});
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "decide" && f.params.len() == 1)
+        {
+            return Err(format!("{}: fn decide(c) is not defined", path));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn max_operations(&self) -> u64 {
+        self.engine.max_operations()
+    }
+
+    /// 对每个候选调用 decide，结果与 candidates 一一对应；None 表示按内置策略处理
+    ///
+    /// 某次调用出错（含超出操作数上限）时本轮其余候选都按内置策略处理
+    pub fn evaluate(&self, candidates: &[ScriptCandidate]) -> Vec<Option<ScriptAction>> {
+        let mut actions = vec![None; candidates.len()];
+        let mut scope = Scope::new();
+        for (action, c) in actions.iter_mut().zip(candidates) {
+            let mut map = Map::new();
+            map.insert("pid".into(), (c.pid as i64).into());
+            map.insert("name".into(), c.name.into());
+            map.insert("pkg".into(), c.package.into());
+            map.insert("oom".into(), (c.oom as i64).into());
+            map.insert("rss_kb".into(), (c.rss_kb as i64).into());
+            map.insert("swap_kb".into(), (c.swap_kb as i64).into());
+            map.insert("gpu_kb".into(), (c.gpu_kb as i64).into());
+            map.insert("score".into(), (c.score as f64).into());
+            map.insert("background_secs".into(), (c.background_secs as i64).into());
+            match self
+                .engine
+                .call_fn::<Dynamic>(&mut scope, &self.ast, "decide", (map,))
+            {
+                Ok(value) => *action = ScriptAction::from_dynamic(&value),
+                Err(e) => {
+                    dedup::warn(
+                        "script",
+                        &format!("Policy script {} failed on {}: {}", self.path, c.name, e),
+                    );
+                    break;
+                }
+            }
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(source: &str, max_operations: u64) -> PolicyScript {
+        PolicyScript::compile("test.rhai", source, max_operations).unwrap()
+    }
+
+    fn candidates(names: &[&'static str]) -> Vec<ScriptCandidate<'static>> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ScriptCandidate {
+                pid: 1000 + i as i32,
+                name,
+                package: name,
+                oom: 900,
+                rss_kb: 1024 * (i as u64 + 1),
+                swap_kb: 0,
+                gpu_kb: 0,
+                score: 1.0,
+                background_secs: 600,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn returns_action_per_candidate() {
+        let s = script(
+            r#"
+            fn decide(c) {
+                if c.pkg == "com.a" { return "keep"; }
+                if c.rss_kb > 2048 { return "freeze"; }
+                if c.name.starts_with("com.b") { "demote" }
+            }
+            "#,
+            DEFAULT_MAX_OPERATIONS,
+        );
+        assert_eq!(
+            s.evaluate(&candidates(&["com.a", "com.b", "com.c", "com.d"])),
+            vec![
+                Some(ScriptAction::Keep),
+                Some(ScriptAction::Demote),
+                Some(ScriptAction::Freeze),
+                Some(ScriptAction::Freeze),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_results_fall_back_to_builtin() {
+        let s = script(
+            "fn decide(c) { if c.pid == 1000 { \"kill\" } else { 42 } }",
+            1000,
+        );
+        assert_eq!(
+            s.evaluate(&candidates(&["com.a", "com.b"])),
+            vec![Some(ScriptAction::Kill), None]
+        );
+    }
+
+    #[test]
+    fn operation_limit_stops_runaway_script() {
+        let s = script("fn decide(c) { loop {} }", 1000);
+        assert_eq!(
+            s.evaluate(&candidates(&["com.a", "com.b"])),
+            vec![None, None]
+        );
+    }
+
+    #[test]
+    fn rejects_script_without_decide() {
+        let err = PolicyScript::compile("test.rhai", "let x = 1;", 1000)
+            .err()
+            .unwrap();
+        assert!(err.contains("decide"));
+        assert!(PolicyScript::compile("test.rhai", "fn decide(c) {", 1000).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+
+use crate::{boottime_secs, monotonic_secs};
+
+pub const SELF_REPORT_INTERVAL_SECS: u64 = 3600;
+
+/// 守护进程自身的资源占用，定期写入日志，便于核实开销、发现 fd 泄漏
+pub struct SelfUsage {
+    pub cpu_ms: u64,  // 自上次报告以来的 CPU 时间（用户态 + 内核态，含所有线程）
+    pub rss_kb: u64,  // 当前 VmRSS
+    pub fds: usize,   // 当前打开的 fd 数
+    pub wakeups: u64, // 自上次报告以来主循环被唤醒的次数
+    pub period_secs: u64,
+}
+
+pub struct SelfReporter {
+    last_at: u64, // boottime 秒
+    last_cpu_ms: u64,
+    wakeups: u64,
+}
+
+impl SelfReporter {
+    pub fn new(now: u64) -> Self {
+        Self {
+            last_at: now,
+            last_cpu_ms: cpu_time_ms(),
+            wakeups: 0,
+        }
+    }
+
+    pub fn on_wakeup(&mut self) {
+        self.wakeups += 1;
+    }
+
+    /// 距上次报告满一小时时返回本期统计并重新计数
+    pub fn due(&mut self, now: u64) -> Option<SelfUsage> {
+        if now.saturating_sub(self.last_at) < SELF_REPORT_INTERVAL_SECS {
+            return None;
+        }
+        let cpu_ms = cpu_time_ms();
+        let usage = SelfUsage {
+            cpu_ms: cpu_ms.saturating_sub(self.last_cpu_ms),
+            rss_kb: self_rss_kb(),
+            fds: open_fds(),
+            wakeups: self.wakeups,
+            period_secs: now - self.last_at,
+        };
+        self.last_at = now;
+        self.last_cpu_ms = cpu_ms;
+        self.wakeups = 0;
+        Some(usage)
+    }
+}
+
+fn cpu_time_ms() -> u64 {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return 0;
+    }
+    let ms = |tv: libc::timeval| tv.tv_sec as u64 * 1000 + tv.tv_usec as u64 / 1000;
+    ms(usage.ru_utime) + ms(usage.ru_stime)
+}
+
+fn self_rss_kb() -> u64 {
+    fs::read("/proc/self/status")
+        .ok()
+        .and_then(|s| crate::memory::status_field_kb(&s, "VmRSS"))
+        .unwrap_or(0)
+}
+
+fn open_fds() -> usize {
+    // read_dir 自身占用的 fd 也会被列出，减掉
+    fs::read_dir("/proc/self/fd")
+        .map(|d| d.count().saturating_sub(1))
+        .unwrap_or(0)
+}
+
+/// 对比两次定时器事件之间 boottime 与 monotonic 的增量，差值即设备休眠的时长
+///
+/// 定时器用的是非唤醒的 CLOCK_BOOTTIME：休眠期间到期的周期不会执行，醒来后只补一次
+pub struct SleepAudit {
+    last_boot: u64,
+    last_mono: u64,
+}
+
+impl SleepAudit {
+    pub fn new() -> Self {
+        Self {
+            last_boot: boottime_secs(),
+            last_mono: monotonic_secs(),
+        }
+    }
+
+    /// 返回自上次调用以来休眠的秒数
+    pub fn tick(&mut self) -> u64 {
+        let (boot, mono) = (boottime_secs(), monotonic_secs());
+        let slept = boot
+            .saturating_sub(self.last_boot)
+            .saturating_sub(mono.saturating_sub(self.last_mono));
+        self.last_boot = boot;
+        self.last_mono = mono;
+        slept
+    }
+}
+
+impl Default for SleepAudit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 本次运行的累计统计；放在全局原子量里，信号退出和 panic hook 都能读到
+pub struct SessionStats {
+    kills: AtomicU64,
+    freed_kb: AtomicU64,
+    started_at: AtomicU64, // boottime 秒
+}
+
+pub static SESSION: SessionStats = SessionStats {
+    kills: AtomicU64::new(0),
+    freed_kb: AtomicU64::new(0),
+    started_at: AtomicU64::new(0),
+};
+
+impl SessionStats {
+    pub fn start(&self, now: u64) {
+        self.started_at.store(now, Ordering::Relaxed);
+    }
+
+    pub fn record_kill(&self, freed_kb: u64) {
+        self.kills.fetch_add(1, Ordering::Relaxed);
+        self.freed_kb.fetch_add(freed_kb, Ordering::Relaxed);
+    }
+
+    pub fn kills(&self) -> u64 {
+        self.kills.load(Ordering::Relaxed)
+    }
+
+    pub fn freed_kb(&self) -> u64 {
+        self.freed_kb.load(Ordering::Relaxed)
+    }
+
+    pub fn uptime_secs(&self, now: u64) -> u64 {
+        now.saturating_sub(self.started_at.load(Ordering::Relaxed))
+    }
+
+    pub fn totals(&self, now: u64) -> SessionTotals {
+        SessionTotals {
+            kills: self.kills(),
+            freed_kb: self.freed_kb(),
+            uptime_secs: self.uptime_secs(now),
+        }
+    }
+}
+
+/// 某次运行的累计统计快照；守护进程取自 SESSION，C 接口按句柄各自统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionTotals {
+    pub kills: u64,
+    pub freed_kb: u64,
+    pub uptime_secs: u64,
+}
@@ -0,0 +1,97 @@
+//! 清理效果统计：周期数、扫描耗时、各包击杀次数与按击杀前 RSS 估算的回收量
+//!
+//! 累计值随 status/stats 命令查询；另有一份按报告周期清零的本期统计，定期写成日志汇总。
+
+use fxhash::FxHashMap;
+
+use crate::whitelist::unix_secs;
+
+/// 一段时间内的清理统计
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub cycles: u64,
+    pub scan_ms: u64, // 清理周期的总耗时（扫描、筛选与击杀）
+    pub kills: u64,
+    pub freed_kb: u64,                           // 按击杀前 RSS 估算
+    pub packages: FxHashMap<String, (u32, u64)>, // 包名 -> (击杀次数, 回收 KB)
+}
+
+impl Stats {
+    fn record_cycle(&mut self, elapsed_ms: u64) {
+        self.cycles += 1;
+        self.scan_ms += elapsed_ms;
+    }
+
+    fn record_kill(&mut self, package: &str, rss_kb: u64) {
+        self.kills += 1;
+        self.freed_kb += rss_kb;
+        let entry = self.packages.entry(package.to_string()).or_default();
+        entry.0 += 1;
+        entry.1 += rss_kb;
+    }
+
+    pub fn avg_scan_ms(&self) -> u64 {
+        self.scan_ms / self.cycles.max(1)
+    }
+
+    /// 击杀次数最多的 n 个包：(包名, 次数, 回收 KB)
+    pub fn top(&self, n: usize) -> Vec<(&str, u32, u64)> {
+        let mut top: Vec<(&str, u32, u64)> = self
+            .packages
+            .iter()
+            .map(|(p, &(kills, kb))| (p.as_str(), kills, kb))
+            .collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top.truncate(n);
+        top
+    }
+}
+
+/// 累计统计与本期统计；report_secs 为 0 时不做定期汇总
+pub struct StatsTracker {
+    pub total: Stats,
+    period: Stats,
+    period_start: u64, // boottime 秒
+    report_secs: u64,
+    pub last_scan_ms: u64, // 最近一轮清理的耗时
+    pub last_kill_at: u64, // 最近一次击杀的 Unix 秒，0 表示还没有
+}
+
+impl StatsTracker {
+    pub fn new(report_secs: u64, now: u64) -> Self {
+        Self {
+            total: Stats::default(),
+            period: Stats::default(),
+            period_start: now,
+            report_secs,
+            last_scan_ms: 0,
+            last_kill_at: 0,
+        }
+    }
+
+    pub fn set_report_secs(&mut self, report_secs: u64) {
+        self.report_secs = report_secs;
+    }
+
+    pub fn record_cycle(&mut self, elapsed_ms: u64) {
+        self.total.record_cycle(elapsed_ms);
+        self.period.record_cycle(elapsed_ms);
+        self.last_scan_ms = elapsed_ms;
+    }
+
+    pub fn record_kill(&mut self, package: &str, rss_kb: u64) {
+        self.total.record_kill(package, rss_kb);
+        self.period.record_kill(package, rss_kb);
+        self.last_kill_at = unix_secs();
+    }
+
+    /// 本期满一个报告周期时返回本期统计及时长，并开始新的一期
+    pub fn due(&mut self, now: u64) -> Option<(Stats, u64)> {
+        let elapsed = now.saturating_sub(self.period_start);
+        if self.report_secs == 0 || elapsed < self.report_secs {
+            return None;
+        }
+        self.period_start = now;
+        Some((std::mem::take(&mut self.period), elapsed))
+    }
+}
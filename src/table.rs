@@ -0,0 +1,572 @@
+use fxhash::{FxHashMap, FxHashSet};
+
+use nix::sys::uio::pread;
+
+use std::fs;
+use std::os::unix::io::OwnedFd;
+
+use crate::policy::android_user;
+use crate::proc::ProcSource;
+use crate::procevents::ProcEvent;
+
+pub const CLK_TCK: u64 = 100; // /proc/<pid>/stat 时间单位 (USER_HZ)
+const STATE_HEADER: &str = "# mem_cleaner table 1";
+const STATE_SAVE_INTERVAL_SECS: u64 = 600;
+
+/// /proc/<pid>/stat 中用到的字段
+#[derive(Debug, Clone, Copy)]
+pub struct ProcStat {
+    pub cpu_ticks: u64,  // utime + stime
+    pub start_time: u64, // 开机后的启动时刻（clock ticks），用于识别 PID 复用
+    pub state: u8,       // R/S/D/T/Z...
+}
+
+impl ProcStat {
+    /// 启动时刻（boottime 秒）
+    pub fn started_at(&self) -> u64 {
+        self.start_time / CLK_TCK
+    }
+
+    /// 处于不可中断睡眠（通常卡在 I/O 上）
+    pub fn is_uninterruptible(&self) -> bool {
+        self.state == b'D'
+    }
+
+    pub fn parse(content: &[u8]) -> Option<Self> {
+        // comm 可能含空格和括号，从最后一个 ')' 之后开始按空格切分
+        let close = content.iter().rposition(|&b| b == b')')?;
+        let rest = std::str::from_utf8(&content[close + 1..]).ok()?;
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        // fields[0] 对应 stat 第 3 个字段 (state)
+        let state = *fields.first()?.as_bytes().first()?;
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        let start_time: u64 = fields.get(19)?.parse().ok()?;
+        Some(Self {
+            cpu_ticks: utime + stime,
+            start_time,
+            state,
+        })
+    }
+}
+
+/// 跨周期保留的单进程状态
+pub struct ProcessNode {
+    pub start_time: u64,
+    pub cpu_ticks: u64,
+    pub last_active: u64, // 最近一次观察到 CPU 时间增长的时刻（boottime 秒）
+    pub background_since: u64, // 连续处于后台候选状态的起点
+    pub rss_kb: u64,      // 最近一次扫描读到的 VmRSS
+    pub uid: u32,
+    pub user: u32,              // Android 用户（uid / 100000），按用户区分策略时使用
+    pub oom: i32,               // 最近一次扫描读到的 oom_score_adj
+    pub name: String,           // 进程名，首次采样时记下（exec 后节点会作废）
+    pub package: String,        // 进程名中 ':' 之前的包名
+    pub process_suffix: String, // ':' 之后的进程后缀，主进程为空
+    pub state: u8,              // /proc/<pid>/stat 的状态字符
+    term_cycle: Option<u64>,    // 发出 SIGTERM 的那一轮，等待它自行退出
+    demoted_to: Option<i32>,    // demote 模式写入的 oom_score_adj
+    oom_fd: Option<OwnedFd>,    // 保持打开的 oom_score_adj，节点作废时随之关闭
+    seen_cycle: u64,
+}
+
+impl ProcessNode {
+    fn new(stat: &ProcStat, now: u64, cycle: u64) -> Self {
+        // 首轮扫描之前没有任何观察，只能用进程启动时间近似
+        let background_since = if cycle <= 1 { stat.started_at() } else { now };
+        Self {
+            start_time: stat.start_time,
+            cpu_ticks: stat.cpu_ticks,
+            last_active: now,
+            background_since,
+            rss_kb: 0,
+            uid: 0,
+            user: 0,
+            oom: 0,
+            name: String::new(),
+            package: String::new(),
+            process_suffix: String::new(),
+            state: stat.state,
+            term_cycle: None,
+            demoted_to: None,
+            oom_fd: None,
+            seen_cycle: cycle,
+        }
+    }
+
+    fn set_name(&mut self, name: &str) {
+        let (package, suffix) = name.split_once(':').unwrap_or((name, ""));
+        self.name = name.to_string();
+        self.package = package.to_string();
+        self.process_suffix = suffix.to_string();
+    }
+}
+
+/// 进程表：按 pid 记录候选进程的历史，用于计算空闲时长、后台时长等跨周期指标
+///
+/// 某轮未被观察到（进程退出或回到前台）的节点会被清除，再次出现时重新计时
+#[derive(Default)]
+pub struct ProcessTable {
+    nodes: FxHashMap<i32, ProcessNode>,
+    cycle: u64,
+    last_save: u64,         // boottime 秒
+    live: Option<LivePids>, // 订阅了进程事件时由事件维护的 pid 集合
+}
+
+#[derive(Default)]
+struct LivePids {
+    pids: FxHashSet<i32>,
+    synced: bool, // 为 false 时下一轮先完整扫描 /proc 作为起点
+}
+
+impl ProcessTable {
+    /// 改由进程事件维护 pid 集合，下一轮完整扫描一次作为起点
+    pub fn track_events(&mut self) {
+        self.live = Some(LivePids::default());
+    }
+
+    /// 清空全部节点；事件维护的 pid 集合保留订阅，下一轮重新完整扫描
+    pub fn reset(&mut self) {
+        let live = self.live.is_some().then(LivePids::default);
+        *self = Self {
+            live,
+            ..Self::default()
+        };
+    }
+
+    /// fork 加入 pid 集合；exec 与 exit 作废节点，exit 还从集合中移出
+    pub fn apply(&mut self, event: ProcEvent) {
+        match event {
+            ProcEvent::Fork(pid) => {
+                if let Some(live) = &mut self.live {
+                    live.pids.insert(pid);
+                }
+            }
+            ProcEvent::Exec(pid) => {
+                self.nodes.remove(&pid);
+            }
+            ProcEvent::Exit(pid) => {
+                self.nodes.remove(&pid);
+                if let Some(live) = &mut self.live {
+                    live.pids.remove(&pid);
+                }
+            }
+        }
+    }
+
+    /// 事件丢失（接收缓冲溢出）：集合不再可信，下一轮重新完整扫描
+    pub fn events_lost(&mut self) {
+        if let Some(live) = &mut self.live {
+            live.synced = false;
+        }
+    }
+
+    /// 本轮要检查的进程：事件维护的集合可用时直接取，否则列出 /proc
+    pub fn pids(&mut self, procfs: &dyn ProcSource) -> Vec<i32> {
+        match &mut self.live {
+            Some(live) if live.synced => live.pids.iter().copied().collect(),
+            Some(live) => {
+                let pids = procfs.pids();
+                live.pids = pids.iter().copied().collect();
+                live.synced = true;
+                pids
+            }
+            None => procfs.pids(),
+        }
+    }
+
+    /// 开始新一轮扫描
+    pub fn begin_cycle(&mut self) {
+        self.cycle += 1;
+    }
+
+    /// 更新进程采样，返回其节点；PID 被复用时重置
+    pub fn observe(&mut self, pid: i32, stat: &ProcStat, now: u64) -> &ProcessNode {
+        let cycle = self.cycle;
+        let node = self
+            .nodes
+            .entry(pid)
+            .or_insert_with(|| ProcessNode::new(stat, now, cycle));
+
+        if node.start_time != stat.start_time {
+            *node = ProcessNode::new(stat, now, cycle);
+        } else if stat.cpu_ticks != node.cpu_ticks {
+            node.cpu_ticks = stat.cpu_ticks;
+            node.last_active = now;
+        }
+        node.state = stat.state;
+        node.seen_cycle = cycle;
+        node
+    }
+
+    pub fn get(&self, pid: i32) -> Option<&ProcessNode> {
+        self.nodes.get(&pid)
+    }
+
+    /// 记下本轮读到的身份与内存占用
+    pub fn set_sample(&mut self, pid: i32, uid: u32, oom: i32, name: &str, rss_kb: u64) {
+        if let Some(node) = self.nodes.get_mut(&pid) {
+            node.uid = uid;
+            node.user = android_user(uid);
+            node.oom = oom;
+            if node.name.is_empty() {
+                node.set_name(name);
+            }
+            node.rss_kb = rss_kb;
+        }
+    }
+
+    /// 记下已向该进程发出 SIGTERM
+    pub fn mark_terminated(&mut self, pid: i32) {
+        let cycle = self.cycle;
+        if let Some(node) = self.nodes.get_mut(&pid) {
+            node.term_cycle = Some(cycle);
+        }
+    }
+
+    /// 记下已把该进程的 oom_score_adj 调为 adj
+    pub fn mark_demoted(&mut self, pid: i32, adj: i32) {
+        if let Some(node) = self.nodes.get_mut(&pid) {
+            node.demoted_to = Some(adj);
+            node.oom = adj;
+        }
+    }
+
+    /// 降级过且 oom_score_adj 仍是写入的值；被系统改回后需要重写
+    pub fn is_demoted(&self, pid: i32) -> bool {
+        self.nodes
+            .get(&pid)
+            .is_some_and(|n| n.demoted_to == Some(n.oom))
+    }
+
+    /// 经保持打开的 fd 读取 oom_score_adj，追加到 buf
+    ///
+    /// 没有 fd 时返回 false；进程已退出时 pread 报 ESRCH，关掉失效的 fd 同样返回 false，
+    /// 由调用方退回按路径读取（PID 被复用时读到的是新进程，随后 observe 会重置节点）
+    pub fn read_oom(&mut self, pid: i32, buf: &mut Vec<u8>) -> bool {
+        let Some(node) = self.nodes.get_mut(&pid) else {
+            return false;
+        };
+        let Some(fd) = &node.oom_fd else {
+            return false;
+        };
+        let mut chunk = [0u8; 16];
+        match pread(fd, &mut chunk, 0) {
+            Ok(n) if n > 0 => {
+                buf.extend_from_slice(&chunk[..n]);
+                true
+            }
+            _ => {
+                node.oom_fd = None;
+                false
+            }
+        }
+    }
+
+    pub fn has_oom_fd(&self, pid: i32) -> bool {
+        self.nodes.get(&pid).is_some_and(|n| n.oom_fd.is_some())
+    }
+
+    /// 为已有节点保存 oom_score_adj 的 fd，供之后的扫描复用
+    pub fn keep_oom_fd(&mut self, pid: i32, fd: OwnedFd) {
+        if let Some(node) = self.nodes.get_mut(&pid) {
+            node.oom_fd = Some(fd);
+        }
+    }
+
+    /// 发出 SIGTERM 后经过的扫描轮数，未发过时为 None
+    pub fn cycles_since_term(&self, pid: i32) -> Option<u64> {
+        let term_cycle = self.nodes.get(&pid)?.term_cycle?;
+        Some(self.cycle - term_cycle)
+    }
+
+    /// 进程退出或 exec 了新程序，节点作废
+    pub fn remove(&mut self, pid: i32) {
+        self.nodes.remove(&pid);
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &ProcessNode)> {
+        self.nodes.iter().map(|(&pid, node)| (pid, node))
+    }
+
+    /// 清除本轮未再出现的进程
+    pub fn end_cycle(&mut self) {
+        let cycle = self.cycle;
+        self.nodes.retain(|_, node| node.seen_cycle == cycle);
+    }
+
+    /// 保存到 table_state 文件，供重启后恢复；不带 force 时至多每 10 分钟写一次，返回这次是否写了
+    ///
+    /// 每行 `<pid> <start_time> <uid> <oom> <last_active> <background_since> <state> <name>`，
+    /// 时间为 boottime 秒，只在同一次开机内有效，所以文件头记下 boot_id
+    pub fn save(&mut self, path: &str, now: u64, force: bool) -> bool {
+        if !force && now.saturating_sub(self.last_save) < STATE_SAVE_INTERVAL_SECS {
+            return false;
+        }
+        self.last_save = now;
+        let mut out = format!("{}\nboot {}\n", STATE_HEADER, boot_id());
+        for (pid, n) in &self.nodes {
+            out.push_str(&format!(
+                "{} {} {} {} {} {} {} {}\n",
+                pid,
+                n.start_time,
+                n.uid,
+                n.oom,
+                n.last_active,
+                n.background_since,
+                n.state as char,
+                n.name
+            ));
+        }
+        // 先写临时文件再 rename，中途退出不会留下半截的状态
+        let tmp = format!("{}.tmp", path);
+        if fs::write(&tmp, out).is_err() || fs::rename(&tmp, path).is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        true
+    }
+
+    /// 从 table_state 恢复，返回恢复的节点数；换过开机、pid 已退出或被复用
+    /// （/proc/<pid>/stat 的 start_time 不同）的条目丢弃
+    pub fn restore(&mut self, path: &str, procfs: &dyn ProcSource) -> usize {
+        let Ok(content) = fs::read_to_string(path) else {
+            return 0;
+        };
+        let mut lines = content.lines();
+        if lines.next() != Some(STATE_HEADER)
+            || lines.next().and_then(|l| l.strip_prefix("boot ")) != Some(boot_id().as_str())
+        {
+            return 0;
+        }
+        let mut buf = Vec::new();
+        let mut restored = 0;
+        for line in lines {
+            let f: Vec<&str> = line.splitn(8, ' ').collect();
+            let [pid, start_time, uid, oom, last_active, background_since, state, name] = f[..]
+            else {
+                continue;
+            };
+            let (Ok(pid), Ok(start_time)) = (pid.parse::<i32>(), start_time.parse::<u64>()) else {
+                continue;
+            };
+            buf.clear();
+            let stat = procfs
+                .read(&format!("{}/stat", pid), &mut buf)
+                .ok()
+                .and_then(|()| ProcStat::parse(&buf));
+            let Some(stat) = stat.filter(|st| st.start_time == start_time) else {
+                continue;
+            };
+            let mut node = ProcessNode::new(&stat, 0, self.cycle);
+            node.uid = uid.parse().unwrap_or(0);
+            node.user = android_user(node.uid);
+            node.oom = oom.parse().unwrap_or(0);
+            node.last_active = last_active.parse().unwrap_or(0);
+            node.background_since = background_since.parse().unwrap_or(0);
+            node.state = state.bytes().next().unwrap_or(b'S');
+            node.set_name(name);
+            self.nodes.insert(pid, node);
+            restored += 1;
+        }
+        restored
+    }
+}
+
+/// 本次开机的唯一标识
+fn boot_id() -> String {
+    fs::read_to_string("/proc/sys/kernel/random/boot_id")
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(start_time: u64, cpu_ticks: u64) -> ProcStat {
+        ProcStat {
+            cpu_ticks,
+            start_time,
+            state: b'S',
+        }
+    }
+
+    #[test]
+    fn parses_stat_with_spaces_in_comm() {
+        let line = b"123 (my app) S 1 2 3 4 5 6 7 8 9 10 20 30 0 0 0 0 0 0 4500 0 0\n";
+        let st = ProcStat::parse(line).unwrap();
+        assert_eq!((st.cpu_ticks, st.start_time, st.state), (50, 4500, b'S'));
+        assert_eq!(st.started_at(), 45);
+    }
+
+    #[test]
+    fn first_cycle_uses_process_start_time() {
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        assert_eq!(table.observe(1, &stat(2000, 0), 100).background_since, 20);
+
+        // 之后出现的进程从被看到时开始计时
+        table.begin_cycle();
+        assert_eq!(table.observe(2, &stat(2000, 0), 150).background_since, 150);
+    }
+
+    #[test]
+    fn cpu_activity_updates_last_active() {
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        table.observe(1, &stat(100, 5), 10);
+        table.begin_cycle();
+        assert_eq!(table.observe(1, &stat(100, 5), 20).last_active, 10);
+        table.begin_cycle();
+        let node = table.observe(1, &stat(100, 9), 30);
+        assert_eq!((node.last_active, node.cpu_ticks), (30, 9));
+    }
+
+    #[test]
+    fn pid_reuse_resets_node() {
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        table.observe(1, &stat(100, 0), 10);
+        table.set_sample(1, 1010100, 900, "com.old:push", 1024);
+        assert_eq!(table.get(1).unwrap().user, 10);
+        table.mark_terminated(1);
+
+        table.begin_cycle();
+        let node = table.observe(1, &stat(500, 0), 20);
+        assert!(node.name.is_empty());
+        assert_eq!(node.background_since, 20);
+        assert_eq!(table.cycles_since_term(1), None);
+    }
+
+    #[test]
+    fn unseen_processes_are_dropped() {
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        table.observe(1, &stat(100, 0), 10);
+        table.observe(2, &stat(100, 0), 10);
+        table.end_cycle();
+
+        table.begin_cycle();
+        table.observe(2, &stat(100, 0), 20);
+        table.end_cycle();
+        assert!(table.get(1).is_none());
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn oom_fd_is_reused_until_node_resets() {
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        table.observe(1, &stat(100, 0), 10);
+        let mut buf = Vec::new();
+        assert!(!table.read_oom(1, &mut buf));
+
+        let fd = fs::File::open("/proc/self/oom_score_adj").unwrap().into();
+        table.keep_oom_fd(1, fd);
+        for _ in 0..2 {
+            buf.clear();
+            assert!(table.read_oom(1, &mut buf));
+            assert!(std::str::from_utf8(&buf)
+                .unwrap()
+                .trim()
+                .parse::<i32>()
+                .is_ok());
+        }
+
+        // PID 复用：新节点不继承旧进程的 fd
+        table.observe(1, &stat(200, 0), 20);
+        assert!(!table.has_oom_fd(1));
+    }
+
+    #[test]
+    fn counts_cycles_since_sigterm() {
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        table.observe(1, &stat(100, 0), 10);
+        table.mark_terminated(1);
+        assert_eq!(table.cycles_since_term(1), Some(0));
+        table.begin_cycle();
+        table.observe(1, &stat(100, 0), 20);
+        assert_eq!(table.cycles_since_term(1), Some(1));
+    }
+
+    #[test]
+    fn demoted_until_adj_is_reset() {
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        table.observe(1, &stat(100, 0), 10);
+        table.set_sample(1, 10100, 900, "com.foo:push", 1024);
+        assert!(!table.is_demoted(1));
+        table.mark_demoted(1, 999);
+        assert!(table.is_demoted(1));
+
+        // 系统重新分配了 adj，需要再写一次
+        table.set_sample(1, 10100, 905, "com.foo:push", 1024);
+        assert!(!table.is_demoted(1));
+    }
+
+    #[test]
+    fn state_survives_restart_unless_pid_reused() {
+        use crate::proc::fake::FakeProc;
+
+        let path = std::env::temp_dir().join(format!("mc_table_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut procfs = FakeProc::new();
+        procfs
+            .spawn(1, 10100, "com.a:push", 900)
+            .spawn(2, 10200, "com.b:push", 900);
+
+        let mut table = ProcessTable::default();
+        table.begin_cycle();
+        table.observe(1, &stat(1000, 0), 10);
+        table.observe(2, &stat(1000, 0), 10);
+        table.set_sample(1, 10100, 900, "com.a:push", 1024);
+        table.save(path, 50, true);
+
+        procfs.set_stat(2, 5000);
+        let mut restored = ProcessTable::default();
+        assert_eq!(restored.restore(path, &procfs), 1);
+        let node = restored.get(1).unwrap();
+        assert_eq!(
+            (node.name.as_str(), node.uid, node.oom),
+            ("com.a:push", 10100, 900)
+        );
+        assert_eq!(node.background_since, 10);
+        assert_eq!(
+            (node.package.as_str(), node.process_suffix.as_str()),
+            ("com.a", "push")
+        );
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn proc_events_maintain_pid_set_until_lost() {
+        use crate::proc::fake::FakeProc;
+
+        let mut procfs = FakeProc::new();
+        procfs.spawn(1, 10100, "com.a:push", 900);
+        let mut table = ProcessTable::default();
+        table.track_events();
+        // 第一轮完整扫描作为起点
+        assert_eq!(table.pids(&procfs), vec![1]);
+
+        // 之后只按事件增减，不再列出 /proc
+        procfs.spawn(2, 10200, "com.b:push", 900);
+        table.apply(ProcEvent::Fork(3));
+        table.apply(ProcEvent::Exit(1));
+        assert_eq!(table.pids(&procfs), vec![3]);
+
+        table.events_lost();
+        let mut pids = table.pids(&procfs);
+        pids.sort_unstable();
+        assert_eq!(pids, vec![1, 2]);
+    }
+}
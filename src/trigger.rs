@@ -0,0 +1,59 @@
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::os::fd::{AsFd, BorrowedFd};
+use std::path::{Path, PathBuf};
+
+/// 触发文件：任何进程 touch 该路径即请求立即清理一轮，供没有 socket 客户端的 shell 脚本使用
+///
+/// 监听所在目录而不是文件本身，文件不存在（已被我们删除）时也能收到创建事件
+pub struct TriggerFile {
+    inotify: Inotify,
+    path: PathBuf,
+    name: OsString,
+}
+
+impl TriggerFile {
+    pub fn watch(path: &str) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid path"));
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+        inotify.add_watch(
+            dir,
+            AddWatchFlags::IN_CREATE | AddWatchFlags::IN_CLOSE_WRITE | AddWatchFlags::IN_ATTRIB,
+        )?;
+        let name = name.to_os_string();
+        Ok(Self {
+            inotify,
+            path,
+            name,
+        })
+    }
+
+    /// 读出全部待处理事件；触发文件被 touch 过则删除它并返回 true
+    pub fn take(&self) -> bool {
+        let Ok(events) = self.inotify.read_events() else {
+            return false;
+        };
+        let touched = events
+            .iter()
+            .any(|e| e.name.as_deref() == Some(self.name.as_os_str()));
+        // 同一次 touch 会产生多个事件，以删除成功为准只触发一次
+        touched && fs::remove_file(&self.path).is_ok()
+    }
+}
+
+impl AsFd for TriggerFile {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inotify.as_fd()
+    }
+}
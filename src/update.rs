@@ -0,0 +1,52 @@
+//! 版本信息与更新检查：模块用户常常还在跑早已修复了问题的旧构建
+//!
+//! check-update 默认关闭，只有配置了 `check_update: on` 才会联网；通过 curl 请求发布接口，
+//! 返回内容中取第一个 "tag_name"（GitHub releases/latest 的格式）与本机版本比较。
+
+use std::process::Command;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_HASH: &str = env!("MEM_CLEANER_GIT_HASH");
+pub const BUILD_DATE: &str = env!("MEM_CLEANER_BUILD_DATE");
+pub const DEFAULT_UPDATE_URL: &str =
+    "https://api.github.com/repos/rogerwwx/mem-cleaner/releases/latest";
+const CURL_TIMEOUT_SECS: &str = "15";
+
+/// --version 的输出
+pub fn version_string() -> String {
+    format!("mem_cleaner {} ({} {})", VERSION, GIT_HASH, BUILD_DATE)
+}
+
+/// 查询发布接口，返回最新版本号；比本机新时 newer 为 true
+pub fn check(url: &str) -> Result<(String, bool), String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", CURL_TIMEOUT_SECS, url])
+        .output()
+        .map_err(|e| format!("failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("request to {} failed ({})", url, output.status));
+    }
+    let body = String::from_utf8_lossy(&output.stdout);
+    let latest = tag_name(&body).ok_or("no tag_name in response")?;
+    let newer = parse_version(latest) > parse_version(VERSION);
+    Ok((latest.to_string(), newer))
+}
+
+fn tag_name(body: &str) -> Option<&str> {
+    let rest = &body[body.find("\"tag_name\"")? + "\"tag_name\"".len()..];
+    let rest = rest
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start()
+        .strip_prefix('"')?;
+    Some(&rest[..rest.find('"')?])
+}
+
+/// "v1.2.3" → [1, 2, 3]；无法解析的部分记为 0
+fn parse_version(v: &str) -> Vec<u32> {
+    v.trim_start_matches('v')
+        .split(['.', '-'])
+        .take(3)
+        .map(|p| p.parse().unwrap_or(0))
+        .collect()
+}
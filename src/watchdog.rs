@@ -0,0 +1,237 @@
+//! 主循环看门狗：主线程卡在某次 /proc 读取（如存储栈故障时进程处于 D 状态）时，
+//! 定时器照常到期却再也没有清理完成，这里负责发现并大声报告
+//!
+//! 另外在主线程内隔离每次清理：panic 被接住后跳过本轮，连续出错或单轮耗时异常时
+//! 丢弃进程表从头再来，免得一份坏状态拖垮之后的每一轮
+
+use std::cell::Cell;
+use std::os::unix::process::CommandExt;
+use std::panic::{self, AssertUnwindSafe};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::errlog;
+use crate::logfile::Logger;
+use crate::monotonic_secs;
+
+/// 主循环最近一次完成定时任务的时刻（CLOCK_MONOTONIC 秒）
+///
+/// 用 monotonic 而不是 boottime：休眠期间主循环本来就不运行，不能算作卡死
+static HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+/// 当前清理周期，set interval 后随之更新
+static INTERVAL_SECS: AtomicU64 = AtomicU64::new(0);
+
+/// 单轮清理超过这么久视为异常（毫秒）
+pub const DEFAULT_MAX_CYCLE_MS: u64 = 10_000;
+/// 连续这么多轮 panic 后重置进程表
+const PANIC_LIMIT: u32 = 3;
+/// 重新执行前写入，新进程据此在日志里记一笔"已自动重启"
+const REEXEC_ENV: &str = "MEM_CLEANER_REEXEC";
+
+thread_local! {
+    static GUARDED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 主循环每处理完一次定时器事件调用一次
+pub fn beat() {
+    HEARTBEAT.store(monotonic_secs(), Ordering::Relaxed);
+}
+
+pub fn set_interval(secs: u64) {
+    INTERVAL_SECS.store(secs, Ordering::Relaxed);
+}
+
+/// 当前设定的清理周期（秒）
+pub fn interval() -> u64 {
+    INTERVAL_SECS.load(Ordering::Relaxed)
+}
+
+/// 启动看门狗线程：连续 intervals 个周期没有心跳即写日志，reexec 时用原参数重新执行自身
+///
+/// 主线程若处于不可中断睡眠，exec 会一直等它退出，此时只能靠日志提醒
+pub fn spawn(
+    interval: u64,
+    intervals: u64,
+    reexec: bool,
+    mut logger: Option<Logger>,
+) -> std::io::Result<()> {
+    set_interval(interval);
+    beat();
+    thread::Builder::new()
+        .name("watchdog".to_string())
+        .spawn(move || {
+            let mut reported = false;
+            loop {
+                let stall_secs = INTERVAL_SECS.load(Ordering::Relaxed) * intervals;
+                thread::sleep(Duration::from_secs((stall_secs / 4).max(1)));
+                let stalled = monotonic_secs().saturating_sub(HEARTBEAT.load(Ordering::Relaxed));
+                if stalled < stall_secs {
+                    reported = false;
+                    continue;
+                }
+                if reported {
+                    continue;
+                }
+                reported = true;
+                errlog::error(
+                    "watchdog",
+                    &format!(
+                        "WATCHDOG: main loop stalled for {}s{}",
+                        stalled,
+                        if reexec { ", re-executing" } else { "" }
+                    ),
+                );
+                if let Some(l) = &mut logger {
+                    l.write_watchdog(stalled, reexec);
+                }
+                if reexec {
+                    let mut args = std::env::args_os();
+                    let err = Command::new("/proc/self/exe")
+                        .arg0(args.next().unwrap_or_default())
+                        .args(args)
+                        .env(REEXEC_ENV, stalled.to_string())
+                        .exec();
+                    errlog::error("watchdog", &format!("WATCHDOG: re-exec failed: {}", err));
+                }
+            }
+        })
+        .map(|_| ())
+}
+
+/// 是否由看门狗重新执行而来；返回当时卡住的秒数，并清掉标记免得再传给子进程
+pub fn take_reexec() -> Option<u64> {
+    let stalled = std::env::var(REEXEC_ENV).ok()?;
+    std::env::remove_var(REEXEC_ENV);
+    Some(stalled.parse().unwrap_or(0))
+}
+
+/// 当前线程是否正处在 isolate 内：panic hook 据此区分会被接住的 panic
+pub fn guarded() -> bool {
+    GUARDED.with(Cell::get)
+}
+
+/// 隔离执行一轮清理，panic 时返回其消息
+///
+/// 依赖 panic = "unwind"；编成 abort 时 panic 仍会直接终止进程
+pub fn isolate<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    GUARDED.with(|g| g.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    GUARDED.with(|g| g.set(false));
+    result.map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    })
+}
+
+/// 一轮清理出的问题
+#[derive(Debug, Clone, PartialEq)]
+pub enum CycleFault {
+    Panic { message: String, consecutive: u32 },
+    Slow { millis: u64 },
+}
+
+/// 需要记下的恢复动作；reset_table 为真时调用方应丢弃进程表
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovery {
+    pub fault: CycleFault,
+    pub reset_table: bool,
+}
+
+/// 跨周期记录清理是否出错
+#[derive(Debug, Default)]
+pub struct CycleGuard {
+    consecutive_panics: u32,
+    pub resets: u64, // 本次运行累计重置进程表的次数
+}
+
+impl CycleGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记下本轮结果；panic 为 isolate 返回的消息，max_ms 为 0 表示不检查耗时
+    pub fn record(
+        &mut self,
+        panic: Option<&str>,
+        elapsed_ms: u64,
+        max_ms: u64,
+    ) -> Option<Recovery> {
+        let recovery = if let Some(message) = panic {
+            self.consecutive_panics += 1;
+            let consecutive = self.consecutive_panics;
+            let reset_table = consecutive >= PANIC_LIMIT;
+            if reset_table {
+                self.consecutive_panics = 0;
+            }
+            Recovery {
+                fault: CycleFault::Panic {
+                    message: message.to_string(),
+                    consecutive,
+                },
+                reset_table,
+            }
+        } else {
+            self.consecutive_panics = 0;
+            if max_ms == 0 || elapsed_ms <= max_ms {
+                return None;
+            }
+            // 进程表异常膨胀或残留了坏节点时每轮都会变慢，重建一次成本很低
+            Recovery {
+                fault: CycleFault::Slow { millis: elapsed_ms },
+                reset_table: true,
+            }
+        };
+        if recovery.reset_table {
+            self.resets += 1;
+        }
+        Some(recovery)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isolate_catches_panics_and_reports_message() {
+        assert_eq!(isolate(|| 7), Ok(7));
+        let caught = isolate(|| -> u32 { panic!("bad node {}", 42) });
+        assert_eq!(caught, Err("bad node 42".to_string()));
+        assert!(!guarded());
+    }
+
+    #[test]
+    fn repeated_panics_or_slow_cycles_reset_table() {
+        let mut guard = CycleGuard::new();
+        assert_eq!(guard.record(None, 50, 1000), None);
+        for n in 1..PANIC_LIMIT {
+            let r = guard.record(Some("boom"), 5, 1000).unwrap();
+            assert!(!r.reset_table, "panic #{}", n);
+        }
+        let r = guard.record(Some("boom"), 5, 1000).unwrap();
+        assert!(r.reset_table);
+        assert_eq!(
+            r.fault,
+            CycleFault::Panic {
+                message: "boom".to_string(),
+                consecutive: PANIC_LIMIT
+            }
+        );
+
+        // 中间一轮正常即重新计数
+        guard.record(Some("boom"), 5, 1000);
+        guard.record(None, 5, 1000);
+        assert!(!guard.record(Some("boom"), 5, 1000).unwrap().reset_table);
+
+        let r = guard.record(None, 2500, 1000).unwrap();
+        assert_eq!(r.fault, CycleFault::Slow { millis: 2500 });
+        assert!(r.reset_table);
+        assert_eq!(guard.record(None, 2500, 0), None);
+        assert_eq!(guard.resets, 2);
+    }
+}
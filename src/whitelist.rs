@@ -0,0 +1,152 @@
+//! 白名单命中统计：长期从未命中的条目多半是拼错的包名或早已卸载的应用
+//!
+//! 统计跨越重启，时间一律用 Unix 秒（boottime 每次开机从零开始）。配置了 whitelist_stats 时保存为：
+//!
+//! ```text
+//! # mem_cleaner whitelist 1
+//! since <开始统计> <上次报告>
+//! <规则> <命中次数> <最近命中>
+//! ```
+
+use fxhash::FxHashMap;
+
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::WhitelistRule;
+
+const HEADER: &str = "# mem_cleaner whitelist 1";
+const SAVE_INTERVAL_SECS: u64 = 3600;
+
+/// Unix 秒
+pub fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 单条规则的命中情况
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuleHits {
+    pub count: u64,
+    pub last_hit: u64, // Unix 秒
+}
+
+/// 各白名单规则被进程命中的次数
+pub struct WhitelistHits {
+    rules: FxHashMap<WhitelistRule, RuleHits>,
+    since: u64,       // 开始统计的时刻
+    last_report: u64, // 上次输出未命中报告的时刻
+    last_save: u64,
+    dirty: bool,
+}
+
+impl WhitelistHits {
+    /// 从统计文件恢复，文件不存在或格式不对时从现在开始统计
+    pub fn load(path: Option<&str>, now: u64) -> Self {
+        let mut hits = Self {
+            rules: FxHashMap::default(),
+            since: now,
+            last_report: now,
+            last_save: now,
+            dirty: false,
+        };
+        let Some(content) = path.and_then(|p| fs::read_to_string(p).ok()) else {
+            return hits;
+        };
+        let mut lines = content.lines();
+        if lines.next() != Some(HEADER) {
+            return hits;
+        }
+        for line in lines {
+            // 正则规则可能含空格，从右边切出两个数字
+            let mut parts = line.rsplitn(3, ' ');
+            let (last, count, rule) = (parts.next(), parts.next(), parts.next());
+            match (rule, count, last) {
+                (Some("since"), Some(since), Some(report)) => {
+                    hits.since = since.parse().unwrap_or(now).min(now);
+                    hits.last_report = report.parse().unwrap_or(now).min(now);
+                }
+                (Some(rule), Some(count), Some(last)) => {
+                    let Some(rule) = WhitelistRule::parse(rule) else {
+                        continue;
+                    };
+                    hits.rules.insert(
+                        rule,
+                        RuleHits {
+                            count: count.parse().unwrap_or(0),
+                            last_hit: last.parse().unwrap_or(0),
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+        hits
+    }
+
+    pub fn hit(&mut self, rule: &WhitelistRule, now: u64) {
+        match self.rules.get_mut(rule) {
+            Some(h) => {
+                h.count += 1;
+                h.last_hit = now;
+            }
+            None => {
+                self.rules.insert(
+                    rule.clone(),
+                    RuleHits {
+                        count: 1,
+                        last_hit: now,
+                    },
+                );
+            }
+        }
+        self.dirty = true;
+    }
+
+    pub fn get(&self, rule: &WhitelistRule) -> RuleHits {
+        self.rules.get(rule).copied().unwrap_or_default()
+    }
+
+    pub fn since(&self) -> u64 {
+        self.since
+    }
+
+    /// 距上次报告满 period 秒且统计时长足够时，返回期间从未命中的规则（按名字排序）
+    pub fn unused_report<'a>(
+        &mut self,
+        rules: impl Iterator<Item = &'a WhitelistRule>,
+        period: u64,
+        now: u64,
+    ) -> Option<Vec<&'a WhitelistRule>> {
+        if period == 0
+            || now.saturating_sub(self.last_report) < period
+            || now.saturating_sub(self.since) < period
+        {
+            return None;
+        }
+        self.last_report = now;
+        self.dirty = true;
+        let mut unused: Vec<&WhitelistRule> = rules
+            .filter(|r| now.saturating_sub(self.get(r).last_hit) >= period)
+            .collect();
+        unused.sort_by_key(|r| r.to_string());
+        Some(unused)
+    }
+
+    /// 有变化时最多每小时写一次统计文件；force 用于退出前
+    pub fn save(&mut self, path: &str, now: u64, force: bool) {
+        if !self.dirty || (!force && now.saturating_sub(self.last_save) < SAVE_INTERVAL_SECS) {
+            return;
+        }
+        let mut out = format!("{}\nsince {} {}\n", HEADER, self.since, self.last_report);
+        for (rule, h) in &self.rules {
+            out.push_str(&format!("{} {} {}\n", rule, h.count, h.last_hit));
+        }
+        if fs::write(path, out).is_ok() {
+            self.dirty = false;
+        }
+        self.last_save = now;
+    }
+}
@@ -0,0 +1,99 @@
+use fxhash::FxHashSet;
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const WIDGET_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// 当前桌面上有小部件的 App：杀掉后桌面会显示空白或"无法加载小部件"，直到下次更新
+#[derive(Default)]
+pub struct WidgetProviders {
+    packages: FxHashSet<String>,
+    last_refresh: Option<Instant>,
+}
+
+impl WidgetProviders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&mut self, package: &str) -> bool {
+        if self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= WIDGET_CACHE_TTL)
+        {
+            self.refresh();
+        }
+        self.packages.contains(package)
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        let Ok(output) = Command::new("dumpsys").arg("appwidget").output() else {
+            return;
+        };
+        self.packages = parse_appwidget(
+            &String::from_utf8_lossy(&output.stdout),
+            current_launcher().as_deref(),
+        );
+    }
+}
+
+/// 解析 `dumpsys appwidget` 的 Widgets 段：
+///
+/// ```text
+///   [0] id=12
+///     host=HostId{user:0, app:10123, hostId:1024, pkg:com.android.launcher3}
+///     provider=ProviderId{user:0, app:10089, cid:ComponentInfo{com.foo.weather/com.foo.weather.Widget}}
+/// ```
+///
+/// 知道当前桌面时只统计宿主为桌面的小部件
+fn parse_appwidget(text: &str, launcher: Option<&str>) -> FxHashSet<String> {
+    let mut packages = FxHashSet::default();
+    let mut host_matches = launcher.is_none();
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(host) = line.strip_prefix("host=") {
+            host_matches = launcher.is_none_or(|l| field(host, "pkg:") == Some(l));
+        } else if let Some(provider) = line.strip_prefix("provider=") {
+            if host_matches {
+                if let Some((pkg, _)) =
+                    field(provider, "ComponentInfo{").and_then(|c| c.split_once('/'))
+                {
+                    packages.insert(pkg.to_string());
+                }
+            }
+        }
+    }
+    packages
+}
+
+/// 取 key 之后到下一个 ',' 或 '}' 之前的内容
+fn field<'a>(s: &'a str, key: &str) -> Option<&'a str> {
+    let start = s.find(key)? + key.len();
+    let rest = &s[start..];
+    let end = rest.find([',', '}']).unwrap_or(rest.len());
+    Some(rest[..end].trim())
+}
+
+/// 当前默认桌面包名
+pub(crate) fn current_launcher() -> Option<String> {
+    let output = Command::new("cmd")
+        .args([
+            "package",
+            "resolve-activity",
+            "--brief",
+            "-a",
+            "android.intent.action.MAIN",
+            "-c",
+            "android.intent.category.HOME",
+        ])
+        .output()
+        .ok()?;
+    // 最后一行形如 "com.android.launcher3/.Launcher"
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .last()?
+        .split_once('/')
+        .map(|(pkg, _)| pkg.trim().to_string())
+}